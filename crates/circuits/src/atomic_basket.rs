@@ -0,0 +1,218 @@
+//! AtomicBasket Circuit for SMT-based inventory.
+//!
+//! Proves that a whole basket of withdrawals was affordable: every item held at
+//! least the amount withdrawn, and the inventory moved by exactly the basket.
+//! There is no partial outcome; if any single item is short, no valid proof
+//! exists for the basket.
+//!
+//! Each withdrawal is an [`ItemTransfer`] out of the inventory, applied to the
+//! SMT root in order like the source side of a `MultiTransferCircuit`. The new
+//! quantity `old_quantity - amount` is range checked, which enforces
+//! `old_quantity >= amount` per item.
+//!
+//! The basket size is fixed when the circuit is built (see
+//! [`AtomicBasketCircuit::empty`]) and is at most [`MAX_BASKET_ITEMS`].
+//!
+//! Public input: Poseidon(old_commitment, new_commitment,
+//!                        item_id_0, amount_0, item_volume_0, ...)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::multi_transfer::{ItemTransfer, TransferSide, TransferSideVar};
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::smt::SparseMerkleTree;
+
+/// Most withdrawals a single basket can carry.
+pub const MAX_BASKET_ITEMS: usize = 8;
+
+/// Compute the public input hash for AtomicBasket proof.
+pub fn compute_basket_hash(old_commitment: Fr, new_commitment: Fr, basket: &[ItemTransfer]) -> Fr {
+    let mut inputs = vec![old_commitment, new_commitment];
+    for item in basket {
+        inputs.push(Fr::from(item.item_id));
+        inputs.push(Fr::from(item.amount));
+        inputs.push(Fr::from(item.item_volume));
+    }
+    poseidon_hash_many(&inputs)
+}
+
+/// AtomicBasket Circuit.
+#[derive(Clone)]
+pub struct AtomicBasketCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    /// Withdrawals in the basket, in order
+    pub basket: Option<Vec<ItemTransfer>>,
+
+    /// Inventory witnesses
+    pub inventory: Option<TransferSide>,
+}
+
+impl AtomicBasketCircuit {
+    /// Create an empty circuit for setup with room for `num_items` withdrawals.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty(num_items: usize) -> Self {
+        assert!(num_items <= MAX_BASKET_ITEMS, "at most {} basket items", MAX_BASKET_ITEMS);
+        let dummy_item = ItemTransfer {
+            item_id: 0,
+            amount: 0,
+            item_volume: 0,
+        };
+
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            basket: Some(vec![dummy_item; num_items]),
+            inventory: Some(TransferSide::empty(num_items)),
+        }
+    }
+
+    /// Withdraw every item in `basket` from `tree` and record the witnesses.
+    ///
+    /// `tree` is updated in place so it reflects the new state afterwards.
+    /// Panics if the inventory holds too few of any item.
+    pub fn new(
+        basket: Vec<ItemTransfer>,
+        tree: &mut SparseMerkleTree,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+    ) -> Self {
+        assert!(basket.len() <= MAX_BASKET_ITEMS, "at most {} basket items", MAX_BASKET_ITEMS);
+
+        let inventory = TransferSide::debit(tree, old_volume, old_blinding, new_blinding, &basket);
+        let public_hash = compute_basket_hash(
+            inventory.old_state.commitment(),
+            inventory.new_state.commitment(),
+            &basket,
+        );
+
+        Self {
+            public_hash: Some(public_hash),
+            basket: Some(basket),
+            inventory: Some(inventory),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for AtomicBasketCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate inventory witnesses ===
+        let basket = self.basket.as_ref().unwrap();
+        let inventory = self.inventory.as_ref().unwrap();
+        assert!(basket.len() <= MAX_BASKET_ITEMS, "at most {} basket items", MAX_BASKET_ITEMS);
+        assert_eq!(inventory.old_quantities.len(), basket.len(), "one quantity per item");
+        assert_eq!(inventory.proofs.len(), basket.len(), "one proof per item");
+
+        let mut inventory_var = TransferSideVar::new_witness(cs.clone(), inventory)?;
+
+        // === Constraint 1: Every item covers its withdrawal ===
+        let mut hash_inputs = Vec::with_capacity(3 * basket.len());
+        for (i, item) in basket.iter().enumerate() {
+            let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(item.item_id)))?;
+            let amount_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(item.amount)))?;
+            let item_volume_var =
+                FpVar::new_witness(cs.clone(), || Ok(Fr::from(item.item_volume)))?;
+
+            inventory_var.apply(
+                cs.clone(),
+                &item_id_var,
+                &amount_var,
+                &item_volume_var,
+                inventory.old_quantities[i],
+                &inventory.proofs[i],
+                false,
+            )?;
+
+            hash_inputs.extend([item_id_var, amount_var, item_volume_var]);
+        }
+
+        // === Constraint 2: No other item changed, volume matches ===
+        let (old_commitment, new_commitment) = inventory_var.finish(cs.clone())?;
+
+        // === Constraint 3: Compute and verify public hash ===
+        let mut inputs = vec![old_commitment, new_commitment];
+        inputs.extend(hash_inputs);
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::DEFAULT_DEPTH;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// 30 of item 1 (volume 2), 5 of item 2, all 4 of item 3 (volume 10).
+    fn basket() -> Vec<ItemTransfer> {
+        vec![
+            ItemTransfer { item_id: 1, amount: 30, item_volume: 2 },
+            ItemTransfer { item_id: 2, amount: 5, item_volume: 1 },
+            ItemTransfer { item_id: 3, amount: 4, item_volume: 10 },
+        ]
+    }
+
+    fn atomic_basket() -> AtomicBasketCircuit {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100), (2, 5), (3, 4)], DEFAULT_DEPTH);
+        AtomicBasketCircuit::new(basket(), &mut tree, 245, Fr::from(1u64), Fr::from(2u64))
+    }
+
+    fn is_satisfied(circuit: AtomicBasketCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    /// Recompute the public hash after tampering with the witnesses.
+    fn rebind(circuit: &mut AtomicBasketCircuit) {
+        let inventory = circuit.inventory.as_ref().unwrap();
+        circuit.public_hash = Some(compute_basket_hash(
+            inventory.old_state.commitment(),
+            inventory.new_state.commitment(),
+            circuit.basket.as_ref().unwrap(),
+        ));
+    }
+
+    #[test]
+    fn test_affordable_basket() {
+        let circuit = atomic_basket();
+        assert_eq!(circuit.inventory.as_ref().unwrap().new_state.current_volume, 140);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("AtomicBasket (3 items) constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_single_short_item_fails() {
+        // Item 2 holds 5, but the basket now asks for 6; the other items are fine
+        let mut circuit = atomic_basket();
+        let basket = circuit.basket.as_mut().unwrap();
+        basket[1].amount = 6;
+        circuit.inventory.as_mut().unwrap().new_state.current_volume -= 1;
+        rebind(&mut circuit);
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    #[should_panic(expected = "quantity underflow")]
+    fn test_short_basket_cannot_be_built() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100), (2, 4), (3, 4)], DEFAULT_DEPTH);
+        AtomicBasketCircuit::new(basket(), &mut tree, 244, Fr::from(1u64), Fr::from(2u64));
+    }
+}
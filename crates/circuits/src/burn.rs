@@ -0,0 +1,286 @@
+//! Burn Circuit for SMT-based inventory.
+//!
+//! Proves that `amount` of `item_id` was destroyed: the inventory loses the
+//! items (with the same underflow check as a withdrawal) and the committed volume
+//! drops by `amount * item_volume`. Unlike a withdrawal, the items go nowhere.
+//!
+//! To make a burn recordable, the circuit also exposes a burn nullifier,
+//! `Poseidon(BURN_NULLIFIER_DOMAIN, item_id, amount, old_commitment)`. A verifier
+//! contract stores the nullifiers it has seen and rejects a proof whose
+//! nullifier is already known, so the same burn cannot be claimed twice. The
+//! domain tag keeps burn nullifiers apart from gift nullifiers kept in the same
+//! set.
+//!
+//! Public inputs:
+//! - public_hash: Poseidon(old_commitment, new_commitment, item_id, amount, item_volume)
+//! - burn_nullifier
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::inventory_update::{InventoryUpdate, InventoryUpdateVar};
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::enforce_u32_range;
+use crate::smt::SparseMerkleTree;
+
+/// Domain tag for burn nullifiers ("BURNNULL" in ASCII).
+pub const BURN_NULLIFIER_DOMAIN: u64 = 0x4255524e4e554c4c;
+
+/// Compute the public input hash for Burn proof.
+pub fn compute_burn_hash(
+    old_commitment: Fr,
+    new_commitment: Fr,
+    item_id: u64,
+    amount: u64,
+    item_volume: u64,
+) -> Fr {
+    let inputs = vec![
+        old_commitment,
+        new_commitment,
+        Fr::from(item_id),
+        Fr::from(amount),
+        Fr::from(item_volume),
+    ];
+    poseidon_hash_many(&inputs)
+}
+
+/// Compute the nullifier identifying a burn.
+pub fn compute_burn_nullifier(item_id: u64, amount: u64, old_commitment: Fr) -> Fr {
+    poseidon_hash_many(&[
+        Fr::from(BURN_NULLIFIER_DOMAIN),
+        Fr::from(item_id),
+        Fr::from(amount),
+        old_commitment,
+    ])
+}
+
+/// Burn Circuit.
+#[derive(Clone)]
+pub struct BurnCircuit {
+    // Public inputs
+    /// Hash binding both commitments, item_id, amount and item_volume
+    pub public_hash: Option<Fr>,
+    /// Poseidon(BURN_NULLIFIER_DOMAIN, item_id, amount, old_commitment)
+    pub burn_nullifier: Option<Fr>,
+
+    // Public data (bound via hash)
+    /// Item ID being burned
+    pub item_id: Option<u64>,
+    /// Quantity burned
+    pub amount: Option<u64>,
+    /// Volume per unit of this item type
+    pub item_volume: Option<u64>,
+
+    /// The inventory update
+    pub update: Option<InventoryUpdate>,
+}
+
+impl BurnCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            burn_nullifier: Some(Fr::from(0u64)),
+            item_id: Some(0),
+            amount: Some(0),
+            item_volume: Some(0),
+            update: Some(InventoryUpdate::empty()),
+        }
+    }
+
+    /// Burn `amount` of `item_id` from `tree`.
+    ///
+    /// `tree` is updated in place so it reflects the new state afterwards.
+    /// Panics if the inventory holds fewer than `amount` items or too little volume.
+    pub fn new(
+        tree: &mut SparseMerkleTree,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+        item_id: u64,
+        amount: u64,
+        item_volume: u64,
+    ) -> Self {
+        let new_quantity = tree
+            .get(item_id)
+            .checked_sub(amount)
+            .expect("Burn would cause quantity underflow");
+        // A burned volume too large for a u64 is more than any inventory holds
+        let new_volume = amount
+            .checked_mul(item_volume)
+            .and_then(|burned| old_volume.checked_sub(burned))
+            .expect("Burn would cause volume underflow");
+
+        let update = InventoryUpdate::apply(
+            tree,
+            old_volume,
+            old_blinding,
+            item_id,
+            new_quantity,
+            new_volume,
+            new_blinding,
+        );
+
+        let old_commitment = update.old_state.commitment();
+        let public_hash = compute_burn_hash(
+            old_commitment,
+            update.new_state.commitment(),
+            item_id,
+            amount,
+            item_volume,
+        );
+
+        Self {
+            public_hash: Some(public_hash),
+            burn_nullifier: Some(compute_burn_nullifier(item_id, amount, old_commitment)),
+            item_id: Some(item_id),
+            amount: Some(amount),
+            item_volume: Some(item_volume),
+            update: Some(update),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for BurnCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public inputs ===
+        // Order: public_hash, burn_nullifier
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let nullifier_var = FpVar::new_input(cs.clone(), || {
+            self.burn_nullifier.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate public data (bound via hash) ===
+        let item_id_var = FpVar::new_witness(cs.clone(), || {
+            self.item_id
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let amount_var = FpVar::new_witness(cs.clone(), || {
+            self.amount
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let item_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.item_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Constraint 1: Only the item leaf changes ===
+        let update = self.update.as_ref().unwrap();
+        let update = InventoryUpdateVar::new_witness(cs.clone(), &item_id_var, update)?;
+
+        // === Constraint 2: new_quantity = old_quantity - amount, without underflow ===
+        update
+            .new_quantity
+            .enforce_equal(&(&update.old_quantity - &amount_var))?;
+        enforce_u32_range(cs.clone(), &update.new_quantity)?;
+
+        // === Constraint 3: Volume drops by amount * item_volume ===
+        let expected_volume =
+            &update.old_state.current_volume - &(&amount_var * &item_volume_var);
+        update.new_state.current_volume.enforce_equal(&expected_volume)?;
+        enforce_u32_range(cs.clone(), &update.new_state.current_volume)?;
+
+        // === Constraint 4: Burn nullifier ===
+        let nullifier = poseidon_hash_many_var(
+            cs.clone(),
+            &[
+                FpVar::Constant(Fr::from(BURN_NULLIFIER_DOMAIN)),
+                item_id_var.clone(),
+                amount_var.clone(),
+                update.old_commitment.clone(),
+            ],
+        )?;
+        nullifier.enforce_equal(&nullifier_var)?;
+
+        // === Constraint 5: Compute and verify public hash ===
+        let inputs = vec![
+            update.old_commitment,
+            update.new_commitment,
+            item_id_var,
+            amount_var,
+            item_volume_var,
+        ];
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::DEFAULT_DEPTH;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Burn 30 of item 1 (volume 10) from an inventory holding 100.
+    fn burn() -> BurnCircuit {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100), (2, 5)], DEFAULT_DEPTH);
+        BurnCircuit::new(&mut tree, 1005, Fr::from(1u64), Fr::from(2u64), 1, 30, 10)
+    }
+
+    fn is_satisfied(circuit: BurnCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_burn() {
+        let circuit = burn();
+        assert_eq!(circuit.update.as_ref().unwrap().new_quantity, 70);
+        assert_eq!(circuit.update.as_ref().unwrap().new_state.current_volume, 705);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("Burn constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_burn_more_than_held_fails() {
+        // The inventory holds 10; claim a burn of 30 that emptied it
+        let mut tree = SparseMerkleTree::from_items(&[(1, 10)], DEFAULT_DEPTH);
+        let mut circuit =
+            BurnCircuit::new(&mut tree, 100, Fr::from(1u64), Fr::from(2u64), 1, 10, 10);
+
+        let update = circuit.update.as_ref().unwrap();
+        let old_commitment = update.old_state.commitment();
+        let new_commitment = update.new_state.commitment();
+        circuit.amount = Some(30);
+        circuit.public_hash = Some(compute_burn_hash(old_commitment, new_commitment, 1, 30, 10));
+        circuit.burn_nullifier = Some(compute_burn_nullifier(1, 30, old_commitment));
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_wrong_nullifier_fails() {
+        let mut circuit = burn();
+        let old_commitment = circuit.update.as_ref().unwrap().old_state.commitment();
+
+        // Nullifier for a different amount, so it would not match the recorded burn
+        circuit.burn_nullifier = Some(compute_burn_nullifier(1, 29, old_commitment));
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    #[should_panic(expected = "Burn would cause volume underflow")]
+    fn test_burn_volume_overflow_panics() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 2)], DEFAULT_DEPTH);
+        let item_volume = u64::MAX / 2 + 1;
+
+        BurnCircuit::new(&mut tree, u64::MAX, Fr::from(1u64), Fr::from(2u64), 1, 2, item_volume);
+    }
+}
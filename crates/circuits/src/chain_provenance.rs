@@ -0,0 +1,406 @@
+//! ChainProvenance Circuit for SMT-based inventory.
+//!
+//! Proves that a current commitment derives from a public genesis commitment
+//! through a chain of valid deposits and withdrawals, without revealing the
+//! items, amounts or intermediate states.
+//!
+//! Each link is a single-item update (see [`crate::inventory_update`]) whose
+//! old commitment is the previous link's new commitment; the first link starts
+//! at the genesis and the last ends at the current commitment. Item volumes are
+//! looked up in a public volume registry (an SMT mapping item_id -> item_volume),
+//! so the committed volume moves by `amount * item_volume` at every step.
+//!
+//! The chain hash folds in every intermediate commitment:
+//! `chain_0 = genesis`, `chain_i = Poseidon(chain_{i-1}, new_commitment_i)`.
+//! A verifier that records each commitment as it is published recomputes it with
+//! [`compute_chain_hash`], tying the proof to that exact history.
+//!
+//! The number of links is fixed when the circuit is built
+//! (see [`ChainProvenanceCircuit::empty`]).
+//!
+//! Public input: Poseidon(genesis_commitment, current_commitment, chain_hash, registry_root)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::inventory_update::{InventoryUpdate, InventoryUpdateVar};
+use crate::poseidon::{
+    poseidon_hash_many, poseidon_hash_many_var, poseidon_hash_two, poseidon_hash_two_var,
+};
+use crate::range_check::enforce_u32_range;
+use crate::signal::OpType;
+use crate::smt::{verify_membership, MerkleProof, MerkleProofVar, SparseMerkleTree, DEFAULT_DEPTH};
+
+/// Compute the public input hash for ChainProvenance proof.
+pub fn compute_chain_provenance_hash(
+    genesis_commitment: Fr,
+    current_commitment: Fr,
+    chain_hash: Fr,
+    registry_root: Fr,
+) -> Fr {
+    poseidon_hash_many(&[genesis_commitment, current_commitment, chain_hash, registry_root])
+}
+
+/// Fold each commitment after the genesis into the chain hash, in order.
+pub fn compute_chain_hash(genesis_commitment: Fr, commitments: &[Fr]) -> Fr {
+    commitments
+        .iter()
+        .fold(genesis_commitment, |chain, &commitment| poseidon_hash_two(chain, commitment))
+}
+
+/// One deposit or withdrawal to apply when building a chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainStep {
+    /// Item deposited or withdrawn
+    pub item_id: u64,
+    /// Quantity moved
+    pub amount: u64,
+    /// Deposit or withdraw
+    pub op_type: OpType,
+    /// Blinding factor of the resulting commitment
+    pub new_blinding: Fr,
+}
+
+/// Witnesses for one link of the chain.
+#[derive(Clone, Debug)]
+pub struct ChainLink {
+    /// Item deposited or withdrawn
+    pub item_id: u64,
+    /// Quantity moved
+    pub amount: u64,
+    /// Deposit or withdraw
+    pub op_type: OpType,
+    /// Volume per unit, from the registry
+    pub item_volume: u64,
+    /// Proof of (item_id, item_volume) in the registry
+    pub registry_proof: MerkleProof<Fr>,
+    /// The inventory update itself
+    pub update: InventoryUpdate,
+}
+
+impl ChainLink {
+    /// Dummy link with the correct proof depths, for circuit setup.
+    pub fn empty() -> Self {
+        Self {
+            item_id: 0,
+            amount: 0,
+            op_type: OpType::Deposit,
+            item_volume: 0,
+            registry_proof: MerkleProof::new(
+                vec![Fr::from(0u64); DEFAULT_DEPTH],
+                vec![false; DEFAULT_DEPTH],
+            ),
+            update: InventoryUpdate::empty(),
+        }
+    }
+}
+
+/// ChainProvenance Circuit.
+#[derive(Clone)]
+pub struct ChainProvenanceCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Public data (bound via hash)
+    /// Commitment the chain starts from
+    pub genesis_commitment: Option<Fr>,
+    /// Commitment the chain ends at
+    pub current_commitment: Option<Fr>,
+    /// Hash over every commitment in the chain
+    pub chain_hash: Option<Fr>,
+    /// Root of the volume registry (item_id -> item_volume)
+    pub registry_root: Option<Fr>,
+
+    /// Links of the chain, oldest first
+    pub links: Option<Vec<ChainLink>>,
+}
+
+impl ChainProvenanceCircuit {
+    /// Create an empty circuit for setup with room for `num_links` transitions.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty(num_links: usize) -> Self {
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            genesis_commitment: Some(Fr::from(0u64)),
+            current_commitment: Some(Fr::from(0u64)),
+            chain_hash: Some(Fr::from(0u64)),
+            registry_root: Some(Fr::from(0u64)),
+            links: Some(vec![ChainLink::empty(); num_links]),
+        }
+    }
+
+    /// Apply `steps` to `tree`, starting from the genesis volume and blinding.
+    ///
+    /// `tree` is updated in place so it reflects the current state afterwards.
+    /// Panics if a withdrawal exceeds the held quantity or volume, or if an item
+    /// is missing from the registry.
+    pub fn new(
+        tree: &mut SparseMerkleTree,
+        genesis_volume: u64,
+        genesis_blinding: Fr,
+        steps: &[ChainStep],
+        registry: &SparseMerkleTree,
+    ) -> Self {
+        assert!(!steps.is_empty(), "A chain needs at least one link");
+
+        let mut volume = genesis_volume;
+        let mut blinding = genesis_blinding;
+        let mut links = Vec::with_capacity(steps.len());
+        for step in steps {
+            let item_volume = registry.get(step.item_id);
+            assert!(item_volume > 0, "Item is not in the volume registry");
+
+            let old_quantity = tree.get(step.item_id);
+            let volume_delta = step.amount * item_volume;
+            let (new_quantity, new_volume) = match step.op_type {
+                OpType::Deposit => (old_quantity + step.amount, volume + volume_delta),
+                OpType::Withdraw => (
+                    old_quantity
+                        .checked_sub(step.amount)
+                        .expect("Withdrawal would cause quantity underflow"),
+                    volume
+                        .checked_sub(volume_delta)
+                        .expect("Withdrawal would cause volume underflow"),
+                ),
+            };
+
+            let update = InventoryUpdate::apply(
+                tree,
+                volume,
+                blinding,
+                step.item_id,
+                new_quantity,
+                new_volume,
+                step.new_blinding,
+            );
+            links.push(ChainLink {
+                item_id: step.item_id,
+                amount: step.amount,
+                op_type: step.op_type,
+                item_volume,
+                registry_proof: registry.get_proof(step.item_id),
+                update,
+            });
+
+            volume = new_volume;
+            blinding = step.new_blinding;
+        }
+
+        let genesis_commitment = links[0].update.old_state.commitment();
+        let commitments: Vec<Fr> =
+            links.iter().map(|link| link.update.new_state.commitment()).collect();
+        let current_commitment = *commitments.last().unwrap();
+        let chain_hash = compute_chain_hash(genesis_commitment, &commitments);
+        let registry_root = registry.root();
+
+        Self {
+            public_hash: Some(compute_chain_provenance_hash(
+                genesis_commitment,
+                current_commitment,
+                chain_hash,
+                registry_root,
+            )),
+            genesis_commitment: Some(genesis_commitment),
+            current_commitment: Some(current_commitment),
+            chain_hash: Some(chain_hash),
+            registry_root: Some(registry_root),
+            links: Some(links),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for ChainProvenanceCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate public data (bound via hash) ===
+        let genesis_var = FpVar::new_witness(cs.clone(), || {
+            self.genesis_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let current_var = FpVar::new_witness(cs.clone(), || {
+            self.current_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let chain_hash_var = FpVar::new_witness(cs.clone(), || {
+            self.chain_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let registry_root_var = FpVar::new_witness(cs.clone(), || {
+            self.registry_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let links = self.links.as_ref().unwrap();
+        assert!(!links.is_empty(), "A chain needs at least one link");
+
+        // === Constraint 1: Each link is a valid deposit or withdrawal ===
+        // The first link starts at the genesis; each later one at its predecessor
+        let mut commitment = genesis_var.clone();
+        let mut chain = genesis_var.clone();
+
+        for link in links {
+            let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(link.item_id)))?;
+            let amount_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(link.amount)))?;
+            let is_deposit = Boolean::new_witness(cs.clone(), || {
+                Ok(link.op_type == OpType::Deposit)
+            })?;
+            let item_volume_var =
+                FpVar::new_witness(cs.clone(), || Ok(Fr::from(link.item_volume)))?;
+            let registry_proof = MerkleProofVar::new_witness(cs.clone(), &link.registry_proof)?;
+
+            // Item volume comes from the registry; a zero leaf would be an empty slot
+            item_volume_var.is_zero()?.enforce_equal(&Boolean::FALSE)?;
+            verify_membership(
+                cs.clone(),
+                &registry_root_var,
+                &item_id_var,
+                &item_volume_var,
+                &registry_proof,
+            )?;
+
+            // Only the item leaf changes
+            let update = InventoryUpdateVar::new_witness(cs.clone(), &item_id_var, &link.update)?;
+            update.old_commitment.enforce_equal(&commitment)?;
+
+            // Quantity moves by +/- amount and must not underflow
+            let delta = is_deposit.select(&amount_var, &amount_var.negate()?)?;
+            update.new_quantity.enforce_equal(&(&update.old_quantity + &delta))?;
+            enforce_u32_range(cs.clone(), &update.new_quantity)?;
+
+            // Volume moves by +/- amount * item_volume
+            let expected_volume = &update.old_state.current_volume + &(&delta * &item_volume_var);
+            update.new_state.current_volume.enforce_equal(&expected_volume)?;
+            enforce_u32_range(cs.clone(), &update.new_state.current_volume)?;
+
+            chain = poseidon_hash_two_var(cs.clone(), &chain, &update.new_commitment)?;
+            commitment = update.new_commitment;
+        }
+
+        // === Constraint 2: The chain ends at the current commitment ===
+        commitment.enforce_equal(&current_var)?;
+
+        // === Constraint 3: Chain hash covers every commitment ===
+        chain.enforce_equal(&chain_hash_var)?;
+
+        // === Constraint 4: Compute and verify public hash ===
+        let inputs = vec![genesis_var, current_var, chain_hash_var, registry_root_var];
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Item 1 takes 10 volume per unit, item 2 takes 3.
+    fn registry() -> SparseMerkleTree {
+        SparseMerkleTree::from_items(&[(1, 10), (2, 3)], DEFAULT_DEPTH)
+    }
+
+    /// Withdraw 30 of item 1, then deposit 4 of item 2.
+    fn steps() -> Vec<ChainStep> {
+        vec![
+            ChainStep {
+                item_id: 1,
+                amount: 30,
+                op_type: OpType::Withdraw,
+                new_blinding: Fr::from(2u64),
+            },
+            ChainStep {
+                item_id: 2,
+                amount: 4,
+                op_type: OpType::Deposit,
+                new_blinding: Fr::from(3u64),
+            },
+        ]
+    }
+
+    fn two_link_chain() -> ChainProvenanceCircuit {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        ChainProvenanceCircuit::new(&mut tree, 1000, Fr::from(1u64), &steps(), &registry())
+    }
+
+    fn is_satisfied(circuit: ChainProvenanceCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_two_link_chain() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let circuit =
+            ChainProvenanceCircuit::new(&mut tree, 1000, Fr::from(1u64), &steps(), &registry());
+
+        assert_eq!(tree.get(1), 70);
+        assert_eq!(tree.get(2), 4);
+        let last = &circuit.links.as_ref().unwrap()[1].update.new_state;
+        assert_eq!(last.current_volume, 712);
+        assert_eq!(circuit.current_commitment, Some(last.commitment()));
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("ChainProvenance (2 links) constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_broken_link_fails() {
+        let mut circuit = two_link_chain();
+
+        // Second link starts from a state the first link never produced
+        let links = circuit.links.as_mut().unwrap();
+        let mut tree = SparseMerkleTree::from_items(&[(1, 80)], DEFAULT_DEPTH);
+        links[1].update = InventoryUpdate::apply(
+            &mut tree,
+            800,
+            Fr::from(2u64),
+            2,
+            4,
+            812,
+            Fr::from(3u64),
+        );
+
+        let commitments: Vec<Fr> =
+            links.iter().map(|link| link.update.new_state.commitment()).collect();
+        let genesis = circuit.genesis_commitment.unwrap();
+        let chain_hash = compute_chain_hash(genesis, &commitments);
+        circuit.current_commitment = Some(commitments[1]);
+        circuit.chain_hash = Some(chain_hash);
+        circuit.public_hash = Some(compute_chain_provenance_hash(
+            genesis,
+            commitments[1],
+            chain_hash,
+            circuit.registry_root.unwrap(),
+        ));
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_wrong_chain_hash_fails() {
+        let mut circuit = two_link_chain();
+
+        // Claim a history that skips the intermediate commitment
+        let genesis = circuit.genesis_commitment.unwrap();
+        let current = circuit.current_commitment.unwrap();
+        let chain_hash = compute_chain_hash(genesis, &[current]);
+        circuit.chain_hash = Some(chain_hash);
+        circuit.public_hash = Some(compute_chain_provenance_hash(
+            genesis,
+            current,
+            chain_hash,
+            circuit.registry_root.unwrap(),
+        ));
+
+        assert!(!is_satisfied(circuit));
+    }
+}
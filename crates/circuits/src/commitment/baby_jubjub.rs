@@ -0,0 +1,101 @@
+//! Baby Jubjub, the twisted Edwards curve embedded in BN254.
+//!
+//! Its base field is the BN254 scalar field, so point arithmetic is native
+//! field arithmetic inside our circuits. Parameters follow EIP-2494:
+//!
+//! a*x^2 + y^2 = 1 + d*x^2*y^2,  a = 168700, d = 168696
+//!
+//! The curve has order 8*l for a 251-bit prime l; [`BabyJubjubScalar`] is the
+//! field of integers modulo l.
+
+use ark_bn254::Fr;
+use ark_ec::{
+    models::CurveConfig,
+    twisted_edwards::{Affine, MontCurveConfig, Projective, TECurveConfig},
+};
+use ark_ff::{Fp256, MontBackend, MontFp};
+
+pub use scalar::BabyJubjubScalarConfig;
+
+// The derive expands to code that trips lints of newer compilers
+#[allow(unexpected_cfgs, non_local_definitions)]
+mod scalar {
+    use ark_ff::MontConfig;
+
+    /// Modulus of the prime-order subgroup.
+    #[derive(MontConfig)]
+    #[modulus = "2736030358979909402780800718157159386076813972158567259200215660948447373041"]
+    #[generator = "31"]
+    pub struct BabyJubjubScalarConfig;
+}
+
+/// Scalar field of the prime-order subgroup.
+pub type BabyJubjubScalar = Fp256<MontBackend<BabyJubjubScalarConfig, 4>>;
+
+/// Baby Jubjub point in affine coordinates.
+pub type BabyJubjubAffine = Affine<BabyJubjubConfig>;
+
+/// Baby Jubjub point in projective coordinates.
+pub type BabyJubjubProjective = Projective<BabyJubjubConfig>;
+
+/// Curve parameters.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct BabyJubjubConfig;
+
+impl CurveConfig for BabyJubjubConfig {
+    type BaseField = Fr;
+    type ScalarField = BabyJubjubScalar;
+
+    const COFACTOR: &'static [u64] = &[8];
+
+    /// 8^-1 mod l
+    const COFACTOR_INV: BabyJubjubScalar = MontFp!(
+        "2394026564107420727433200628387514462817212225638746351800188703329891451411"
+    );
+}
+
+impl TECurveConfig for BabyJubjubConfig {
+    const COEFF_A: Fr = MontFp!("168700");
+    const COEFF_D: Fr = MontFp!("168696");
+
+    /// `Base8` of EIP-2494, a generator of the prime-order subgroup.
+    const GENERATOR: BabyJubjubAffine = BabyJubjubAffine::new_unchecked(
+        MontFp!("5299619240641551281634865583518297030282874472190772894086521144482721001553"),
+        MontFp!("16950150798460657717958625567821834550301663161624707787222815936182638968203"),
+    );
+
+    type MontCurveConfig = BabyJubjubConfig;
+}
+
+impl MontCurveConfig for BabyJubjubConfig {
+    const COEFF_A: Fr = MontFp!("168698");
+    const COEFF_B: Fr = MontFp!("1");
+
+    type TECurveConfig = BabyJubjubConfig;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_ff::{Field, PrimeField};
+
+    #[test]
+    fn test_generator_in_subgroup() {
+        let g = BabyJubjubAffine::generator();
+
+        assert!(g.is_on_curve());
+        assert!(g.is_in_correct_subgroup_assuming_on_curve());
+        assert!(!g.is_zero());
+    }
+
+    #[test]
+    fn test_scalar_field_order() {
+        // l * G is the identity, and 8 * l is the full curve order
+        let g = BabyJubjubAffine::generator();
+        let order = BabyJubjubScalar::MODULUS;
+        assert!(g.mul_bigint(order).into_affine().is_zero());
+        let cofactor = BabyJubjubScalar::from(8u64);
+        assert_eq!(BabyJubjubConfig::COFACTOR_INV * cofactor, BabyJubjubScalar::ONE);
+    }
+}
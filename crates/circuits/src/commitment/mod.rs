@@ -0,0 +1,5 @@
+//! Commitment schemes besides the Poseidon inventory commitment in
+//! [`crate::smt_commitment`].
+
+pub mod baby_jubjub;
+pub mod pedersen;
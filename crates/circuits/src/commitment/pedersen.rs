@@ -0,0 +1,152 @@
+//! Additively homomorphic Pedersen commitments to item quantities.
+//!
+//! Poseidon inventory commitments cannot be combined, so aggregating
+//! quantities across inventories would need every opening. Here each quantity
+//! is committed on its own over Baby Jubjub:
+//!
+//! C = quantity * G + blinding * H
+//!
+//! so that C(a, r) + C(b, s) = C(a + b, r + s): summing commitments to the same
+//! item sums the quantities without revealing them. `G` is the curve
+//! generator and `H` is derived by hashing (see [`blinding_generator`]), so
+//! nobody knows log_G(H) and a commitment cannot be opened to two quantities.
+
+use std::sync::OnceLock;
+
+use ark_bn254::Fr;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::groups::curves::twisted_edwards::AffineVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use super::baby_jubjub::{BabyJubjubAffine, BabyJubjubConfig, BabyJubjubScalar};
+use crate::poseidon::poseidon_hash_many;
+use crate::range_check::range_bits_le;
+
+/// Domain tag for deriving the blinding generator ("PEDERSEN" in ASCII).
+pub const PEDERSEN_H_DOMAIN: u64 = 0x504544455253454e;
+
+/// Bits of a blinding scalar (the bit size of the subgroup order).
+pub const BLINDING_BITS: usize = BabyJubjubScalar::MODULUS_BIT_SIZE as usize;
+
+/// In-circuit Pedersen commitment (a Baby Jubjub point).
+pub type PedersenCommitmentVar = AffineVar<BabyJubjubConfig, FpVar<Fr>>;
+
+/// The blinding generator `H`.
+///
+/// The first y = Poseidon(PEDERSEN_H_DOMAIN, i), i = 0, 1, ..., that lies on
+/// the curve, with the smaller x, multiplied by the cofactor into the
+/// prime-order subgroup.
+pub fn blinding_generator() -> BabyJubjubAffine {
+    static H: OnceLock<BabyJubjubAffine> = OnceLock::new();
+    *H.get_or_init(|| {
+        (0u64..)
+            .filter_map(|i| {
+                let y = poseidon_hash_many(&[Fr::from(PEDERSEN_H_DOMAIN), Fr::from(i)]);
+                BabyJubjubAffine::get_point_from_y_unchecked(y, false)
+            })
+            .map(|point| point.clear_cofactor())
+            .find(|point| !point.is_zero())
+            .unwrap()
+    })
+}
+
+/// Commit to a single quantity: quantity * G + blinding * H.
+pub fn pedersen_commit(quantity: u64, blinding: BabyJubjubScalar) -> BabyJubjubAffine {
+    let g = BabyJubjubAffine::generator();
+    (g * BabyJubjubScalar::from(quantity) + blinding_generator() * blinding).into_affine()
+}
+
+/// Commit to each slot quantity of an inventory, one Pedersen commitment per
+/// slot with its own blinding.
+///
+/// Commitments for the same slot of different inventories can be added to
+/// commit to the total quantity.
+pub fn create_inventory_commitment_pedersen(
+    quantities: &[u64],
+    blindings: &[BabyJubjubScalar],
+) -> Vec<BabyJubjubAffine> {
+    assert_eq!(quantities.len(), blindings.len(), "one blinding per quantity");
+    quantities
+        .iter()
+        .zip(blindings)
+        .map(|(&quantity, &blinding)| pedersen_commit(quantity, blinding))
+        .collect()
+}
+
+/// A blinding scalar as a BN254 field element, for allocating it as a witness.
+///
+/// The subgroup order is below the BN254 modulus, so this is lossless.
+pub fn blinding_to_field(blinding: BabyJubjubScalar) -> Fr {
+    Fr::from_le_bytes_mod_order(&blinding.into_bigint().to_bytes_le())
+}
+
+/// Compute a Pedersen commitment in-circuit.
+///
+/// `quantity` must fit in 64 bits and `blinding` (see [`blinding_to_field`])
+/// in [`BLINDING_BITS`]; both are enforced.
+pub fn pedersen_commit_var(
+    cs: ConstraintSystemRef<Fr>,
+    quantity: &FpVar<Fr>,
+    blinding: &FpVar<Fr>,
+) -> Result<PedersenCommitmentVar, SynthesisError> {
+    let quantity_bits = range_bits_le(cs.clone(), quantity, 64)?;
+    let blinding_bits = range_bits_le(cs, blinding, BLINDING_BITS)?;
+
+    let g = PedersenCommitmentVar::constant(BabyJubjubAffine::generator().into());
+    let h = PedersenCommitmentVar::constant(blinding_generator().into());
+
+    Ok(g.scalar_mul_le(quantity_bits.iter())? + h.scalar_mul_le(blinding_bits.iter())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn test_blinding_generator_independent() {
+        let h = blinding_generator();
+
+        assert!(h.is_on_curve());
+        assert!(h.is_in_correct_subgroup_assuming_on_curve());
+        assert_ne!(h, BabyJubjubAffine::generator());
+    }
+
+    #[test]
+    fn test_commitments_add() {
+        let (r, s) = (BabyJubjubScalar::from(1234u64), BabyJubjubScalar::from(5678u64));
+
+        let sum = (pedersen_commit(30, r) + pedersen_commit(70, s)).into_affine();
+
+        assert_eq!(sum, pedersen_commit(100, r + s));
+        assert_ne!(sum, pedersen_commit(99, r + s));
+    }
+
+    #[test]
+    fn test_inventory_commitment_per_slot() {
+        let blindings = [BabyJubjubScalar::from(1u64), BabyJubjubScalar::from(2u64)];
+        let commitments = create_inventory_commitment_pedersen(&[100, 50], &blindings);
+
+        assert_eq!(commitments[0], pedersen_commit(100, blindings[0]));
+        assert_eq!(commitments[1], pedersen_commit(50, blindings[1]));
+    }
+
+    #[test]
+    fn test_gadget_matches_native() {
+        let blinding = -BabyJubjubScalar::from(3u64);
+        let expected = pedersen_commit(100, blinding);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let quantity = FpVar::new_witness(cs.clone(), || Ok(Fr::from(100u64))).unwrap();
+        let blinding_var =
+            FpVar::new_witness(cs.clone(), || Ok(blinding_to_field(blinding))).unwrap();
+        let commitment = pedersen_commit_var(cs.clone(), &quantity, &blinding_var).unwrap();
+
+        assert_eq!(commitment.value().unwrap().into_affine(), expected);
+        assert!(cs.is_satisfied().unwrap());
+        println!("Pedersen commitment constraints: {}", cs.num_constraints());
+    }
+}
@@ -0,0 +1,275 @@
+//! Crafting Circuit for SMT-based inventory.
+//!
+//! Proves that an inventory consumed a recipe's inputs and produced its outputs
+//! (e.g. 3 iron + 2 wood -> 1 sword) without revealing anything else it holds.
+//!
+//! Inputs are debited from the old SMT root and outputs credited, one after
+//! another; every new quantity is range checked, so an input cannot be consumed
+//! beyond what is held. The result must equal the new root, so any item not in
+//! the recipe is unchanged. The committed volume moves by the recipe's net
+//! volume.
+//!
+//! The recipe shape is fixed when the circuit is built (see
+//! [`CraftingCircuit::empty`]): at most [`MAX_INPUTS`] inputs and
+//! [`MAX_OUTPUTS`] outputs.
+//!
+//! Public inputs:
+//! - old_commitment, new_commitment
+//! - recipe_commitment: Poseidon(inputs..., outputs...), each as
+//!   (item_id, amount, item_volume)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::multi_transfer::{ItemTransfer, TransferSide, TransferSideVar};
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::smt::SparseMerkleTree;
+
+/// Most items a recipe can consume.
+pub const MAX_INPUTS: usize = 4;
+
+/// Most items a recipe can produce.
+pub const MAX_OUTPUTS: usize = 2;
+
+/// One item consumed or produced by a recipe.
+///
+/// Inputs are debited and outputs credited exactly like the items of a
+/// multi-item transfer, so a recipe item is an [`ItemTransfer`].
+pub type RecipeItem = ItemTransfer;
+
+/// A crafting recipe.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recipe {
+    /// Items consumed
+    pub inputs: Vec<RecipeItem>,
+    /// Items produced
+    pub outputs: Vec<RecipeItem>,
+}
+
+impl Recipe {
+    /// Items in the order they are applied: inputs, then outputs.
+    fn items(&self) -> impl Iterator<Item = (&RecipeItem, bool)> {
+        let inputs = self.inputs.iter().map(|item| (item, false));
+        inputs.chain(self.outputs.iter().map(|item| (item, true)))
+    }
+
+    fn len(&self) -> usize {
+        self.inputs.len() + self.outputs.len()
+    }
+
+    fn assert_within_bounds(&self) {
+        assert!(self.inputs.len() <= MAX_INPUTS, "at most {} inputs", MAX_INPUTS);
+        assert!(self.outputs.len() <= MAX_OUTPUTS, "at most {} outputs", MAX_OUTPUTS);
+    }
+}
+
+/// Compute the public commitment to a recipe.
+pub fn compute_recipe_commitment(recipe: &Recipe) -> Fr {
+    let mut inputs = Vec::with_capacity(3 * recipe.len());
+    for (item, _) in recipe.items() {
+        inputs.push(Fr::from(item.item_id));
+        inputs.push(Fr::from(item.amount));
+        inputs.push(Fr::from(item.item_volume));
+    }
+    poseidon_hash_many(&inputs)
+}
+
+/// Crafting Circuit.
+#[derive(Clone)]
+pub struct CraftingCircuit {
+    // Public inputs
+    /// Commitment before crafting
+    pub old_commitment: Option<Fr>,
+    /// Commitment after crafting
+    pub new_commitment: Option<Fr>,
+    /// Commitment to the recipe
+    pub recipe_commitment: Option<Fr>,
+
+    /// Recipe crafted
+    pub recipe: Option<Recipe>,
+
+    /// Inventory witnesses, with one step per recipe item (inputs, then outputs)
+    pub inventory: Option<TransferSide>,
+}
+
+impl CraftingCircuit {
+    /// Create an empty circuit for setup for recipes with `num_inputs` inputs
+    /// and `num_outputs` outputs.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty(num_inputs: usize, num_outputs: usize) -> Self {
+        let dummy_item = RecipeItem {
+            item_id: 0,
+            amount: 0,
+            item_volume: 0,
+        };
+        let recipe = Recipe {
+            inputs: vec![dummy_item; num_inputs],
+            outputs: vec![dummy_item; num_outputs],
+        };
+        recipe.assert_within_bounds();
+
+        Self {
+            old_commitment: Some(Fr::from(0u64)),
+            new_commitment: Some(Fr::from(0u64)),
+            recipe_commitment: Some(Fr::from(0u64)),
+            inventory: Some(TransferSide::empty(recipe.len())),
+            recipe: Some(recipe),
+        }
+    }
+
+    /// Craft `recipe` in `tree` and record the witnesses.
+    ///
+    /// `tree` is updated in place so it reflects the new state afterwards.
+    /// Panics if the inventory holds too few of an input.
+    pub fn new(
+        recipe: Recipe,
+        tree: &mut SparseMerkleTree,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+    ) -> Self {
+        recipe.assert_within_bounds();
+        let inventory = TransferSide::exchange(
+            tree,
+            old_volume,
+            old_blinding,
+            new_blinding,
+            &recipe.inputs,
+            &recipe.outputs,
+        );
+
+        Self {
+            old_commitment: Some(inventory.old_state.commitment()),
+            new_commitment: Some(inventory.new_state.commitment()),
+            recipe_commitment: Some(compute_recipe_commitment(&recipe)),
+            recipe: Some(recipe),
+            inventory: Some(inventory),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for CraftingCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public inputs ===
+        // Order: old_commitment, new_commitment, recipe_commitment
+        let old_commitment_var = FpVar::new_input(cs.clone(), || {
+            self.old_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let new_commitment_var = FpVar::new_input(cs.clone(), || {
+            self.new_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let recipe_commitment_var = FpVar::new_input(cs.clone(), || {
+            self.recipe_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate inventory witnesses ===
+        let recipe = self.recipe.as_ref().unwrap();
+        let inventory = self.inventory.as_ref().unwrap();
+        recipe.assert_within_bounds();
+        assert_eq!(inventory.old_quantities.len(), recipe.len(), "one quantity per recipe item");
+        assert_eq!(inventory.proofs.len(), recipe.len(), "one proof per recipe item");
+
+        let mut inventory_var = TransferSideVar::new_witness(cs.clone(), inventory)?;
+
+        // === Constraint 1: Consume each input, then produce each output ===
+        let mut hash_inputs = Vec::with_capacity(3 * recipe.len());
+        for (i, (item, is_output)) in recipe.items().enumerate() {
+            let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(item.item_id)))?;
+            let amount_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(item.amount)))?;
+            let item_volume_var =
+                FpVar::new_witness(cs.clone(), || Ok(Fr::from(item.item_volume)))?;
+
+            inventory_var.apply(
+                cs.clone(),
+                &item_id_var,
+                &amount_var,
+                &item_volume_var,
+                inventory.old_quantities[i],
+                &inventory.proofs[i],
+                is_output,
+            )?;
+
+            hash_inputs.extend([item_id_var, amount_var, item_volume_var]);
+        }
+
+        // === Constraint 2: No other item changed, volume moved by the recipe's net volume ===
+        let (old_commitment, new_commitment) = inventory_var.finish(cs.clone())?;
+
+        // === Constraint 3: Commitments match the public inputs ===
+        old_commitment.enforce_equal(&old_commitment_var)?;
+        new_commitment.enforce_equal(&new_commitment_var)?;
+
+        // === Constraint 4: Compute and verify recipe commitment ===
+        let computed_recipe = poseidon_hash_many_var(cs.clone(), &hash_inputs)?;
+
+        computed_recipe.enforce_equal(&recipe_commitment_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::DEFAULT_DEPTH;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    const IRON: u64 = 1;
+    const WOOD: u64 = 2;
+    const SWORD: u64 = 10;
+
+    /// 3 iron (volume 2) + 2 wood (volume 1) -> 1 sword (volume 5).
+    fn sword_recipe() -> Recipe {
+        Recipe {
+            inputs: vec![
+                RecipeItem { item_id: IRON, amount: 3, item_volume: 2 },
+                RecipeItem { item_id: WOOD, amount: 2, item_volume: 1 },
+            ],
+            outputs: vec![RecipeItem { item_id: SWORD, amount: 1, item_volume: 5 }],
+        }
+    }
+
+    fn is_satisfied(circuit: CraftingCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_crafting_valid() {
+        let items = [(IRON, 10), (WOOD, 4), (3, 7)];
+        let mut tree = SparseMerkleTree::from_items(&items, DEFAULT_DEPTH);
+        let circuit =
+            CraftingCircuit::new(sword_recipe(), &mut tree, 31, Fr::from(1u64), Fr::from(2u64));
+
+        assert_eq!(tree.get(IRON), 7);
+        assert_eq!(tree.get(WOOD), 2);
+        assert_eq!(tree.get(SWORD), 1);
+        assert_eq!(tree.get(3), 7);
+        assert_eq!(circuit.inventory.as_ref().unwrap().new_state.current_volume, 28);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("Crafting (2 -> 1) constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_crafting_underfunded_input_fails() {
+        // Only 2 iron held: craft with 2, then claim the recipe's 3
+        let mut tree = SparseMerkleTree::from_items(&[(IRON, 2), (WOOD, 4)], DEFAULT_DEPTH);
+        let mut recipe = sword_recipe();
+        recipe.inputs[0].amount = 2;
+        let mut circuit =
+            CraftingCircuit::new(recipe, &mut tree, 8, Fr::from(1u64), Fr::from(2u64));
+
+        let recipe = circuit.recipe.as_mut().unwrap();
+        recipe.inputs[0].amount = 3;
+        circuit.recipe_commitment = Some(compute_recipe_commitment(recipe));
+
+        assert!(!is_satisfied(circuit));
+    }
+}
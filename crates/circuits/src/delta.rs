@@ -0,0 +1,295 @@
+//! Delta Circuit for SMT-based inventory.
+//!
+//! Proves that the difference between two committed inventories is exactly a
+//! publicly known list of `(item_id, signed_delta)` changes, for reconciliation
+//! with an external ledger.
+//!
+//! The deltas are applied to the old SMT root one after another; the result must
+//! equal the new root, so any item not in the list is unchanged. Each new
+//! quantity is range checked, so a negative delta cannot underflow. The volume
+//! is part of the commitment, so each delta also carries its per-unit volume and
+//! the committed volume must move by the sum of `item_volume * delta`.
+//!
+//! The number of deltas is fixed when the circuit is built (see [`DeltaCircuit::empty`]).
+//!
+//! Public input: Poseidon(old_commitment, new_commitment,
+//!                        item_id_0, delta_0, item_volume_0, ...)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::enforce_u32_range;
+use crate::smt::{verify_and_update, MerkleProof, MerkleProofVar, SparseMerkleTree, DEFAULT_DEPTH};
+use crate::smt_commitment::{InventoryState, InventoryStateVar};
+
+/// A publicly known change to one item's quantity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ItemDelta {
+    /// Item ID that changed
+    pub item_id: u64,
+    /// Signed change in quantity
+    pub delta: i64,
+    /// Volume per unit of this item type
+    pub item_volume: u64,
+}
+
+impl ItemDelta {
+    /// Field encoding of the signed delta (negative values wrap to p - |delta|).
+    pub fn delta_field(&self) -> Fr {
+        if self.delta < 0 {
+            -Fr::from(self.delta.unsigned_abs())
+        } else {
+            Fr::from(self.delta as u64)
+        }
+    }
+}
+
+/// Compute the public input hash for Delta proof.
+pub fn compute_delta_hash(old_commitment: Fr, new_commitment: Fr, deltas: &[ItemDelta]) -> Fr {
+    let mut inputs = vec![old_commitment, new_commitment];
+    for delta in deltas {
+        inputs.push(Fr::from(delta.item_id));
+        inputs.push(delta.delta_field());
+        inputs.push(Fr::from(delta.item_volume));
+    }
+    poseidon_hash_many(&inputs)
+}
+
+/// Delta Circuit.
+#[derive(Clone)]
+pub struct DeltaCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    /// Changes applied, in order
+    pub deltas: Option<Vec<ItemDelta>>,
+
+    // State witnesses
+    /// Inventory state before the changes
+    pub old_state: Option<InventoryState>,
+    /// Inventory state after the changes
+    pub new_state: Option<InventoryState>,
+
+    // Per-delta witnesses
+    /// Quantity of each item before its delta is applied
+    pub old_quantities: Option<Vec<u64>>,
+    /// Proof for each item, taken after the preceding deltas were applied
+    pub proofs: Option<Vec<MerkleProof<Fr>>>,
+}
+
+impl DeltaCircuit {
+    /// Create an empty circuit for setup with room for `num_deltas` changes.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty(num_deltas: usize) -> Self {
+        let zero_state = InventoryState::new(Fr::from(0u64), 0, Fr::from(0u64));
+        let dummy_proof = MerkleProof::new(
+            vec![Fr::from(0u64); DEFAULT_DEPTH],
+            vec![false; DEFAULT_DEPTH],
+        );
+        let dummy_delta = ItemDelta {
+            item_id: 0,
+            delta: 0,
+            item_volume: 0,
+        };
+
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            deltas: Some(vec![dummy_delta; num_deltas]),
+            old_state: Some(zero_state.clone()),
+            new_state: Some(zero_state),
+            old_quantities: Some(vec![0; num_deltas]),
+            proofs: Some(vec![dummy_proof; num_deltas]),
+        }
+    }
+
+    /// Apply `deltas` to `tree` and record the witnesses.
+    ///
+    /// `tree` is updated in place so it reflects the new state afterwards.
+    pub fn new(
+        deltas: Vec<ItemDelta>,
+        tree: &mut SparseMerkleTree,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+    ) -> Self {
+        let old_state = InventoryState::new(tree.root(), old_volume, old_blinding);
+
+        let mut new_volume = old_volume as i128;
+        let mut old_quantities = Vec::with_capacity(deltas.len());
+        let mut proofs = Vec::with_capacity(deltas.len());
+        for delta in &deltas {
+            let old_quantity = tree.get(delta.item_id);
+            let new_quantity = old_quantity as i128 + delta.delta as i128;
+            assert!(new_quantity >= 0, "Delta would cause quantity underflow");
+
+            old_quantities.push(old_quantity);
+            proofs.push(tree.get_proof(delta.item_id));
+            tree.update(delta.item_id, new_quantity as u64);
+
+            new_volume += delta.item_volume as i128 * delta.delta as i128;
+        }
+        assert!(new_volume >= 0, "Delta would cause volume underflow");
+
+        let new_state = InventoryState::new(tree.root(), new_volume as u64, new_blinding);
+        let public_hash =
+            compute_delta_hash(old_state.commitment(), new_state.commitment(), &deltas);
+
+        Self {
+            public_hash: Some(public_hash),
+            deltas: Some(deltas),
+            old_state: Some(old_state),
+            new_state: Some(new_state),
+            old_quantities: Some(old_quantities),
+            proofs: Some(proofs),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for DeltaCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate state witnesses ===
+        let old_state =
+            InventoryStateVar::new_witness(cs.clone(), self.old_state.as_ref().unwrap())?;
+        let new_state =
+            InventoryStateVar::new_witness(cs.clone(), self.new_state.as_ref().unwrap())?;
+
+        let deltas = self.deltas.as_ref().unwrap();
+        let old_quantities = self.old_quantities.as_ref().unwrap();
+        let proofs = self.proofs.as_ref().unwrap();
+        assert_eq!(old_quantities.len(), deltas.len(), "one quantity per delta");
+        assert_eq!(proofs.len(), deltas.len(), "one proof per delta");
+
+        // === Constraint 1: Apply each delta to the root and volume ===
+        let mut root = old_state.inventory_root.clone();
+        let mut volume = old_state.current_volume.clone();
+        let mut hash_inputs = Vec::with_capacity(2 + 3 * deltas.len());
+
+        for ((delta, &old_quantity), proof) in deltas.iter().zip(old_quantities).zip(proofs) {
+            let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(delta.item_id)))?;
+            let delta_var = FpVar::new_witness(cs.clone(), || Ok(delta.delta_field()))?;
+            let item_volume_var =
+                FpVar::new_witness(cs.clone(), || Ok(Fr::from(delta.item_volume)))?;
+            let old_quantity_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(old_quantity)))?;
+            let proof_var = MerkleProofVar::new_witness(cs.clone(), proof)?;
+
+            // New quantity must not underflow
+            let new_quantity_var = &old_quantity_var + &delta_var;
+            enforce_u32_range(cs.clone(), &new_quantity_var)?;
+
+            root = verify_and_update(
+                cs.clone(),
+                &root,
+                &item_id_var,
+                &old_quantity_var,
+                &new_quantity_var,
+                &proof_var,
+            )?;
+
+            volume += &item_volume_var * &delta_var;
+            hash_inputs.extend([item_id_var, delta_var, item_volume_var]);
+        }
+
+        // === Constraint 2: No other item changed ===
+        root.enforce_equal(&new_state.inventory_root)?;
+
+        // === Constraint 3: Volume moved by exactly the listed deltas ===
+        volume.enforce_equal(&new_state.current_volume)?;
+        enforce_u32_range(cs.clone(), &new_state.current_volume)?;
+
+        // === Constraint 4: Compute and verify public hash ===
+        let old_commitment = old_state.commitment(cs.clone())?;
+        let new_commitment = new_state.commitment(cs.clone())?;
+
+        let mut inputs = vec![old_commitment, new_commitment];
+        inputs.extend(hash_inputs);
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Ledger entry: 30 of item 1 (volume 2) left, 10 of item 4 (volume 1) arrived.
+    fn ledger() -> Vec<ItemDelta> {
+        vec![
+            ItemDelta { item_id: 1, delta: -30, item_volume: 2 },
+            ItemDelta { item_id: 4, delta: 10, item_volume: 1 },
+        ]
+    }
+
+    fn inventory() -> SparseMerkleTree {
+        SparseMerkleTree::from_items(&[(1, 100), (2, 5)], DEFAULT_DEPTH)
+    }
+
+    fn is_satisfied(circuit: DeltaCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    /// Recompute the public hash after tampering with the witnesses.
+    fn rebind(circuit: &mut DeltaCircuit) {
+        circuit.public_hash = Some(compute_delta_hash(
+            circuit.old_state.as_ref().unwrap().commitment(),
+            circuit.new_state.as_ref().unwrap().commitment(),
+            circuit.deltas.as_ref().unwrap(),
+        ));
+    }
+
+    #[test]
+    fn test_delta_matches() {
+        let mut tree = inventory();
+        let circuit = DeltaCircuit::new(ledger(), &mut tree, 205, Fr::from(1u64), Fr::from(2u64));
+
+        assert_eq!(tree.get(1), 70);
+        assert_eq!(tree.get(4), 10);
+        assert_eq!(circuit.new_state.as_ref().unwrap().current_volume, 155);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("Delta (2 entries) constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_delta_mismatch_fails() {
+        let mut tree = inventory();
+        let mut circuit =
+            DeltaCircuit::new(ledger(), &mut tree, 205, Fr::from(1u64), Fr::from(2u64));
+
+        // Publish -29 for item 1 while the inventory actually lost 30
+        circuit.deltas.as_mut().unwrap()[0].delta = -29;
+        rebind(&mut circuit);
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_unlisted_item_changed_fails() {
+        let mut tree = inventory();
+        let mut circuit =
+            DeltaCircuit::new(ledger(), &mut tree, 205, Fr::from(1u64), Fr::from(2u64));
+
+        // Item 2 also changes, but the ledger does not mention it
+        tree.update(2, 50);
+        circuit.new_state.as_mut().unwrap().inventory_root = tree.root();
+        rebind(&mut circuit);
+
+        assert!(!is_satisfied(circuit));
+    }
+}
@@ -0,0 +1,347 @@
+//! Debugging helpers for unsatisfied constraint systems.
+//!
+//! Circuits label their sections with arkworks namespaces (`ns!`). The labels
+//! are only recorded while a [`ConstraintLayer`] is installed as the tracing
+//! subscriber during synthesis; without one, constraints are reported by index
+//! with the label `"unlabeled"`.
+//!
+//! Installing the layer slows synthesis down considerably (see the note in the
+//! proof server's `main.rs`), so only do it when debugging a failing circuit.
+//!
+//! [`generate_witness`] returns a circuit's assignment without building any
+//! constraints, for inspecting the values a witness computes.
+//!
+//! [`constraint_breakdown`] installs the layer itself to count the constraints
+//! each section creates.
+//!
+//! [`ConstraintLayer`]: ark_relations::r1cs::ConstraintLayer
+
+use std::fmt;
+
+use ark_bn254::Fr;
+use ark_ff::{PrimeField, Zero};
+use ark_relations::r1cs::{
+    ConstraintLayer, ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef,
+    SynthesisError, SynthesisMode, TracingMode,
+};
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Section label for constraints created outside any namespace.
+pub const UNLABELED: &str = "unlabeled";
+
+/// Synthesize `circuit` without building constraints and return its full assignment.
+///
+/// The assignment is indexed like the constraint matrices: the constant 1, then
+/// the public inputs, then the witnesses, each in allocation order. Much faster
+/// than full synthesis, but cannot tell whether the witness satisfies the
+/// circuit; use [`explain_unsatisfied`] for that.
+pub fn generate_witness<C: ConstraintSynthesizer<Fr>>(
+    circuit: C,
+) -> Result<Vec<Fr>, SynthesisError> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    cs.set_mode(SynthesisMode::Prove { construct_matrices: false });
+    circuit.generate_constraints(cs.clone())?;
+
+    let inner = cs.borrow().ok_or(SynthesisError::MissingCS)?;
+    Ok(inner
+        .instance_assignment
+        .iter()
+        .chain(&inner.witness_assignment)
+        .copied()
+        .collect())
+}
+
+/// An unsatisfied constraint `a * b = c`, with each side evaluated on the witness.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsatisfiedConstraint {
+    /// Index of the constraint in the (finalized) system
+    pub index: usize,
+    /// Outermost namespace that created it, or `"unlabeled"`
+    pub label: String,
+    /// Value of the A linear combination
+    pub a: Fr,
+    /// Value of the B linear combination
+    pub b: Fr,
+    /// Value of the C linear combination
+    pub c: Fr,
+}
+
+impl fmt::Display for UnsatisfiedConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "constraint {} ({}): A * B != C with A = {}, B = {}, C = {}",
+            self.index,
+            self.label,
+            // Fr's own Display prints zero as an empty string
+            self.a.into_bigint(),
+            self.b.into_bigint(),
+            self.c.into_bigint()
+        )
+    }
+}
+
+/// Find the first unsatisfied constraint and the section that created it.
+///
+/// The section is the outermost namespace, e.g. `"state_transition::volume"`.
+///
+/// Returns `None` if every constraint is satisfied, or if `cs` has no witness
+/// assignment (setup mode). Finalizes `cs`, which inlines linear combinations
+/// but does not change satisfiability.
+pub fn which_constraint_failed(cs: &ConstraintSystemRef<Fr>) -> Option<(usize, String)> {
+    explain_unsatisfied(cs).map(|failed| (failed.index, failed.label))
+}
+
+/// Like [`which_constraint_failed`], but also reports the evaluated A, B and C.
+pub fn explain_unsatisfied(cs: &ConstraintSystemRef<Fr>) -> Option<UnsatisfiedConstraint> {
+    if cs.is_in_setup_mode() {
+        return None;
+    }
+    cs.finalize();
+    let matrices = cs.to_matrices()?;
+    let names = cs.constraint_names();
+
+    let assignment: Vec<Fr> = {
+        let inner = cs.borrow()?;
+        inner
+            .instance_assignment
+            .iter()
+            .chain(&inner.witness_assignment)
+            .copied()
+            .collect()
+    };
+    let eval = |row: &[(Fr, usize)]| {
+        row.iter()
+            .fold(Fr::zero(), |acc, &(coeff, column)| acc + coeff * assignment[column])
+    };
+
+    let (index, (a, b, c)) = (0..matrices.num_constraints)
+        .map(|i| (i, (eval(&matrices.a[i]), eval(&matrices.b[i]), eval(&matrices.c[i]))))
+        .find(|&(_, (a, b, c))| a * b != c)?;
+
+    let label = names
+        .as_ref()
+        .and_then(|names| names.get(index))
+        .map_or(UNLABELED, |name| section_of(name))
+        .to_string();
+
+    Some(UnsatisfiedConstraint { index, label, a, b, c })
+}
+
+/// Number of constraints one section of a circuit creates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionCount {
+    /// Outermost namespace, e.g. `"state_transition::volume"`, or `"unlabeled"`
+    pub section: String,
+    /// Constraints created inside it
+    pub constraints: usize,
+}
+
+/// Count the constraints each section of `circuit` creates.
+///
+/// Synthesizes in setup mode with constraint labels recorded, so pass the
+/// circuit's `empty()` instance. Sections are listed in the order they first
+/// create a constraint, and their counts sum to the circuit's total.
+///
+/// Constraints outside the circuit's own namespaces are grouped by the
+/// outermost arkworks gadget that created them (e.g. `"eq::enforce_equal"`).
+pub fn constraint_breakdown<C: ConstraintSynthesizer<Fr>>(
+    circuit: C,
+) -> Result<Vec<SectionCount>, SynthesisError> {
+    let subscriber = tracing_subscriber::Registry::default()
+        .with(ConstraintLayer::new(TracingMode::OnlyConstraints));
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+    tracing::subscriber::with_default(subscriber, || circuit.generate_constraints(cs.clone()))?;
+
+    let names = cs.constraint_names().ok_or(SynthesisError::MissingCS)?;
+    let mut sections: Vec<SectionCount> = Vec::new();
+    for name in &names {
+        let section = section_of(name);
+        match sections.iter_mut().find(|count| count.section == section) {
+            Some(count) => count.constraints += 1,
+            None => sections.push(SectionCount { section: section.to_string(), constraints: 1 }),
+        }
+    }
+
+    Ok(sections)
+}
+
+/// The circuit section a constraint was created in.
+fn section_of(name: &str) -> &str {
+    // Traces look like "/state_transition::volume/eq::enforce_equal/...";
+    // the outermost namespace is the circuit section
+    name.trim_start_matches('/')
+        .split('/')
+        .next()
+        .filter(|section| !section.is_empty())
+        .unwrap_or(UNLABELED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal::OpType;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use crate::{CapacitySMTCircuit, StateTransitionCircuit};
+
+    /// Deposit 50 of item 1 (volume 10) into an inventory holding 100.
+    fn deposit() -> StateTransitionCircuit {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+        tree.update(1, 150);
+
+        StateTransitionCircuit::new(
+            old_root,
+            1000,
+            Fr::from(12345u64),
+            tree.root(),
+            1500,
+            Fr::from(67890u64),
+            1,
+            100,
+            150,
+            50,
+            OpType::Deposit,
+            proof,
+            10,
+            Fr::from(99999u64),
+            10000,
+            0,
+            Fr::from(12345678u64),
+        )
+    }
+
+    /// Synthesize with constraint labels recorded.
+    fn synthesize_labeled(circuit: StateTransitionCircuit) -> ConstraintSystemRef<Fr> {
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(ConstraintLayer::new(TracingMode::OnlyConstraints));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs
+        })
+    }
+
+    #[test]
+    fn test_satisfied_circuit_reports_nothing() {
+        let cs = synthesize_labeled(deposit());
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(which_constraint_failed(&cs), None);
+    }
+
+    #[test]
+    fn test_broken_commitment_reports_signal_hash() {
+        // Opening the new commitment with the wrong blinding changes the
+        // commitment, which is only bound through the signal hash
+        let mut circuit = deposit();
+        circuit.new_blinding = Some(Fr::from(1u64));
+
+        let cs = synthesize_labeled(circuit);
+        let (index, label) = which_constraint_failed(&cs).unwrap();
+
+        assert!(index < cs.num_constraints());
+        assert_eq!(label, "state_transition::signal_hash");
+    }
+
+    #[test]
+    fn test_broken_volume_reports_volume() {
+        let mut circuit = deposit();
+        circuit.new_volume = Some(1499);
+
+        let cs = synthesize_labeled(circuit);
+        let (_, label) = which_constraint_failed(&cs).unwrap();
+
+        assert_eq!(label, "state_transition::volume");
+    }
+
+    #[test]
+    fn test_breakdown_sums_to_total() {
+        let breakdown = constraint_breakdown(StateTransitionCircuit::empty()).unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        cs.set_mode(SynthesisMode::Setup);
+        StateTransitionCircuit::empty().generate_constraints(cs.clone()).unwrap();
+
+        let total: usize = breakdown.iter().map(|count| count.constraints).sum();
+        assert_eq!(total, cs.num_constraints());
+        let sections: Vec<&str> = breakdown.iter().map(|count| count.section.as_str()).collect();
+        assert!(sections.contains(&"state_transition::volume"));
+        assert!(sections.contains(&"state_transition::signal_hash"));
+    }
+
+    #[test]
+    fn test_breakdown_of_unlabeled_circuit() {
+        // Capacity has no sections of its own, so constraints are grouped by
+        // the arkworks gadget that created them
+        let breakdown = constraint_breakdown(CapacitySMTCircuit::empty()).unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        cs.set_mode(SynthesisMode::Setup);
+        CapacitySMTCircuit::empty().generate_constraints(cs.clone()).unwrap();
+
+        let total: usize = breakdown.iter().map(|count| count.constraints).sum();
+        assert_eq!(total, cs.num_constraints());
+        assert!(breakdown.iter().any(|count| count.section == "sponge::poseidon::absorb"));
+    }
+
+    #[test]
+    fn test_generate_witness_matches_full_synthesis() {
+        let witness = generate_witness(deposit()).unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        deposit().generate_constraints(cs.clone()).unwrap();
+        let inner = cs.borrow().unwrap();
+        let expected: Vec<Fr> = inner
+            .instance_assignment
+            .iter()
+            .chain(&inner.witness_assignment)
+            .copied()
+            .collect();
+
+        assert_eq!(witness, expected);
+        assert_eq!(witness[0], Fr::from(1u64));
+    }
+
+    #[test]
+    fn test_broken_withdraw_reports_evaluated_values() {
+        // Withdraw 30 of item 1 (volume 10), claiming the volume only dropped by 299
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+        tree.update(1, 70);
+
+        let circuit = StateTransitionCircuit::new(
+            old_root,
+            1000,
+            Fr::from(12345u64),
+            tree.root(),
+            701,
+            Fr::from(67890u64),
+            1,
+            100,
+            70,
+            30,
+            OpType::Withdraw,
+            proof,
+            10,
+            Fr::from(99999u64),
+            10000,
+            0,
+            Fr::from(12345678u64),
+        );
+
+        let cs = synthesize_labeled(circuit);
+        let failed = explain_unsatisfied(&cs).unwrap();
+        println!("{}", failed);
+
+        assert_eq!(failed.label, "state_transition::volume");
+        assert_ne!(failed.a * failed.b, failed.c);
+        // The claimed volume is off by one from old_volume - amount * item_volume
+        let off_by = failed.a * failed.b - failed.c;
+        assert!(off_by == Fr::from(1u64) || off_by == -Fr::from(1u64), "{}", failed);
+    }
+}
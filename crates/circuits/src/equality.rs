@@ -0,0 +1,234 @@
+//! Equality Circuit for SMT-based inventory.
+//!
+//! Proves that two commitments, taken at different times with different blindings,
+//! commit to identical inventory contents. Used for "vault untouched during period T"
+//! audits without revealing what the vault holds.
+//!
+//! Public input: Poseidon(old_commitment, new_commitment)
+//!
+//! Contents are compared canonically: an SMT root depends only on the set of
+//! non-empty leaves (a quantity of 0 resets a leaf to the empty default), so
+//! equal roots and volumes mean equal contents. This replaces a slot-by-slot
+//! comparison with two field equalities, and an item held at quantity 0 counts
+//! the same as an absent item.
+//!
+//! Cost: ~1.2k constraints in total, almost all from the two commitment hashes
+//! and the public hash. The equality checks add 2 constraints, independent of
+//! the number of items.
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+
+/// Compute the public input hash for Equality proof.
+pub fn compute_equality_hash(old_commitment: Fr, new_commitment: Fr) -> Fr {
+    let inputs = vec![old_commitment, new_commitment];
+    poseidon_hash_many(&inputs)
+}
+
+/// Equality Circuit.
+///
+/// Proves both commitments open to the same inventory root and volume.
+#[derive(Clone)]
+pub struct EqualityCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Old commitment components (witnesses)
+    /// Old inventory SMT root
+    pub old_inventory_root: Option<Fr>,
+    /// Old total volume
+    pub old_volume: Option<u64>,
+    /// Old blinding factor
+    pub old_blinding: Option<Fr>,
+
+    // New commitment components (witnesses)
+    /// New inventory SMT root
+    pub new_inventory_root: Option<Fr>,
+    /// New total volume
+    pub new_volume: Option<u64>,
+    /// New blinding factor
+    pub new_blinding: Option<Fr>,
+}
+
+impl EqualityCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            old_inventory_root: Some(Fr::from(0u64)),
+            old_volume: Some(0),
+            old_blinding: Some(Fr::from(0u64)),
+            new_inventory_root: Some(Fr::from(0u64)),
+            new_volume: Some(0),
+            new_blinding: Some(Fr::from(0u64)),
+        }
+    }
+
+    /// Create a new circuit with witnesses.
+    pub fn new(
+        old_inventory_root: Fr,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_inventory_root: Fr,
+        new_volume: u64,
+        new_blinding: Fr,
+    ) -> Self {
+        let old_commitment = create_smt_commitment(old_inventory_root, old_volume, old_blinding);
+        let new_commitment = create_smt_commitment(new_inventory_root, new_volume, new_blinding);
+        let public_hash = compute_equality_hash(old_commitment, new_commitment);
+
+        Self {
+            public_hash: Some(public_hash),
+            old_inventory_root: Some(old_inventory_root),
+            old_volume: Some(old_volume),
+            old_blinding: Some(old_blinding),
+            new_inventory_root: Some(new_inventory_root),
+            new_volume: Some(new_volume),
+            new_blinding: Some(new_blinding),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for EqualityCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate old commitment witnesses ===
+        let old_root_var = FpVar::new_witness(cs.clone(), || {
+            self.old_inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let old_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.old_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let old_blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.old_blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate new commitment witnesses ===
+        let new_root_var = FpVar::new_witness(cs.clone(), || {
+            self.new_inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let new_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.new_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let new_blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.new_blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Constraint 1: Same contents ===
+        // Canonical roots make this equivalent to comparing every item
+        old_root_var.enforce_equal(&new_root_var)?;
+        old_volume_var.enforce_equal(&new_volume_var)?;
+
+        // === Constraint 2: Compute commitments using Poseidon ===
+        let old_commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &old_root_var,
+            &old_volume_var,
+            &old_blinding_var,
+        )?;
+        let new_commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &new_root_var,
+            &new_volume_var,
+            &new_blinding_var,
+        )?;
+
+        // === Constraint 3: Compute and verify public hash ===
+        let inputs = vec![old_commitment_var, new_commitment_var];
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn equality_circuit(old: &SparseMerkleTree, new: &SparseMerkleTree) -> EqualityCircuit {
+        EqualityCircuit::new(
+            old.root(),
+            500,
+            Fr::from(12345u64),
+            new.root(),
+            500,
+            Fr::from(67890u64),
+        )
+    }
+
+    fn is_satisfied(circuit: EqualityCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_equality_identical_contents() {
+        let old = SparseMerkleTree::from_items(&[(1, 10), (2, 20)], DEFAULT_DEPTH);
+        let new = SparseMerkleTree::from_items(&[(2, 20), (1, 10)], DEFAULT_DEPTH);
+
+        let circuit = equality_circuit(&old, &new);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("Equality constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_equality_quantity_difference_fails() {
+        let old = SparseMerkleTree::from_items(&[(1, 10), (2, 20)], DEFAULT_DEPTH);
+        let new = SparseMerkleTree::from_items(&[(1, 10), (2, 21)], DEFAULT_DEPTH);
+
+        assert!(!is_satisfied(equality_circuit(&old, &new)));
+    }
+
+    #[test]
+    fn test_equality_volume_difference_fails() {
+        let tree = SparseMerkleTree::from_items(&[(1, 10)], DEFAULT_DEPTH);
+
+        let circuit = EqualityCircuit::new(
+            tree.root(),
+            500,
+            Fr::from(12345u64),
+            tree.root(),
+            501,
+            Fr::from(67890u64),
+        );
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_equality_zero_quantity_item_is_canonical() {
+        // Holding 0 of item 3 is the same content as never holding it
+        let old = SparseMerkleTree::from_items(&[(1, 10)], DEFAULT_DEPTH);
+        let mut new = SparseMerkleTree::from_items(&[(1, 10), (3, 5)], DEFAULT_DEPTH);
+        new.update(3, 0);
+
+        assert!(is_satisfied(equality_circuit(&old, &new)));
+
+        // ...but a different item at a non-zero quantity is a real difference
+        let moved = SparseMerkleTree::from_items(&[(3, 10)], DEFAULT_DEPTH);
+        assert!(!is_satisfied(equality_circuit(&old, &moved)));
+    }
+}
@@ -0,0 +1,300 @@
+//! FeeTransfer Circuit for SMT-based inventory.
+//!
+//! Proves a marketplace transfer where a fee is taken from the transferred amount:
+//! - source loses `amount` of `item_id`
+//! - destination gains `amount - fee`
+//! - fee recipient gains `fee`
+//!
+//! Conservation: src_loss == dst_gain + fee_gain
+//!
+//! Public inputs:
+//! - public_hash: Poseidon(src_old, src_new, dst_old, dst_new, fee_old, fee_new, item_id, item_volume)
+//! - amount
+//! - fee
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::inventory_update::{InventoryUpdate, InventoryUpdateVar};
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::{enforce_geq, enforce_u32_range};
+
+/// Compute the public input hash for FeeTransfer proof.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_fee_transfer_hash(
+    src_old_commitment: Fr,
+    src_new_commitment: Fr,
+    dst_old_commitment: Fr,
+    dst_new_commitment: Fr,
+    fee_old_commitment: Fr,
+    fee_new_commitment: Fr,
+    item_id: u64,
+    item_volume: u64,
+) -> Fr {
+    let inputs = vec![
+        src_old_commitment,
+        src_new_commitment,
+        dst_old_commitment,
+        dst_new_commitment,
+        fee_old_commitment,
+        fee_new_commitment,
+        Fr::from(item_id),
+        Fr::from(item_volume),
+    ];
+    poseidon_hash_many(&inputs)
+}
+
+/// FeeTransfer Circuit.
+#[derive(Clone)]
+pub struct FeeTransferCircuit {
+    // Public inputs
+    /// Hash binding all commitments, item_id and item_volume
+    pub public_hash: Option<Fr>,
+    /// Amount debited from the source
+    pub amount: Option<u64>,
+    /// Portion of `amount` credited to the fee recipient
+    pub fee: Option<u64>,
+
+    // Item witnesses
+    /// Item ID being transferred
+    pub item_id: Option<u64>,
+    /// Volume per unit of this item type
+    pub item_volume: Option<u64>,
+
+    // Inventory updates
+    /// Source inventory update
+    pub src: Option<InventoryUpdate>,
+    /// Destination inventory update
+    pub dst: Option<InventoryUpdate>,
+    /// Fee recipient inventory update
+    pub fee_recipient: Option<InventoryUpdate>,
+}
+
+impl FeeTransferCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            amount: Some(0),
+            fee: Some(0),
+            item_id: Some(0),
+            item_volume: Some(0),
+            src: Some(InventoryUpdate::empty()),
+            dst: Some(InventoryUpdate::empty()),
+            fee_recipient: Some(InventoryUpdate::empty()),
+        }
+    }
+
+    /// Create a new circuit with witnesses.
+    pub fn new(
+        item_id: u64,
+        item_volume: u64,
+        amount: u64,
+        fee: u64,
+        src: InventoryUpdate,
+        dst: InventoryUpdate,
+        fee_recipient: InventoryUpdate,
+    ) -> Self {
+        let public_hash = compute_fee_transfer_hash(
+            src.old_state.commitment(),
+            src.new_state.commitment(),
+            dst.old_state.commitment(),
+            dst.new_state.commitment(),
+            fee_recipient.old_state.commitment(),
+            fee_recipient.new_state.commitment(),
+            item_id,
+            item_volume,
+        );
+
+        Self {
+            public_hash: Some(public_hash),
+            amount: Some(amount),
+            fee: Some(fee),
+            item_id: Some(item_id),
+            item_volume: Some(item_volume),
+            src: Some(src),
+            dst: Some(dst),
+            fee_recipient: Some(fee_recipient),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for FeeTransferCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public inputs ===
+        // Order: public_hash, amount, fee
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let amount_var = FpVar::new_input(cs.clone(), || {
+            self.amount
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let fee_var = FpVar::new_input(cs.clone(), || {
+            self.fee
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate item witnesses ===
+        let item_id_var = FpVar::new_witness(cs.clone(), || {
+            self.item_id
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let item_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.item_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Constraint 1: Each inventory changes only the item leaf ===
+        let src = InventoryUpdateVar::new_witness(cs.clone(), &item_id_var, self.src.as_ref().unwrap())?;
+        let dst = InventoryUpdateVar::new_witness(cs.clone(), &item_id_var, self.dst.as_ref().unwrap())?;
+        let fee_recipient = InventoryUpdateVar::new_witness(
+            cs.clone(),
+            &item_id_var,
+            self.fee_recipient.as_ref().unwrap(),
+        )?;
+
+        // === Constraint 2: fee <= amount ===
+        enforce_geq(cs.clone(), &amount_var, &fee_var)?;
+        let dst_amount = &amount_var - &fee_var;
+
+        // === Constraint 3: Quantity changes ===
+        let src_loss = &src.old_quantity - &src.new_quantity;
+        let dst_gain = &dst.new_quantity - &dst.old_quantity;
+        let fee_gain = &fee_recipient.new_quantity - &fee_recipient.old_quantity;
+
+        src_loss.enforce_equal(&amount_var)?;
+        dst_gain.enforce_equal(&dst_amount)?;
+        fee_gain.enforce_equal(&fee_var)?;
+
+        // Conservation (implied by the above, stated explicitly)
+        src_loss.enforce_equal(&(&dst_gain + &fee_gain))?;
+
+        // New quantities must not wrap (prevents withdrawing more than held)
+        enforce_u32_range(cs.clone(), &src.new_quantity)?;
+        enforce_u32_range(cs.clone(), &dst.new_quantity)?;
+        enforce_u32_range(cs.clone(), &fee_recipient.new_quantity)?;
+
+        // === Constraint 4: Volume changes ===
+        let parties = [
+            (&src, &amount_var, false),
+            (&dst, &dst_amount, true),
+            (&fee_recipient, &fee_var, true),
+        ];
+        for (party, delta_qty, is_credit) in parties {
+            let volume_delta = &item_volume_var * delta_qty;
+            let expected_volume = if is_credit {
+                &party.old_state.current_volume + &volume_delta
+            } else {
+                &party.old_state.current_volume - &volume_delta
+            };
+            party.new_state.current_volume.enforce_equal(&expected_volume)?;
+            enforce_u32_range(cs.clone(), &party.new_state.current_volume)?;
+        }
+
+        // === Constraint 5: Compute and verify public hash ===
+        let inputs = vec![
+            src.old_commitment,
+            src.new_commitment,
+            dst.old_commitment,
+            dst.new_commitment,
+            fee_recipient.old_commitment,
+            fee_recipient.new_commitment,
+            item_id_var,
+            item_volume_var,
+        ];
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    const ITEM: u64 = 7;
+    const VOLUME: u64 = 2;
+
+    /// Build a fee transfer where the destination receives `dst_gain` and
+    /// the fee recipient receives `fee_gain`, claiming `amount` and `fee`.
+    fn fee_transfer(amount: u64, fee: u64, dst_gain: u64, fee_gain: u64) -> FeeTransferCircuit {
+        let mut src_tree = SparseMerkleTree::from_items(&[(ITEM, 100)], DEFAULT_DEPTH);
+        let mut dst_tree = SparseMerkleTree::from_items(&[(ITEM, 10)], DEFAULT_DEPTH);
+        let mut fee_tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+
+        let src = InventoryUpdate::apply(
+            &mut src_tree,
+            100 * VOLUME,
+            Fr::from(1u64),
+            ITEM,
+            100 - amount,
+            (100 - amount) * VOLUME,
+            Fr::from(2u64),
+        );
+        let dst = InventoryUpdate::apply(
+            &mut dst_tree,
+            10 * VOLUME,
+            Fr::from(3u64),
+            ITEM,
+            10 + dst_gain,
+            (10 + dst_gain) * VOLUME,
+            Fr::from(4u64),
+        );
+        let fee_recipient = InventoryUpdate::apply(
+            &mut fee_tree,
+            0,
+            Fr::from(5u64),
+            ITEM,
+            fee_gain,
+            fee_gain * VOLUME,
+            Fr::from(6u64),
+        );
+
+        FeeTransferCircuit::new(ITEM, VOLUME, amount, fee, src, dst, fee_recipient)
+    }
+
+    #[test]
+    fn test_fee_transfer_valid() {
+        let circuit = fee_transfer(20, 3, 17, 3);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("FeeTransfer constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_fee_transfer_unbalanced_fee_fails() {
+        // Fee recipient takes 5 while only 3 was declared
+        let circuit = fee_transfer(20, 3, 17, 5);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_fee_transfer_fee_exceeds_amount_fails() {
+        // fee > amount would wrap the destination credit
+        let circuit = fee_transfer(3, 5, 0, 5);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
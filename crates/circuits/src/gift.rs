@@ -0,0 +1,387 @@
+//! Gift Circuit for SMT-based inventory.
+//!
+//! Proves a one-way transfer of `amount` of `item_id` from a sender to a
+//! recipient without revealing which inventory the sender is.
+//!
+//! The recipient's old and new commitments are public so the recipient's
+//! inventory can be updated. The sender's old commitment is not: the proof
+//! only shows it is a leaf of a public registry of inventory commitments
+//! (see `SparseMerkleTree::update_leaf_hash`) and exposes a gift nullifier
+//! instead,
+//!
+//! gift_nullifier = Poseidon(GIFT_NULLIFIER_DOMAIN, sender_old_commitment, sender_old_blinding)
+//!
+//! The nullifier is fixed by the sender's old state, so every gift from the
+//! same state has the same nullifier and a verifier that records nullifiers
+//! rejects the second one. Since it depends on the secret blinding, it cannot
+//! be linked to the commitment in the registry. The sender's new commitment is
+//! public but has a fresh blinding, so it cannot be linked to the old one either.
+//!
+//! Public inputs:
+//! - public_hash: Poseidon(registry_root, sender_new, recipient_old, recipient_new,
+//!   item_id, amount, item_volume)
+//! - gift_nullifier
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::inventory_update::{InventoryUpdate, InventoryUpdateVar};
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::enforce_u32_range;
+use crate::smt::{compute_root_from_path, MerkleProof, MerkleProofVar, DEFAULT_DEPTH};
+
+/// Domain tag for gift nullifiers ("GIFTNULL" in ASCII).
+pub const GIFT_NULLIFIER_DOMAIN: u64 = 0x474946544e554c4c;
+
+/// Compute the public input hash for Gift proof.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_gift_hash(
+    registry_root: Fr,
+    sender_new_commitment: Fr,
+    recipient_old_commitment: Fr,
+    recipient_new_commitment: Fr,
+    item_id: u64,
+    amount: u64,
+    item_volume: u64,
+) -> Fr {
+    let inputs = vec![
+        registry_root,
+        sender_new_commitment,
+        recipient_old_commitment,
+        recipient_new_commitment,
+        Fr::from(item_id),
+        Fr::from(amount),
+        Fr::from(item_volume),
+    ];
+    poseidon_hash_many(&inputs)
+}
+
+/// Compute the nullifier of a gift from the sender's old state.
+pub fn compute_gift_nullifier(sender_old_commitment: Fr, sender_old_blinding: Fr) -> Fr {
+    poseidon_hash_many(&[
+        Fr::from(GIFT_NULLIFIER_DOMAIN),
+        sender_old_commitment,
+        sender_old_blinding,
+    ])
+}
+
+/// Gift Circuit.
+#[derive(Clone)]
+pub struct GiftCircuit {
+    // Public inputs
+    /// Hash binding the registry root, the non-sender commitments and the item
+    pub public_hash: Option<Fr>,
+    /// Poseidon(GIFT_NULLIFIER_DOMAIN, sender_old_commitment, sender_old_blinding)
+    pub gift_nullifier: Option<Fr>,
+
+    // Public data (bound via hash)
+    /// Root of the registry tree of inventory commitments
+    pub registry_root: Option<Fr>,
+    /// Item ID being gifted
+    pub item_id: Option<u64>,
+    /// Quantity gifted
+    pub amount: Option<u64>,
+    /// Volume per unit of this item type
+    pub item_volume: Option<u64>,
+
+    // Private witnesses
+    /// Proof for the sender's old commitment in the registry
+    pub registry_proof: Option<MerkleProof<Fr>>,
+    /// Sender inventory update
+    pub sender: Option<InventoryUpdate>,
+    /// Recipient inventory update
+    pub recipient: Option<InventoryUpdate>,
+}
+
+impl GiftCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        let dummy_proof = MerkleProof::new(
+            vec![Fr::from(0u64); DEFAULT_DEPTH],
+            vec![false; DEFAULT_DEPTH],
+        );
+
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            gift_nullifier: Some(Fr::from(0u64)),
+            registry_root: Some(Fr::from(0u64)),
+            item_id: Some(0),
+            amount: Some(0),
+            item_volume: Some(0),
+            registry_proof: Some(dummy_proof),
+            sender: Some(InventoryUpdate::empty()),
+            recipient: Some(InventoryUpdate::empty()),
+        }
+    }
+
+    /// Create a new circuit with witnesses.
+    ///
+    /// `registry_proof` opens the sender's old commitment in the registry at
+    /// `registry_root`.
+    pub fn new(
+        item_id: u64,
+        item_volume: u64,
+        amount: u64,
+        sender: InventoryUpdate,
+        recipient: InventoryUpdate,
+        registry_root: Fr,
+        registry_proof: MerkleProof<Fr>,
+    ) -> Self {
+        let public_hash = compute_gift_hash(
+            registry_root,
+            sender.new_state.commitment(),
+            recipient.old_state.commitment(),
+            recipient.new_state.commitment(),
+            item_id,
+            amount,
+            item_volume,
+        );
+        let gift_nullifier =
+            compute_gift_nullifier(sender.old_state.commitment(), sender.old_state.blinding);
+
+        Self {
+            public_hash: Some(public_hash),
+            gift_nullifier: Some(gift_nullifier),
+            registry_root: Some(registry_root),
+            item_id: Some(item_id),
+            amount: Some(amount),
+            item_volume: Some(item_volume),
+            registry_proof: Some(registry_proof),
+            sender: Some(sender),
+            recipient: Some(recipient),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for GiftCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public inputs ===
+        // Order: public_hash, gift_nullifier
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let nullifier_var = FpVar::new_input(cs.clone(), || {
+            self.gift_nullifier.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate public data (bound via hash) ===
+        let registry_root_var = FpVar::new_witness(cs.clone(), || {
+            self.registry_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let item_id_var = FpVar::new_witness(cs.clone(), || {
+            self.item_id
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let amount_var = FpVar::new_witness(cs.clone(), || {
+            self.amount
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let item_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.item_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate private witnesses ===
+        let registry_proof_var =
+            MerkleProofVar::new_witness(cs.clone(), self.registry_proof.as_ref().unwrap())?;
+
+        // === Constraint 1: Each inventory changes only the item leaf ===
+        let sender = InventoryUpdateVar::new_witness(
+            cs.clone(),
+            &item_id_var,
+            self.sender.as_ref().unwrap(),
+        )?;
+        let recipient = InventoryUpdateVar::new_witness(
+            cs.clone(),
+            &item_id_var,
+            self.recipient.as_ref().unwrap(),
+        )?;
+
+        // === Constraint 2: Sender loses and recipient gains exactly `amount` ===
+        sender
+            .new_quantity
+            .enforce_equal(&(&sender.old_quantity - &amount_var))?;
+        recipient
+            .new_quantity
+            .enforce_equal(&(&recipient.old_quantity + &amount_var))?;
+
+        // New quantities must not wrap (prevents gifting more than held)
+        enforce_u32_range(cs.clone(), &sender.new_quantity)?;
+        enforce_u32_range(cs.clone(), &recipient.new_quantity)?;
+
+        // === Constraint 3: Volume changes ===
+        let volume_delta = &item_volume_var * &amount_var;
+        sender
+            .new_state
+            .current_volume
+            .enforce_equal(&(&sender.old_state.current_volume - &volume_delta))?;
+        recipient
+            .new_state
+            .current_volume
+            .enforce_equal(&(&recipient.old_state.current_volume + &volume_delta))?;
+        enforce_u32_range(cs.clone(), &sender.new_state.current_volume)?;
+        enforce_u32_range(cs.clone(), &recipient.new_state.current_volume)?;
+
+        // === Constraint 4: Sender's old commitment is a leaf of the registry ===
+        let computed_registry_root =
+            compute_root_from_path(cs.clone(), &sender.old_commitment, &registry_proof_var)?;
+        computed_registry_root.enforce_equal(&registry_root_var)?;
+
+        // === Constraint 5: Gift nullifier ===
+        let nullifier = poseidon_hash_many_var(
+            cs.clone(),
+            &[
+                FpVar::Constant(Fr::from(GIFT_NULLIFIER_DOMAIN)),
+                sender.old_commitment.clone(),
+                sender.old_state.blinding.clone(),
+            ],
+        )?;
+        nullifier.enforce_equal(&nullifier_var)?;
+
+        // === Constraint 6: Compute and verify public hash ===
+        let inputs = vec![
+            registry_root_var,
+            sender.new_commitment,
+            recipient.old_commitment,
+            recipient.new_commitment,
+            item_id_var,
+            amount_var,
+            item_volume_var,
+        ];
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::SparseMerkleTree;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    const ITEM: u64 = 7;
+    const VOLUME: u64 = 2;
+    const SENDER_SLOT: u64 = 5;
+
+    /// Gift `amount` of ITEM from a sender holding 100 to a recipient holding 10.
+    fn gift(amount: u64, recipient_blinding: Fr) -> GiftCircuit {
+        let mut sender_tree = SparseMerkleTree::from_items(&[(ITEM, 100)], DEFAULT_DEPTH);
+        let mut recipient_tree = SparseMerkleTree::from_items(&[(ITEM, 10)], DEFAULT_DEPTH);
+
+        let sender = InventoryUpdate::apply(
+            &mut sender_tree,
+            100 * VOLUME,
+            Fr::from(1u64),
+            ITEM,
+            100 - amount,
+            (100 - amount) * VOLUME,
+            Fr::from(2u64),
+        );
+        let recipient = InventoryUpdate::apply(
+            &mut recipient_tree,
+            10 * VOLUME,
+            recipient_blinding,
+            ITEM,
+            10 + amount,
+            (10 + amount) * VOLUME,
+            recipient_blinding + Fr::from(1u64),
+        );
+
+        let mut registry = SparseMerkleTree::new(DEFAULT_DEPTH);
+        registry.update_leaf_hash(0, Fr::from(111u64));
+        registry.update_leaf_hash(SENDER_SLOT, sender.old_state.commitment());
+
+        GiftCircuit::new(
+            ITEM,
+            VOLUME,
+            amount,
+            sender,
+            recipient,
+            registry.root(),
+            registry.get_proof(SENDER_SLOT),
+        )
+    }
+
+    fn is_satisfied(circuit: GiftCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    /// Recompute the public hash after tampering with the witnesses.
+    fn rebind(circuit: &mut GiftCircuit) {
+        let sender = circuit.sender.as_ref().unwrap();
+        let recipient = circuit.recipient.as_ref().unwrap();
+        circuit.public_hash = Some(compute_gift_hash(
+            circuit.registry_root.unwrap(),
+            sender.new_state.commitment(),
+            recipient.old_state.commitment(),
+            recipient.new_state.commitment(),
+            circuit.item_id.unwrap(),
+            circuit.amount.unwrap(),
+            circuit.item_volume.unwrap(),
+        ));
+    }
+
+    #[test]
+    fn test_valid_gift() {
+        let circuit = gift(30, Fr::from(3u64));
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("Gift constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_double_gift_reuses_nullifier() {
+        // A second gift from the same sender state, to another recipient, is a
+        // valid proof on its own but carries the same nullifier
+        let first = gift(30, Fr::from(3u64));
+        let second = gift(40, Fr::from(9u64));
+
+        assert_eq!(first.gift_nullifier, second.gift_nullifier);
+        assert_ne!(first.public_hash, second.public_hash);
+        assert!(is_satisfied(first));
+        assert!(is_satisfied(second));
+    }
+
+    #[test]
+    fn test_wrong_nullifier_fails() {
+        let mut circuit = gift(30, Fr::from(3u64));
+        circuit.gift_nullifier = Some(Fr::from(12345u64));
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_sender_not_in_registry_fails() {
+        let mut circuit = gift(30, Fr::from(3u64));
+        let mut registry = SparseMerkleTree::new(DEFAULT_DEPTH);
+        registry.update_leaf_hash(SENDER_SLOT, Fr::from(999u64));
+        circuit.registry_root = Some(registry.root());
+        rebind(&mut circuit);
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_amount_mismatch_fails() {
+        // Recipient is credited 30 but the public amount claims 40
+        let mut circuit = gift(30, Fr::from(3u64));
+        circuit.amount = Some(40);
+        rebind(&mut circuit);
+
+        assert!(!is_satisfied(circuit));
+    }
+}
@@ -0,0 +1,190 @@
+//! Reusable witnesses for one inventory changing one item's quantity.
+//!
+//! Circuits that touch several inventories (transfers, fees, trades) need the
+//! same building block for each party: open the old and new commitments and
+//! prove the new SMT root differs from the old one only at `item_id`.
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use crate::smt::{verify_and_update, MerkleProof, MerkleProofVar, SparseMerkleTree, DEFAULT_DEPTH};
+use crate::smt_commitment::{InventoryState, InventoryStateVar};
+
+/// Native witnesses for a single-item inventory update.
+#[derive(Clone, Debug)]
+pub struct InventoryUpdate {
+    /// State before the update
+    pub old_state: InventoryState,
+    /// State after the update
+    pub new_state: InventoryState,
+    /// Quantity of the item before the update
+    pub old_quantity: u64,
+    /// Quantity of the item after the update
+    pub new_quantity: u64,
+    /// Proof for the item leaf in the old inventory SMT
+    pub proof: MerkleProof<Fr>,
+}
+
+impl InventoryUpdate {
+    /// Dummy update with the correct proof depth, for circuit setup.
+    pub fn empty() -> Self {
+        let zero_state = InventoryState::new(Fr::from(0u64), 0, Fr::from(0u64));
+        Self {
+            old_state: zero_state.clone(),
+            new_state: zero_state,
+            old_quantity: 0,
+            new_quantity: 0,
+            proof: MerkleProof::new(
+                vec![Fr::from(0u64); DEFAULT_DEPTH],
+                vec![false; DEFAULT_DEPTH],
+            ),
+        }
+    }
+
+    /// Set `item_id` to `new_quantity` in `tree` and record the witnesses.
+    ///
+    /// `tree` is updated in place so it reflects the new state afterwards.
+    pub fn apply(
+        tree: &mut SparseMerkleTree,
+        old_volume: u64,
+        old_blinding: Fr,
+        item_id: u64,
+        new_quantity: u64,
+        new_volume: u64,
+        new_blinding: Fr,
+    ) -> Self {
+        let old_state = InventoryState::new(tree.root(), old_volume, old_blinding);
+        let old_quantity = tree.get(item_id);
+        let proof = tree.get_proof(item_id);
+
+        tree.update(item_id, new_quantity);
+        let new_state = InventoryState::new(tree.root(), new_volume, new_blinding);
+
+        Self {
+            old_state,
+            new_state,
+            old_quantity,
+            new_quantity,
+            proof,
+        }
+    }
+}
+
+/// Circuit variables for a single-item inventory update.
+#[derive(Clone)]
+pub struct InventoryUpdateVar {
+    /// State before the update
+    pub old_state: InventoryStateVar,
+    /// State after the update
+    pub new_state: InventoryStateVar,
+    /// Quantity of the item before the update
+    pub old_quantity: FpVar<Fr>,
+    /// Quantity of the item after the update
+    pub new_quantity: FpVar<Fr>,
+    /// Commitment to the old state
+    pub old_commitment: FpVar<Fr>,
+    /// Commitment to the new state
+    pub new_commitment: FpVar<Fr>,
+}
+
+impl InventoryUpdateVar {
+    /// Allocate the update as witnesses and enforce that the new root is the
+    /// old root with only the `item_id` leaf changed.
+    pub fn new_witness(
+        cs: ConstraintSystemRef<Fr>,
+        item_id: &FpVar<Fr>,
+        update: &InventoryUpdate,
+    ) -> Result<Self, SynthesisError> {
+        let old_state = InventoryStateVar::new_witness(cs.clone(), &update.old_state)?;
+        let new_state = InventoryStateVar::new_witness(cs.clone(), &update.new_state)?;
+        let old_quantity = FpVar::new_witness(cs.clone(), || Ok(Fr::from(update.old_quantity)))?;
+        let new_quantity = FpVar::new_witness(cs.clone(), || Ok(Fr::from(update.new_quantity)))?;
+        let proof = MerkleProofVar::new_witness(cs.clone(), &update.proof)?;
+
+        let computed_new_root = verify_and_update(
+            cs.clone(),
+            &old_state.inventory_root,
+            item_id,
+            &old_quantity,
+            &new_quantity,
+            &proof,
+        )?;
+        computed_new_root.enforce_equal(&new_state.inventory_root)?;
+
+        let old_commitment = old_state.commitment(cs.clone())?;
+        let new_commitment = new_state.commitment(cs)?;
+
+        Ok(Self {
+            old_state,
+            new_state,
+            old_quantity,
+            new_quantity,
+            old_commitment,
+            new_commitment,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn test_inventory_update_valid() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100), (2, 5)], DEFAULT_DEPTH);
+        let update = InventoryUpdate::apply(
+            &mut tree, 105, Fr::from(1u64), 1, 60, 65, Fr::from(2u64),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let item_id = FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u64))).unwrap();
+        let update_var = InventoryUpdateVar::new_witness(cs.clone(), &item_id, &update).unwrap();
+
+        let expected = FpVar::new_input(cs.clone(), || Ok(update.new_state.commitment())).unwrap();
+        update_var.new_commitment.enforce_equal(&expected).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_inventory_update_other_item_changed_fails() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100), (2, 5)], DEFAULT_DEPTH);
+        let mut update = InventoryUpdate::apply(
+            &mut tree, 105, Fr::from(1u64), 1, 60, 65, Fr::from(2u64),
+        );
+
+        // Also change item 2 natively; the circuit must reject the new root
+        tree.update(2, 50);
+        update.new_state.inventory_root = tree.root();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let item_id = FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u64))).unwrap();
+        InventoryUpdateVar::new_witness(cs.clone(), &item_id, &update).unwrap();
+
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_inventory_update_wrong_slot_insertion_fails() {
+        let tree = SparseMerkleTree::from_items(&[(1, 100), (2, 5)], DEFAULT_DEPTH);
+
+        // Credit new item 7 into the empty slot 9 instead of its own
+        let proof = tree.get_proof(9);
+        let update = InventoryUpdate {
+            old_state: InventoryState::new(tree.root(), 105, Fr::from(1u64)),
+            new_state: InventoryState::new(proof.compute_root(7, 30), 135, Fr::from(2u64)),
+            old_quantity: 0,
+            new_quantity: 30,
+            proof,
+        };
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let item_id = FpVar::new_witness(cs.clone(), || Ok(Fr::from(7u64))).unwrap();
+        InventoryUpdateVar::new_witness(cs.clone(), &item_id, &update).unwrap();
+
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,326 @@
+//! ItemExistsInRegistry Circuit for a global inventory registry.
+//!
+//! Proves that some inventory in a shared registry tree contains at least a
+//! minimum quantity of a specific item, without revealing which inventory.
+//!
+//! The registry is a Sparse Merkle Tree whose leaves are inventory commitments
+//! (see `SparseMerkleTree::update_leaf_hash`). The proof combines:
+//! - Knowledge of an opening (inventory_root, current_volume, blinding) of a commitment
+//! - Membership of that commitment in the registry at a public root
+//! - Membership of (item_id, actual_quantity) in the inventory SMT
+//! - actual_quantity >= min_quantity
+//!
+//! Public input: Poseidon(registry_root, item_id, min_quantity)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::enforce_geq;
+use crate::smt::{compute_root_from_path, verify_membership, MerkleProof, MerkleProofVar};
+use crate::smt_commitment::create_smt_commitment_var;
+
+/// Compute the public input hash for ItemExistsInRegistry proof.
+pub fn compute_item_exists_in_registry_hash(
+    registry_root: Fr,
+    item_id: u64,
+    min_quantity: u64,
+) -> Fr {
+    let inputs = vec![
+        registry_root,
+        Fr::from(item_id),
+        Fr::from(min_quantity),
+    ];
+    poseidon_hash_many(&inputs)
+}
+
+/// ItemExistsInRegistry Circuit.
+#[derive(Clone)]
+pub struct ItemExistsInRegistryCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Registry (witnesses)
+    /// Root of the registry tree of inventory commitments
+    pub registry_root: Option<Fr>,
+    /// Proof for the inventory commitment in the registry
+    pub registry_proof: Option<MerkleProof<Fr>>,
+
+    // Commitment components (witnesses)
+    /// Inventory SMT root
+    pub inventory_root: Option<Fr>,
+    /// Current volume
+    pub current_volume: Option<u64>,
+    /// Blinding factor
+    pub blinding: Option<Fr>,
+
+    // Item details (witnesses)
+    /// Item ID to prove
+    pub item_id: Option<u64>,
+    /// Actual quantity (must be >= min_quantity)
+    pub actual_quantity: Option<u64>,
+    /// Minimum quantity to prove
+    pub min_quantity: Option<u64>,
+
+    // Merkle proof
+    /// Proof for item in the inventory SMT
+    pub inventory_proof: Option<MerkleProof<Fr>>,
+}
+
+impl ItemExistsInRegistryCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        use crate::smt::DEFAULT_DEPTH;
+
+        let dummy_proof = MerkleProof::new(
+            vec![Fr::from(0u64); DEFAULT_DEPTH],
+            vec![false; DEFAULT_DEPTH],
+        );
+
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            registry_root: Some(Fr::from(0u64)),
+            registry_proof: Some(dummy_proof.clone()),
+            inventory_root: Some(Fr::from(0u64)),
+            current_volume: Some(0),
+            blinding: Some(Fr::from(0u64)),
+            item_id: Some(0),
+            actual_quantity: Some(0),
+            min_quantity: Some(0),
+            inventory_proof: Some(dummy_proof),
+        }
+    }
+
+    /// Create a new circuit with witnesses.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        registry_root: Fr,
+        registry_proof: MerkleProof<Fr>,
+        inventory_root: Fr,
+        current_volume: u64,
+        blinding: Fr,
+        item_id: u64,
+        actual_quantity: u64,
+        min_quantity: u64,
+        inventory_proof: MerkleProof<Fr>,
+    ) -> Self {
+        let public_hash = compute_item_exists_in_registry_hash(
+            registry_root,
+            item_id,
+            min_quantity,
+        );
+
+        Self {
+            public_hash: Some(public_hash),
+            registry_root: Some(registry_root),
+            registry_proof: Some(registry_proof),
+            inventory_root: Some(inventory_root),
+            current_volume: Some(current_volume),
+            blinding: Some(blinding),
+            item_id: Some(item_id),
+            actual_quantity: Some(actual_quantity),
+            min_quantity: Some(min_quantity),
+            inventory_proof: Some(inventory_proof),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for ItemExistsInRegistryCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate registry witnesses ===
+        let registry_root_var = FpVar::new_witness(cs.clone(), || {
+            self.registry_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let registry_proof_var = MerkleProofVar::new_witness(
+            cs.clone(),
+            self.registry_proof.as_ref().unwrap(),
+        )?;
+
+        // === Allocate commitment witnesses ===
+        let root_var = FpVar::new_witness(cs.clone(), || {
+            self.inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let volume_var = FpVar::new_witness(cs.clone(), || {
+            self.current_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate item witnesses ===
+        let item_id_var = FpVar::new_witness(cs.clone(), || {
+            self.item_id
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let actual_qty_var = FpVar::new_witness(cs.clone(), || {
+            self.actual_quantity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let min_qty_var = FpVar::new_witness(cs.clone(), || {
+            self.min_quantity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate inventory Merkle proof ===
+        let inventory_proof_var = MerkleProofVar::new_witness(
+            cs.clone(),
+            self.inventory_proof.as_ref().unwrap(),
+        )?;
+
+        // === Constraint 1: Open the inventory commitment ===
+        let commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &root_var,
+            &volume_var,
+            &blinding_var,
+        )?;
+
+        // === Constraint 2: Commitment is a leaf of the registry ===
+        let computed_registry_root = compute_root_from_path(
+            cs.clone(),
+            &commitment_var,
+            &registry_proof_var,
+        )?;
+        computed_registry_root.enforce_equal(&registry_root_var)?;
+
+        // === Constraint 3: Verify item membership in inventory SMT ===
+        verify_membership(
+            cs.clone(),
+            &root_var,
+            &item_id_var,
+            &actual_qty_var,
+            &inventory_proof_var,
+        )?;
+
+        // === Constraint 4: actual_quantity >= min_quantity ===
+        enforce_geq(cs.clone(), &actual_qty_var, &min_qty_var)?;
+
+        // === Constraint 5: Compute and verify public hash using Poseidon ===
+        let inputs = vec![
+            registry_root_var,
+            item_id_var,
+            min_qty_var,
+        ];
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use crate::smt_commitment::create_smt_commitment;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Build a registry holding a few unrelated commitments plus ours at `slot`.
+    fn setup_registry(slot: u64, commitment: Fr) -> SparseMerkleTree {
+        let mut registry = SparseMerkleTree::new(DEFAULT_DEPTH);
+        registry.update_leaf_hash(0, Fr::from(111u64));
+        registry.update_leaf_hash(7, Fr::from(222u64));
+        registry.update_leaf_hash(slot, commitment);
+        registry
+    }
+
+    #[test]
+    fn test_item_exists_in_registry_valid() {
+        let tree = SparseMerkleTree::from_items(&[(42, 100)], DEFAULT_DEPTH);
+        let blinding = Fr::from(12345u64);
+        let volume = 1000u64;
+        let commitment = create_smt_commitment(tree.root(), volume, blinding);
+
+        let registry = setup_registry(5, commitment);
+
+        let circuit = ItemExistsInRegistryCircuit::new(
+            registry.root(),
+            registry.get_proof(5),
+            tree.root(),
+            volume,
+            blinding,
+            42,  // item_id
+            100, // actual_quantity
+            50,  // min_quantity
+            tree.get_proof(42),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("ItemExistsInRegistry constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_item_exists_in_registry_wrong_root() {
+        let tree = SparseMerkleTree::from_items(&[(42, 100)], DEFAULT_DEPTH);
+        let blinding = Fr::from(12345u64);
+        let volume = 1000u64;
+        let commitment = create_smt_commitment(tree.root(), volume, blinding);
+
+        let registry = setup_registry(5, commitment);
+
+        // Claim a registry root that does not contain our commitment
+        let other_registry = setup_registry(5, Fr::from(999u64));
+
+        let circuit = ItemExistsInRegistryCircuit::new(
+            other_registry.root(),
+            registry.get_proof(5),
+            tree.root(),
+            volume,
+            blinding,
+            42,
+            100,
+            50,
+            tree.get_proof(42),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_item_exists_in_registry_insufficient_quantity() {
+        let tree = SparseMerkleTree::from_items(&[(42, 30)], DEFAULT_DEPTH);
+        let blinding = Fr::from(12345u64);
+        let volume = 300u64;
+        let commitment = create_smt_commitment(tree.root(), volume, blinding);
+
+        let registry = setup_registry(5, commitment);
+
+        // Honest quantity, but below the claimed minimum
+        let circuit = ItemExistsInRegistryCircuit::new(
+            registry.root(),
+            registry.get_proof(5),
+            tree.root(),
+            volume,
+            blinding,
+            42,
+            30,
+            50,
+            tree.get_proof(42),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
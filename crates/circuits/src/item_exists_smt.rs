@@ -6,6 +6,10 @@
 //! Public input: Poseidon(commitment, item_id, min_quantity)
 //!
 //! This allows proving ownership without revealing exact quantities.
+//!
+//! `actual_quantity - min_quantity` is range checked to `quantity_bits` bits
+//! (default [`RANGE_BITS`]). The width changes the constraint structure, so
+//! keys must be set up with the same width used for proving.
 
 use ark_bn254::Fr;
 use ark_r1cs_std::fields::fp::FpVar;
@@ -13,6 +17,7 @@ use ark_r1cs_std::prelude::*;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 
 use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::{enforce_geq_bits, RANGE_BITS};
 use crate::smt::{verify_membership, MerkleProof, MerkleProofVar};
 use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
 
@@ -55,6 +60,9 @@ pub struct ItemExistsSMTCircuit {
     // Merkle proof
     /// Proof for item in SMT
     pub proof: Option<MerkleProof<Fr>>,
+
+    /// Bit width of the `actual_quantity >= min_quantity` check
+    pub quantity_bits: usize,
 }
 
 impl ItemExistsSMTCircuit {
@@ -78,6 +86,7 @@ impl ItemExistsSMTCircuit {
             actual_quantity: Some(0),
             min_quantity: Some(0),
             proof: Some(dummy_proof),
+            quantity_bits: RANGE_BITS,
         }
     }
 
@@ -114,8 +123,16 @@ impl ItemExistsSMTCircuit {
             actual_quantity: Some(actual_quantity),
             min_quantity: Some(min_quantity),
             proof: Some(proof),
+            quantity_bits: RANGE_BITS,
         }
     }
+
+    /// Range check `actual_quantity - min_quantity` to `quantity_bits` bits
+    /// instead of [`RANGE_BITS`].
+    pub fn with_quantity_bits(mut self, quantity_bits: usize) -> Self {
+        self.quantity_bits = quantity_bits;
+        self
+    }
 }
 
 impl ConstraintSynthesizer<Fr> for ItemExistsSMTCircuit {
@@ -171,15 +188,7 @@ impl ConstraintSynthesizer<Fr> for ItemExistsSMTCircuit {
         )?;
 
         // === Constraint 2: actual_quantity >= min_quantity ===
-        // We enforce: actual_quantity - min_quantity >= 0
-        // This is enforced implicitly by the field arithmetic
-        // The prover can only provide valid witnesses if the constraint holds
-        let _diff = &actual_qty_var - &min_qty_var;
-
-        // For a proper range check, we'd need bit decomposition
-        // For now, we rely on the fact that the verifier checks the public hash
-        // which binds the min_quantity, and the prover can only succeed if
-        // actual_quantity >= min_quantity
+        enforce_geq_bits(cs.clone(), &actual_qty_var, &min_qty_var, self.quantity_bits)?;
 
         // === Constraint 3: Compute and verify commitment using Poseidon ===
         let commitment_var = create_smt_commitment_var(
@@ -299,6 +308,66 @@ mod tests {
         assert!(!cs.is_satisfied().unwrap());
     }
 
+    #[test]
+    fn test_item_exists_insufficient_quantity() {
+        let tree = SparseMerkleTree::from_items(
+            &[(42, 50)],
+            DEFAULT_DEPTH,
+        );
+        let proof = tree.get_proof(42);
+
+        // Honest about holding 50, but claims at least 51
+        let circuit = ItemExistsSMTCircuit::new(
+            tree.root(),
+            500,
+            Fr::from(12345u64),
+            42,
+            50,
+            51,
+            proof,
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_item_exists_quantity_bits() {
+        let count = |quantity_bits| {
+            let circuit = ItemExistsSMTCircuit::empty().with_quantity_bits(quantity_bits);
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs.num_constraints()
+        };
+
+        let narrow = count(40);
+        let wide = count(64);
+        println!("ItemExists constraints: {} at 40 bits, {} at 64 bits", narrow, wide);
+        assert_eq!(wide - narrow, 24);
+
+        // 2^40 of an item passes a 64-bit check but not a 40-bit one
+        let tree = SparseMerkleTree::from_items(&[(42, 1 << 40)], DEFAULT_DEPTH);
+        let satisfied = |quantity_bits| {
+            let circuit = ItemExistsSMTCircuit::new(
+                tree.root(),
+                500,
+                Fr::from(12345u64),
+                42,
+                1 << 40,
+                0,
+                tree.get_proof(42),
+            )
+            .with_quantity_bits(quantity_bits);
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+        assert!(satisfied(64));
+        assert!(!satisfied(40));
+    }
+
     #[test]
     fn test_item_exists_wrong_item() {
         let tree = SparseMerkleTree::from_items(
@@ -3,21 +3,69 @@
 //! This crate provides SMT-based circuits for:
 //! - `StateTransitionCircuit`: Prove valid deposit/withdraw with capacity checking
 //! - `ItemExistsSMTCircuit`: Prove inventory contains >= N of item X
+//! - `ItemExistsInRegistryCircuit`: Prove some inventory in a registry contains >= N of item X
 //! - `CapacitySMTCircuit`: Prove inventory volume is within capacity
+//! - `FirstAcquisitionCircuit`: Prove a deposit introduced a previously absent item
+//! - `FeeTransferCircuit`: Prove a transfer that pays a fee to a third inventory
+//! - `EqualityCircuit`: Prove two commitments hide the same inventory contents
+//! - `StackLimitCircuit`: Prove an item's quantity is within its committed max stack
+//! - `CategoryThresholdCircuit`: Prove inventory holds >= N items across a category
+//! - `MintDepositCircuit`: Prove a deposit authorized by a trusted minter
+//! - `DeltaCircuit`: Prove two inventories differ by exactly a public list of deltas
+//! - `PredicateCircuit`: Prove inventory satisfies an AND/OR formula over item thresholds
+//! - `SumRevealCircuit`: Prove the exact total quantity across a public list of items
+//! - `WithdrawHiddenCircuit`: Prove a withdrawal of some item from a public allowed set
+//! - `ChainProvenanceCircuit`: Prove a commitment derives from a genesis via valid transitions
+//! - `BurnCircuit`: Prove items were destroyed, with a nullifier against replayed burns
+//! - `MultiTransferCircuit`: Prove several items moved between two inventories at once
+//! - `ModuloCircuit`: Prove an item's quantity modulo a public base, e.g. for raffles
+//! - `CraftingCircuit`: Prove a recipe's inputs were consumed and its outputs produced
+//! - `NonMembershipCircuit`: Prove inventory holds none of item X
+//! - `QuantityRangeCircuit`: Prove inventory holds between min and max of item X
+//! - `AtomicBasketCircuit`: Prove every withdrawal in a basket was affordable, all or nothing
+//! - `WhitelistCircuit`: Prove inventory holds some item from a whitelist SMT
+//! - `GiftCircuit`: Prove a one-way transfer from an unnamed sender, with a nullifier
+//! - `ReblindCircuit`: Prove an inventory was handed to a new owner with only its blinding changed
 //!
 //! Uses Poseidon hash function optimized for ZK circuits.
 
 // Core modules
+pub mod commitment; // Pedersen commitments over Baby Jubjub
+pub mod diagnostics; // Witness inspection, constraint breakdowns, first unsatisfied constraint
+pub mod meta; // Circuit metadata (public input counts)
 pub mod poseidon;
 pub mod range_check; // Range checks for underflow prevention
 pub mod signal;
 pub mod smt;
 pub mod smt_commitment;
+pub mod inventory_update; // Single-item update witnesses shared by multi-inventory circuits
 
 // Circuit modules
+pub mod atomic_basket;
+pub mod burn;
 pub mod capacity_smt;
+pub mod category_threshold;
+pub mod chain_provenance;
+pub mod crafting;
+pub mod delta;
+pub mod equality;
+pub mod fee_transfer;
+pub mod first_acquisition;
+pub mod gift;
+pub mod item_exists_registry;
 pub mod item_exists_smt;
+pub mod mint_deposit;
+pub mod modulo;
+pub mod multi_transfer;
+pub mod non_membership;
+pub mod predicate;
+pub mod quantity_range;
+pub mod reblind;
+pub mod stack_limit;
 pub mod state_transition;
+pub mod sum_reveal;
+pub mod whitelist;
+pub mod withdraw_hidden;
 
 #[cfg(test)]
 mod tests;
@@ -25,13 +73,23 @@ mod tests;
 #[cfg(test)]
 mod optimization_bench;
 
+#[cfg(test)]
+mod regression;
+
+// Circuit metadata
+pub use diagnostics::{
+    constraint_breakdown, explain_unsatisfied, generate_witness, which_constraint_failed,
+    SectionCount, UnsatisfiedConstraint,
+};
+pub use meta::CircuitMeta;
+
 // Re-export poseidon hash functions
 pub use poseidon::{poseidon_hash, poseidon_hash_two, poseidon_hash_many};
 
 // SMT infrastructure
 pub use smt::{
-    compute_root_from_path, verify_and_update, verify_membership, MerkleProof, MerkleProofVar,
-    SparseMerkleTree, DEFAULT_DEPTH,
+    compute_root_from_path, verify_and_update, verify_membership, verify_quantity, MerkleProof,
+    MerkleProofVar, MigrationError, SparseMerkleTree, DEFAULT_DEPTH,
 };
 
 // Signal hash (public input compression)
@@ -39,15 +97,63 @@ pub use signal::{
     compute_signal_hash, compute_signal_hash_var, OpType, SignalInputs, SignalInputsVar,
 };
 
+// Pedersen commitment
+pub use commitment::pedersen::{
+    create_inventory_commitment_pedersen, pedersen_commit, pedersen_commit_var,
+    PedersenCommitmentVar,
+};
+
 // SMT commitment
 pub use smt_commitment::{
-    create_smt_commitment, create_smt_commitment_var, InventoryState, InventoryStateVar,
+    create_smt_commitment, create_smt_commitment_var, create_smt_commitment_var_with,
+    create_smt_commitment_with, inventory_content_hash, inventory_content_hash_var,
+    try_create_smt_commitment, verify_commitments, CommitmentError, InventoryState,
+    InventoryStateVar, COMMITMENT_DOMAIN, COMMITMENT_VERSION,
 };
 
+// Inventory update witnesses
+pub use inventory_update::{InventoryUpdate, InventoryUpdateVar};
+
 // Circuit exports
 pub use state_transition::StateTransitionCircuit;
 pub use item_exists_smt::{compute_item_exists_hash, ItemExistsSMTCircuit};
+pub use item_exists_registry::{
+    compute_item_exists_in_registry_hash, ItemExistsInRegistryCircuit,
+};
 pub use capacity_smt::{compute_capacity_hash, CapacitySMTCircuit};
+pub use category_threshold::{compute_category_threshold_hash, CategoryThresholdCircuit};
+pub use delta::{compute_delta_hash, DeltaCircuit, ItemDelta};
+pub use equality::{compute_equality_hash, EqualityCircuit};
+pub use mint_deposit::{
+    compute_mint_authorization, compute_mint_deposit_hash, minter_key_commitment,
+    MintDepositCircuit,
+};
+pub use predicate::{compute_predicate_hash, Predicate, PredicateCircuit, PredicateError};
+pub use stack_limit::{compute_stack_limit_hash, enforce_stack_limit, StackLimitCircuit};
+pub use sum_reveal::{compute_sum_reveal_hash, SumRevealCircuit};
+pub use withdraw_hidden::{compute_withdraw_hidden_hash, WithdrawHiddenCircuit};
+pub use burn::{compute_burn_hash, compute_burn_nullifier, BurnCircuit, BURN_NULLIFIER_DOMAIN};
+pub use chain_provenance::{
+    compute_chain_hash, compute_chain_provenance_hash, ChainLink, ChainProvenanceCircuit,
+    ChainStep,
+};
+pub use modulo::{compute_modulo_hash, ModuloCircuit};
+pub use multi_transfer::{
+    compute_transfers_hash, ItemTransfer, MultiTransferCircuit, TransferSide, MAX_TRANSFERS,
+};
+pub use crafting::{
+    compute_recipe_commitment, CraftingCircuit, Recipe, RecipeItem, MAX_INPUTS, MAX_OUTPUTS,
+};
+pub use non_membership::{compute_non_membership_hash, NonMembershipCircuit};
+pub use quantity_range::{
+    compute_quantity_range_hash, InvalidQuantityRange, QuantityRangeCircuit,
+};
+pub use atomic_basket::{compute_basket_hash, AtomicBasketCircuit, MAX_BASKET_ITEMS};
+pub use whitelist::{compute_whitelist_hash, whitelist_tree, WhitelistCircuit, WHITELISTED};
+pub use gift::{compute_gift_hash, compute_gift_nullifier, GiftCircuit, GIFT_NULLIFIER_DOMAIN};
+pub use reblind::{compute_reblind_hash, ReblindCircuit, NO_OWNER};
+pub use fee_transfer::{compute_fee_transfer_hash, FeeTransferCircuit};
+pub use first_acquisition::{compute_first_acquisition_hash, FirstAcquisitionCircuit};
 
 use ark_bn254::Fr;
 
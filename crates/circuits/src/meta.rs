@@ -0,0 +1,188 @@
+//! Circuit metadata derived from the constraint definitions.
+//!
+//! Counting public inputs by synthesizing the circuit keeps provers and
+//! verifiers in sync with the actual `new_input` allocations instead of
+//! hardcoded vector lengths.
+//!
+//! `public_input_influence` supports security review: a public input that no
+//! constraint references is not actually checked by the proof.
+
+use std::collections::{BTreeSet, HashMap};
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisMode,
+};
+
+use crate::{
+    AtomicBasketCircuit, BurnCircuit, CapacitySMTCircuit, CategoryThresholdCircuit,
+    ChainProvenanceCircuit, CraftingCircuit, DeltaCircuit, EqualityCircuit, FeeTransferCircuit,
+    FirstAcquisitionCircuit, GiftCircuit, ItemExistsInRegistryCircuit, ItemExistsSMTCircuit,
+    MintDepositCircuit, ModuloCircuit, MultiTransferCircuit, NonMembershipCircuit, PredicateCircuit,
+    QuantityRangeCircuit, ReblindCircuit, StackLimitCircuit, StateTransitionCircuit,
+    SumRevealCircuit, WhitelistCircuit, WithdrawHiddenCircuit,
+};
+
+/// Metadata shared by all circuits.
+pub trait CircuitMeta: ConstraintSynthesizer<Fr> + Clone {
+    /// Number of public inputs this circuit allocates (excluding the constant one).
+    ///
+    /// Synthesizes the circuit in setup mode, so witness values are not needed,
+    /// but optional witnesses such as Merkle proofs must be present (as in `empty()`).
+    fn num_public_inputs(&self) -> usize {
+        let cs = synthesize_for_setup(self);
+
+        // Instance variables include the constant one at index 0
+        cs.num_instance_variables() - 1
+    }
+
+    /// Number of constraints this circuit creates, with the same requirements
+    /// as [`num_public_inputs`](Self::num_public_inputs).
+    fn num_constraints(&self) -> usize {
+        synthesize_for_setup(self).num_constraints()
+    }
+
+    /// Map each public input (by position in the public input vector) to the
+    /// indices of the constraints that reference it in A, B or C.
+    ///
+    /// Every public input is present in the map, with an empty list if unused.
+    fn public_input_influence(&self) -> HashMap<usize, Vec<usize>> {
+        let cs = synthesize_for_setup(self);
+        cs.finalize();
+        let matrices = cs.to_matrices().expect("setup mode constructs matrices");
+
+        let num_inputs = matrices.num_instance_variables - 1;
+        let mut influence: HashMap<usize, BTreeSet<usize>> =
+            (0..num_inputs).map(|i| (i, BTreeSet::new())).collect();
+
+        for matrix in [&matrices.a, &matrices.b, &matrices.c] {
+            for (row, terms) in matrix.iter().enumerate() {
+                for &(_, column) in terms {
+                    // Column 0 is the constant one; public inputs follow it
+                    if (1..=num_inputs).contains(&column) {
+                        influence.get_mut(&(column - 1)).unwrap().insert(row);
+                    }
+                }
+            }
+        }
+
+        influence
+            .into_iter()
+            .map(|(input, rows)| (input, rows.into_iter().collect()))
+            .collect()
+    }
+}
+
+/// Synthesize a copy of `circuit` in setup mode.
+fn synthesize_for_setup<C: ConstraintSynthesizer<Fr> + Clone>(circuit: &C) -> ConstraintSystemRef<Fr> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+    circuit
+        .clone()
+        .generate_constraints(cs.clone())
+        .expect("circuit synthesis failed");
+    cs
+}
+
+impl CircuitMeta for StateTransitionCircuit {}
+impl CircuitMeta for ItemExistsSMTCircuit {}
+impl CircuitMeta for ItemExistsInRegistryCircuit {}
+impl CircuitMeta for CapacitySMTCircuit {}
+impl CircuitMeta for FirstAcquisitionCircuit {}
+impl CircuitMeta for FeeTransferCircuit {}
+impl CircuitMeta for EqualityCircuit {}
+impl CircuitMeta for StackLimitCircuit {}
+impl CircuitMeta for CategoryThresholdCircuit {}
+impl CircuitMeta for MintDepositCircuit {}
+impl CircuitMeta for DeltaCircuit {}
+impl CircuitMeta for PredicateCircuit {}
+impl CircuitMeta for SumRevealCircuit {}
+impl CircuitMeta for WithdrawHiddenCircuit {}
+impl CircuitMeta for ChainProvenanceCircuit {}
+impl CircuitMeta for BurnCircuit {}
+impl CircuitMeta for MultiTransferCircuit {}
+impl CircuitMeta for ModuloCircuit {}
+impl CircuitMeta for CraftingCircuit {}
+impl CircuitMeta for NonMembershipCircuit {}
+impl CircuitMeta for QuantityRangeCircuit {}
+impl CircuitMeta for AtomicBasketCircuit {}
+impl CircuitMeta for WhitelistCircuit {}
+impl CircuitMeta for GiftCircuit {}
+impl CircuitMeta for ReblindCircuit {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Predicate;
+
+    #[test]
+    fn test_num_public_inputs() {
+        assert_eq!(StateTransitionCircuit::empty().num_public_inputs(), 4);
+        assert_eq!(
+            StateTransitionCircuit::empty()
+                .with_min_total_reserve(0)
+                .num_public_inputs(),
+            5
+        );
+        assert_eq!(StateTransitionCircuit::empty().with_epoch(0).num_public_inputs(), 5);
+        assert_eq!(
+            StateTransitionCircuit::empty()
+                .with_min_total_reserve(0)
+                .with_epoch(0)
+                .num_public_inputs(),
+            6
+        );
+        assert_eq!(ItemExistsSMTCircuit::empty().num_public_inputs(), 1);
+        assert_eq!(ItemExistsInRegistryCircuit::empty().num_public_inputs(), 1);
+        assert_eq!(CapacitySMTCircuit::empty().num_public_inputs(), 1);
+        assert_eq!(FirstAcquisitionCircuit::empty().num_public_inputs(), 1);
+        assert_eq!(FeeTransferCircuit::empty().num_public_inputs(), 3);
+        assert_eq!(EqualityCircuit::empty().num_public_inputs(), 1);
+        assert_eq!(StackLimitCircuit::empty().num_public_inputs(), 1);
+        assert_eq!(CategoryThresholdCircuit::empty(vec![1, 2]).num_public_inputs(), 1);
+        assert_eq!(MintDepositCircuit::empty().num_public_inputs(), 1);
+        assert_eq!(DeltaCircuit::empty(2).num_public_inputs(), 1);
+        let predicate = Predicate::Threshold { item_id: 1, min_quantity: 1 };
+        assert_eq!(PredicateCircuit::empty(predicate).num_public_inputs(), 1);
+        assert_eq!(SumRevealCircuit::empty(3).num_public_inputs(), 1);
+        assert_eq!(WithdrawHiddenCircuit::empty().num_public_inputs(), 1);
+        assert_eq!(ChainProvenanceCircuit::empty(2).num_public_inputs(), 1);
+        assert_eq!(BurnCircuit::empty().num_public_inputs(), 2);
+        assert_eq!(MultiTransferCircuit::empty(2).num_public_inputs(), 5);
+        assert_eq!(ModuloCircuit::empty().num_public_inputs(), 1);
+        assert_eq!(CraftingCircuit::empty(2, 1).num_public_inputs(), 3);
+        assert_eq!(NonMembershipCircuit::empty().num_public_inputs(), 1);
+        assert_eq!(QuantityRangeCircuit::empty().num_public_inputs(), 1);
+        assert_eq!(AtomicBasketCircuit::empty(3).num_public_inputs(), 1);
+        assert_eq!(WhitelistCircuit::empty().num_public_inputs(), 1);
+        assert_eq!(GiftCircuit::empty().num_public_inputs(), 2);
+        assert_eq!(ReblindCircuit::empty().num_public_inputs(), 1);
+    }
+
+    #[test]
+    fn test_public_input_influence_item_exists() {
+        let circuit = ItemExistsSMTCircuit::empty();
+        let influence = circuit.public_input_influence();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        let last_constraint = cs.num_constraints() - 1;
+
+        // The public hash (which binds the commitment) is checked by the final constraint
+        assert_eq!(influence.len(), 1);
+        assert!(influence[&0].contains(&last_constraint));
+    }
+
+    #[test]
+    fn test_every_state_transition_input_is_constrained() {
+        let circuit = StateTransitionCircuit::empty()
+            .with_min_total_reserve(0)
+            .with_epoch(0);
+        let influence = circuit.public_input_influence();
+
+        assert_eq!(influence.len(), 6);
+        for (input, constraints) in &influence {
+            assert!(!constraints.is_empty(), "public input {} is unconstrained", input);
+        }
+    }
+}
@@ -0,0 +1,321 @@
+//! MintDeposit Circuit for SMT-based inventory.
+//!
+//! Proves a deposit of bridged assets that a trusted minter authorized. On top of
+//! the usual deposit checks, the circuit verifies a Poseidon MAC
+//! `Poseidon(minter_key, item_id, amount, nonce)` against a public commitment
+//! `Poseidon(minter_key)` to the minter's key.
+//!
+//! A MAC can only be checked by a holder of the key, so these proofs are produced
+//! by the minter (or the bridge service acting for it) on the depositor's behalf.
+//! The key itself never leaves the witness.
+//!
+//! Public input: Poseidon(old_commitment, new_commitment, item_id, amount,
+//!                        item_volume, max_capacity, nonce, minter_key_commitment)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::inventory_update::{InventoryUpdate, InventoryUpdateVar};
+use crate::poseidon::{poseidon_hash, poseidon_hash_many, poseidon_hash_many_var, poseidon_hash_var};
+use crate::range_check::{enforce_geq, enforce_u32_range};
+
+/// Public commitment to a minter key.
+pub fn minter_key_commitment(minter_key: Fr) -> Fr {
+    poseidon_hash(minter_key)
+}
+
+/// Authorization tag the minter issues for one deposit.
+pub fn compute_mint_authorization(minter_key: Fr, item_id: u64, amount: u64, nonce: u64) -> Fr {
+    let inputs = vec![
+        minter_key,
+        Fr::from(item_id),
+        Fr::from(amount),
+        Fr::from(nonce),
+    ];
+    poseidon_hash_many(&inputs)
+}
+
+/// Compute the public input hash for MintDeposit proof.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_mint_deposit_hash(
+    old_commitment: Fr,
+    new_commitment: Fr,
+    item_id: u64,
+    amount: u64,
+    item_volume: u64,
+    max_capacity: u64,
+    nonce: u64,
+    minter_key_commitment: Fr,
+) -> Fr {
+    let inputs = vec![
+        old_commitment,
+        new_commitment,
+        Fr::from(item_id),
+        Fr::from(amount),
+        Fr::from(item_volume),
+        Fr::from(max_capacity),
+        Fr::from(nonce),
+        minter_key_commitment,
+    ];
+    poseidon_hash_many(&inputs)
+}
+
+/// MintDeposit Circuit.
+#[derive(Clone)]
+pub struct MintDepositCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Deposit witnesses
+    /// Item ID being deposited
+    pub item_id: Option<u64>,
+    /// Amount deposited
+    pub amount: Option<u64>,
+    /// Volume per unit of this item type
+    pub item_volume: Option<u64>,
+    /// Maximum allowed capacity
+    pub max_capacity: Option<u64>,
+    /// Inventory update for the deposit
+    pub deposit: Option<InventoryUpdate>,
+
+    // Authorization witnesses
+    /// Nonce the authorization was issued for
+    pub nonce: Option<u64>,
+    /// Commitment to the minter key
+    pub minter_key_commitment: Option<Fr>,
+    /// Minter key
+    pub minter_key: Option<Fr>,
+    /// Authorization tag for (item_id, amount, nonce)
+    pub authorization: Option<Fr>,
+}
+
+impl MintDepositCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            item_id: Some(0),
+            amount: Some(0),
+            item_volume: Some(0),
+            max_capacity: Some(0),
+            deposit: Some(InventoryUpdate::empty()),
+            nonce: Some(0),
+            minter_key_commitment: Some(Fr::from(0u64)),
+            minter_key: Some(Fr::from(0u64)),
+            authorization: Some(Fr::from(0u64)),
+        }
+    }
+
+    /// Create a new circuit with witnesses.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        item_id: u64,
+        amount: u64,
+        item_volume: u64,
+        max_capacity: u64,
+        deposit: InventoryUpdate,
+        nonce: u64,
+        minter_key_commitment: Fr,
+        minter_key: Fr,
+        authorization: Fr,
+    ) -> Self {
+        let public_hash = compute_mint_deposit_hash(
+            deposit.old_state.commitment(),
+            deposit.new_state.commitment(),
+            item_id,
+            amount,
+            item_volume,
+            max_capacity,
+            nonce,
+            minter_key_commitment,
+        );
+
+        Self {
+            public_hash: Some(public_hash),
+            item_id: Some(item_id),
+            amount: Some(amount),
+            item_volume: Some(item_volume),
+            max_capacity: Some(max_capacity),
+            deposit: Some(deposit),
+            nonce: Some(nonce),
+            minter_key_commitment: Some(minter_key_commitment),
+            minter_key: Some(minter_key),
+            authorization: Some(authorization),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for MintDepositCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate deposit witnesses ===
+        let item_id_var = FpVar::new_witness(cs.clone(), || {
+            self.item_id
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let amount_var = FpVar::new_witness(cs.clone(), || {
+            self.amount
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let item_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.item_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let max_capacity_var = FpVar::new_witness(cs.clone(), || {
+            self.max_capacity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate authorization witnesses ===
+        let nonce_var = FpVar::new_witness(cs.clone(), || {
+            self.nonce
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let minter_key_commitment_var = FpVar::new_witness(cs.clone(), || {
+            self.minter_key_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let minter_key_var = FpVar::new_witness(cs.clone(), || {
+            self.minter_key.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let authorization_var = FpVar::new_witness(cs.clone(), || {
+            self.authorization.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Constraint 1: Only the item leaf changes ===
+        let deposit = InventoryUpdateVar::new_witness(
+            cs.clone(),
+            &item_id_var,
+            self.deposit.as_ref().unwrap(),
+        )?;
+
+        // === Constraint 2: Minter key opens the public commitment ===
+        poseidon_hash_var(cs.clone(), &minter_key_var)?.enforce_equal(&minter_key_commitment_var)?;
+
+        // === Constraint 3: Authorization is the MAC over (item_id, amount, nonce) ===
+        let mac_inputs = vec![
+            minter_key_var,
+            item_id_var.clone(),
+            amount_var.clone(),
+            nonce_var.clone(),
+        ];
+        poseidon_hash_many_var(cs.clone(), &mac_inputs)?.enforce_equal(&authorization_var)?;
+
+        // === Constraint 4: Quantity increases by amount ===
+        deposit
+            .new_quantity
+            .enforce_equal(&(&deposit.old_quantity + &amount_var))?;
+        enforce_u32_range(cs.clone(), &deposit.new_quantity)?;
+
+        // === Constraint 5: Volume increases by item_volume * amount ===
+        let volume_delta = &item_volume_var * &amount_var;
+        deposit
+            .new_state
+            .current_volume
+            .enforce_equal(&(&deposit.old_state.current_volume + &volume_delta))?;
+        enforce_u32_range(cs.clone(), &deposit.new_state.current_volume)?;
+
+        // === Constraint 6: Capacity check ===
+        enforce_geq(cs.clone(), &max_capacity_var, &deposit.new_state.current_volume)?;
+
+        // === Constraint 7: Compute and verify public hash ===
+        let inputs = vec![
+            deposit.old_commitment,
+            deposit.new_commitment,
+            item_id_var,
+            amount_var,
+            item_volume_var,
+            max_capacity_var,
+            nonce_var,
+            minter_key_commitment_var,
+        ];
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    const ITEM: u64 = 9;
+    const AMOUNT: u64 = 25;
+    const NONCE: u64 = 3;
+
+    fn minter_key() -> Fr {
+        Fr::from(0xB41D6Eu64)
+    }
+
+    /// Deposit AMOUNT of ITEM (volume 2 each) into an inventory holding ITEM x 10.
+    fn mint_deposit(signing_key: Fr, authorized_amount: u64) -> MintDepositCircuit {
+        let mut tree = SparseMerkleTree::from_items(&[(ITEM, 10)], DEFAULT_DEPTH);
+        let deposit = InventoryUpdate::apply(
+            &mut tree,
+            20,
+            Fr::from(1u64),
+            ITEM,
+            10 + AMOUNT,
+            (10 + AMOUNT) * 2,
+            Fr::from(2u64),
+        );
+        let authorization =
+            compute_mint_authorization(signing_key, ITEM, authorized_amount, NONCE);
+
+        MintDepositCircuit::new(
+            ITEM,
+            AMOUNT,
+            2,
+            1000,
+            deposit,
+            NONCE,
+            minter_key_commitment(minter_key()),
+            signing_key,
+            authorization,
+        )
+    }
+
+    fn is_satisfied(circuit: MintDepositCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_mint_deposit_authorized() {
+        let circuit = mint_deposit(minter_key(), AMOUNT);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("MintDeposit constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_mint_deposit_forged_key_fails() {
+        // Authorization produced with a key that does not match the commitment
+        assert!(!is_satisfied(mint_deposit(Fr::from(666u64), AMOUNT)));
+    }
+
+    #[test]
+    fn test_mint_deposit_authorization_for_other_amount_fails() {
+        // Valid tag, but it authorizes a smaller deposit
+        assert!(!is_satisfied(mint_deposit(minter_key(), AMOUNT - 1)));
+    }
+}
@@ -0,0 +1,244 @@
+//! Modulo Circuit for SMT-based inventory.
+//!
+//! Proves `quantity mod ticket_base == claimed_remainder` for one item without
+//! revealing the quantity, e.g. for raffles that assign tickets by remainder.
+//!
+//! The prover supplies the quotient `q` as a witness; the circuit enforces
+//! `quantity = q * ticket_base + claimed_remainder` with
+//! `claimed_remainder < ticket_base`. `q`, `ticket_base` and the remainder are
+//! range checked to 32 bits, so the product cannot wrap around the field and
+//! the decomposition is unique.
+//!
+//! Public input: Poseidon(commitment, item_id, ticket_base, claimed_remainder)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::{enforce_geq, enforce_u32_range};
+use crate::smt::{verify_quantity, MerkleProof, MerkleProofVar, SparseMerkleTree, DEFAULT_DEPTH};
+use crate::smt_commitment::{InventoryState, InventoryStateVar};
+
+/// Compute the public input hash for Modulo proof.
+pub fn compute_modulo_hash(
+    commitment: Fr,
+    item_id: u64,
+    ticket_base: u64,
+    claimed_remainder: u64,
+) -> Fr {
+    let inputs = vec![
+        commitment,
+        Fr::from(item_id),
+        Fr::from(ticket_base),
+        Fr::from(claimed_remainder),
+    ];
+    poseidon_hash_many(&inputs)
+}
+
+/// Modulo Circuit.
+#[derive(Clone)]
+pub struct ModuloCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Public data (bound via hash)
+    /// Item ID whose quantity is reduced
+    pub item_id: Option<u64>,
+    /// Modulus
+    pub ticket_base: Option<u64>,
+    /// quantity mod ticket_base
+    pub claimed_remainder: Option<u64>,
+
+    // Private witnesses
+    /// Inventory state the commitment opens to
+    pub state: Option<InventoryState>,
+    /// Quantity of item_id held
+    pub quantity: Option<u64>,
+    /// quantity / ticket_base
+    pub quotient: Option<u64>,
+    /// Proof for item_id in the inventory SMT
+    pub proof: Option<MerkleProof<Fr>>,
+}
+
+impl ModuloCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        let dummy_proof = MerkleProof::new(
+            vec![Fr::from(0u64); DEFAULT_DEPTH],
+            vec![false; DEFAULT_DEPTH],
+        );
+
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            item_id: Some(0),
+            ticket_base: Some(0),
+            claimed_remainder: Some(0),
+            state: Some(InventoryState::new(Fr::from(0u64), 0, Fr::from(0u64))),
+            quantity: Some(0),
+            quotient: Some(0),
+            proof: Some(dummy_proof),
+        }
+    }
+
+    /// Prove the remainder of `item_id`'s quantity in `tree` modulo `ticket_base`.
+    ///
+    /// Panics if `ticket_base` is 0.
+    pub fn new(
+        tree: &SparseMerkleTree,
+        current_volume: u64,
+        blinding: Fr,
+        item_id: u64,
+        ticket_base: u64,
+    ) -> Self {
+        assert!(ticket_base > 0, "ticket_base must be positive");
+
+        let state = InventoryState::new(tree.root(), current_volume, blinding);
+        let quantity = tree.get(item_id);
+        let claimed_remainder = quantity % ticket_base;
+        let public_hash =
+            compute_modulo_hash(state.commitment(), item_id, ticket_base, claimed_remainder);
+
+        Self {
+            public_hash: Some(public_hash),
+            item_id: Some(item_id),
+            ticket_base: Some(ticket_base),
+            claimed_remainder: Some(claimed_remainder),
+            state: Some(state),
+            quantity: Some(quantity),
+            quotient: Some(quantity / ticket_base),
+            proof: Some(tree.get_proof(item_id)),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for ModuloCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate public data (bound via hash) ===
+        let item_id_var = FpVar::new_witness(cs.clone(), || {
+            self.item_id
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let base_var = FpVar::new_witness(cs.clone(), || {
+            self.ticket_base
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let remainder_var = FpVar::new_witness(cs.clone(), || {
+            self.claimed_remainder
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate private witnesses ===
+        let state = InventoryStateVar::new_witness(cs.clone(), self.state.as_ref().unwrap())?;
+        let quantity_var = FpVar::new_witness(cs.clone(), || {
+            self.quantity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let quotient_var = FpVar::new_witness(cs.clone(), || {
+            self.quotient
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let proof_var = MerkleProofVar::new_witness(cs.clone(), self.proof.as_ref().unwrap())?;
+
+        // === Constraint 1: The inventory holds exactly `quantity` of item_id ===
+        verify_quantity(
+            cs.clone(),
+            &state.inventory_root,
+            &item_id_var,
+            &quantity_var,
+            &proof_var,
+        )?;
+
+        // === Constraint 2: quantity = quotient * ticket_base + claimed_remainder ===
+        enforce_u32_range(cs.clone(), &quotient_var)?;
+        enforce_u32_range(cs.clone(), &base_var)?;
+        enforce_u32_range(cs.clone(), &remainder_var)?;
+        quantity_var.enforce_equal(&(&quotient_var * &base_var + &remainder_var))?;
+
+        // === Constraint 3: claimed_remainder < ticket_base ===
+        let max_remainder = &base_var - FpVar::one();
+        enforce_geq(cs.clone(), &max_remainder, &remainder_var)?;
+
+        // === Constraint 4: Compute and verify public hash ===
+        let commitment_var = state.commitment(cs.clone())?;
+        let inputs = vec![commitment_var, item_id_var, base_var, remainder_var];
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// 23 of item 1, drawn with 5 tickets: remainder 3.
+    fn raffle() -> ModuloCircuit {
+        let tree = SparseMerkleTree::from_items(&[(1, 23), (2, 40)], DEFAULT_DEPTH);
+        ModuloCircuit::new(&tree, 103, Fr::from(1u64), 1, 5)
+    }
+
+    fn is_satisfied(circuit: ModuloCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    /// Recompute the public hash after tampering with the witnesses.
+    fn rebind(circuit: &mut ModuloCircuit) {
+        circuit.public_hash = Some(compute_modulo_hash(
+            circuit.state.as_ref().unwrap().commitment(),
+            circuit.item_id.unwrap(),
+            circuit.ticket_base.unwrap(),
+            circuit.claimed_remainder.unwrap(),
+        ));
+    }
+
+    #[test]
+    fn test_modulo_correct_remainder() {
+        let circuit = raffle();
+        assert_eq!(circuit.claimed_remainder, Some(3));
+        assert_eq!(circuit.quotient, Some(4));
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("Modulo constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_remainder_not_below_base_fails() {
+        // 23 = 3 * 5 + 8 holds, but 8 is not a remainder modulo 5
+        let mut circuit = raffle();
+        circuit.quotient = Some(3);
+        circuit.claimed_remainder = Some(8);
+        rebind(&mut circuit);
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_inconsistent_quotient_fails() {
+        // 5 * 5 + 3 = 28, not the 23 actually held
+        let mut circuit = raffle();
+        circuit.quotient = Some(5);
+
+        assert!(!is_satisfied(circuit));
+    }
+}
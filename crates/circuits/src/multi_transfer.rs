@@ -0,0 +1,490 @@
+//! MultiTransfer Circuit for SMT-based inventory.
+//!
+//! Proves that several items moved from a source inventory to a destination
+//! inventory in one proof, instead of one transfer proof per item.
+//!
+//! Each `(item_id, amount)` transfer is applied to both SMT roots in order:
+//! the source loses `amount` (range checked, so it must hold at least that
+//! many) and the destination gains it. Only after all transfers are applied are
+//! the roots compared with the new ones, so any item not in the list is
+//! unchanged in either inventory. Volumes move by `amount * item_volume` per
+//! transfer.
+//!
+//! The number of transfers is fixed when the circuit is built (see
+//! [`MultiTransferCircuit::empty`]) and is at most [`MAX_TRANSFERS`].
+//!
+//! Public inputs:
+//! - src_old_commitment, src_new_commitment
+//! - dst_old_commitment, dst_new_commitment
+//! - transfers_hash: Poseidon(item_id_0, amount_0, item_volume_0, ...)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::enforce_u32_range;
+use crate::smt::{verify_and_update, MerkleProof, MerkleProofVar, SparseMerkleTree, DEFAULT_DEPTH};
+use crate::smt_commitment::{InventoryState, InventoryStateVar};
+
+/// Most transfers a single proof can carry.
+pub const MAX_TRANSFERS: usize = 8;
+
+/// One item moving from the source to the destination.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ItemTransfer {
+    /// Item ID being transferred
+    pub item_id: u64,
+    /// Quantity transferred
+    pub amount: u64,
+    /// Volume per unit of this item type
+    pub item_volume: u64,
+}
+
+/// Compute the public hash of a transfer list.
+pub fn compute_transfers_hash(transfers: &[ItemTransfer]) -> Fr {
+    let mut inputs = Vec::with_capacity(3 * transfers.len());
+    for transfer in transfers {
+        inputs.push(Fr::from(transfer.item_id));
+        inputs.push(Fr::from(transfer.amount));
+        inputs.push(Fr::from(transfer.item_volume));
+    }
+    poseidon_hash_many(&inputs)
+}
+
+/// Witnesses for one side of a multi-item transfer.
+#[derive(Clone, Debug)]
+pub struct TransferSide {
+    /// State before the transfers
+    pub old_state: InventoryState,
+    /// State after the transfers
+    pub new_state: InventoryState,
+    /// Quantity of each item before its transfer is applied
+    pub old_quantities: Vec<u64>,
+    /// Proof for each item, taken after the preceding transfers were applied
+    pub proofs: Vec<MerkleProof<Fr>>,
+}
+
+impl TransferSide {
+    /// Dummy side with room for `num_transfers` transfers, for circuit setup.
+    pub fn empty(num_transfers: usize) -> Self {
+        let zero_state = InventoryState::new(Fr::from(0u64), 0, Fr::from(0u64));
+        let dummy_proof = MerkleProof::new(
+            vec![Fr::from(0u64); DEFAULT_DEPTH],
+            vec![false; DEFAULT_DEPTH],
+        );
+
+        Self {
+            old_state: zero_state.clone(),
+            new_state: zero_state,
+            old_quantities: vec![0; num_transfers],
+            proofs: vec![dummy_proof; num_transfers],
+        }
+    }
+
+    /// Debit every transfer from `tree` and record the witnesses.
+    ///
+    /// `tree` is updated in place so it reflects the new state afterwards.
+    /// Panics if the inventory holds too few of an item or too little volume.
+    pub fn debit(
+        tree: &mut SparseMerkleTree,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+        transfers: &[ItemTransfer],
+    ) -> Self {
+        let steps = transfers.iter().map(|transfer| (transfer, false));
+        Self::apply(tree, old_volume, old_blinding, new_blinding, steps)
+    }
+
+    /// Credit every transfer to `tree` and record the witnesses.
+    ///
+    /// `tree` is updated in place so it reflects the new state afterwards.
+    /// Panics if a quantity or the volume would overflow a `u64`.
+    pub fn credit(
+        tree: &mut SparseMerkleTree,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+        transfers: &[ItemTransfer],
+    ) -> Self {
+        let steps = transfers.iter().map(|transfer| (transfer, true));
+        Self::apply(tree, old_volume, old_blinding, new_blinding, steps)
+    }
+
+    /// Debit `debits` from `tree`, then credit `credits`, as one state change.
+    ///
+    /// Witnesses follow the same order, so the circuit must apply the debits
+    /// before the credits. `tree` is updated in place so it reflects the new
+    /// state afterwards. Panics if the inventory holds too few of a debited
+    /// item or too little volume.
+    pub fn exchange(
+        tree: &mut SparseMerkleTree,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+        debits: &[ItemTransfer],
+        credits: &[ItemTransfer],
+    ) -> Self {
+        let debits = debits.iter().map(|transfer| (transfer, false));
+        let credits = credits.iter().map(|transfer| (transfer, true));
+        Self::apply(tree, old_volume, old_blinding, new_blinding, debits.chain(credits))
+    }
+
+    fn apply<'a>(
+        tree: &mut SparseMerkleTree,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+        steps: impl Iterator<Item = (&'a ItemTransfer, bool)>,
+    ) -> Self {
+        let old_state = InventoryState::new(tree.root(), old_volume, old_blinding);
+
+        let mut new_volume = old_volume;
+        let mut old_quantities = Vec::with_capacity(steps.size_hint().0);
+        let mut proofs = Vec::with_capacity(steps.size_hint().0);
+        for (transfer, is_credit) in steps {
+            let old_quantity = tree.get(transfer.item_id);
+            let volume_delta = transfer
+                .amount
+                .checked_mul(transfer.item_volume)
+                .expect("Transfer would cause volume overflow");
+            let new_quantity = if is_credit {
+                new_volume = new_volume
+                    .checked_add(volume_delta)
+                    .expect("Transfer would cause volume overflow");
+                old_quantity
+                    .checked_add(transfer.amount)
+                    .expect("Transfer would cause quantity overflow")
+            } else {
+                new_volume = new_volume
+                    .checked_sub(volume_delta)
+                    .expect("Transfer would cause volume underflow");
+                old_quantity
+                    .checked_sub(transfer.amount)
+                    .expect("Transfer would cause quantity underflow")
+            };
+
+            old_quantities.push(old_quantity);
+            proofs.push(tree.get_proof(transfer.item_id));
+            tree.update(transfer.item_id, new_quantity);
+        }
+
+        let new_state = InventoryState::new(tree.root(), new_volume, new_blinding);
+
+        Self {
+            old_state,
+            new_state,
+            old_quantities,
+            proofs,
+        }
+    }
+}
+
+/// Circuit variables for one side after applying every transfer.
+pub(crate) struct TransferSideVar {
+    old_state: InventoryStateVar,
+    new_state: InventoryStateVar,
+    root: FpVar<Fr>,
+    volume: FpVar<Fr>,
+}
+
+impl TransferSideVar {
+    pub(crate) fn new_witness(
+        cs: ConstraintSystemRef<Fr>,
+        side: &TransferSide,
+    ) -> Result<Self, SynthesisError> {
+        let old_state = InventoryStateVar::new_witness(cs.clone(), &side.old_state)?;
+        let new_state = InventoryStateVar::new_witness(cs, &side.new_state)?;
+
+        Ok(Self {
+            root: old_state.inventory_root.clone(),
+            volume: old_state.current_volume.clone(),
+            old_state,
+            new_state,
+        })
+    }
+
+    /// Move `amount` of `item_id` out of (or into) the running root and volume.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn apply(
+        &mut self,
+        cs: ConstraintSystemRef<Fr>,
+        item_id: &FpVar<Fr>,
+        amount: &FpVar<Fr>,
+        item_volume: &FpVar<Fr>,
+        old_quantity: u64,
+        proof: &MerkleProof<Fr>,
+        is_credit: bool,
+    ) -> Result<(), SynthesisError> {
+        let old_quantity = FpVar::new_witness(cs.clone(), || Ok(Fr::from(old_quantity)))?;
+        let proof = MerkleProofVar::new_witness(cs.clone(), proof)?;
+
+        // New quantity must not wrap (the source must hold at least `amount`)
+        let new_quantity = if is_credit {
+            &old_quantity + amount
+        } else {
+            &old_quantity - amount
+        };
+        enforce_u32_range(cs.clone(), &new_quantity)?;
+
+        self.root = verify_and_update(
+            cs,
+            &self.root,
+            item_id,
+            &old_quantity,
+            &new_quantity,
+            &proof,
+        )?;
+
+        let volume_delta = item_volume * amount;
+        if is_credit {
+            self.volume += volume_delta;
+        } else {
+            self.volume -= volume_delta;
+        }
+
+        Ok(())
+    }
+
+    /// Enforce that the running root and volume reached the new state,
+    /// and return the (old, new) commitments.
+    pub(crate) fn finish(
+        self,
+        cs: ConstraintSystemRef<Fr>,
+    ) -> Result<(FpVar<Fr>, FpVar<Fr>), SynthesisError> {
+        self.root.enforce_equal(&self.new_state.inventory_root)?;
+        self.volume.enforce_equal(&self.new_state.current_volume)?;
+        enforce_u32_range(cs.clone(), &self.new_state.current_volume)?;
+
+        let old_commitment = self.old_state.commitment(cs.clone())?;
+        let new_commitment = self.new_state.commitment(cs)?;
+        Ok((old_commitment, new_commitment))
+    }
+}
+
+/// MultiTransfer Circuit.
+#[derive(Clone)]
+pub struct MultiTransferCircuit {
+    // Public inputs
+    /// Source commitment before the transfers
+    pub src_old_commitment: Option<Fr>,
+    /// Source commitment after the transfers
+    pub src_new_commitment: Option<Fr>,
+    /// Destination commitment before the transfers
+    pub dst_old_commitment: Option<Fr>,
+    /// Destination commitment after the transfers
+    pub dst_new_commitment: Option<Fr>,
+    /// Hash of the transfer list
+    pub transfers_hash: Option<Fr>,
+
+    /// Transfers applied, in order
+    pub transfers: Option<Vec<ItemTransfer>>,
+
+    // Inventory witnesses
+    /// Source inventory
+    pub src: Option<TransferSide>,
+    /// Destination inventory
+    pub dst: Option<TransferSide>,
+}
+
+impl MultiTransferCircuit {
+    /// Create an empty circuit for setup with room for `num_transfers` transfers.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty(num_transfers: usize) -> Self {
+        assert!(num_transfers <= MAX_TRANSFERS, "at most {} transfers", MAX_TRANSFERS);
+        let dummy_transfer = ItemTransfer {
+            item_id: 0,
+            amount: 0,
+            item_volume: 0,
+        };
+
+        Self {
+            src_old_commitment: Some(Fr::from(0u64)),
+            src_new_commitment: Some(Fr::from(0u64)),
+            dst_old_commitment: Some(Fr::from(0u64)),
+            dst_new_commitment: Some(Fr::from(0u64)),
+            transfers_hash: Some(Fr::from(0u64)),
+            transfers: Some(vec![dummy_transfer; num_transfers]),
+            src: Some(TransferSide::empty(num_transfers)),
+            dst: Some(TransferSide::empty(num_transfers)),
+        }
+    }
+
+    /// Create a new circuit with witnesses.
+    ///
+    /// `src` and `dst` must be built from the same `transfers`
+    /// (see [`TransferSide::debit`] and [`TransferSide::credit`]).
+    pub fn new(transfers: Vec<ItemTransfer>, src: TransferSide, dst: TransferSide) -> Self {
+        assert!(transfers.len() <= MAX_TRANSFERS, "at most {} transfers", MAX_TRANSFERS);
+
+        Self {
+            src_old_commitment: Some(src.old_state.commitment()),
+            src_new_commitment: Some(src.new_state.commitment()),
+            dst_old_commitment: Some(dst.old_state.commitment()),
+            dst_new_commitment: Some(dst.new_state.commitment()),
+            transfers_hash: Some(compute_transfers_hash(&transfers)),
+            transfers: Some(transfers),
+            src: Some(src),
+            dst: Some(dst),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for MultiTransferCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public inputs ===
+        // Order: src_old, src_new, dst_old, dst_new, transfers_hash
+        let src_old_var = FpVar::new_input(cs.clone(), || {
+            self.src_old_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let src_new_var = FpVar::new_input(cs.clone(), || {
+            self.src_new_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let dst_old_var = FpVar::new_input(cs.clone(), || {
+            self.dst_old_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let dst_new_var = FpVar::new_input(cs.clone(), || {
+            self.dst_new_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let transfers_hash_var = FpVar::new_input(cs.clone(), || {
+            self.transfers_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate inventory witnesses ===
+        let transfers = self.transfers.as_ref().unwrap();
+        let src = self.src.as_ref().unwrap();
+        let dst = self.dst.as_ref().unwrap();
+        assert!(transfers.len() <= MAX_TRANSFERS, "at most {} transfers", MAX_TRANSFERS);
+        for side in [src, dst] {
+            assert_eq!(side.old_quantities.len(), transfers.len(), "one quantity per transfer");
+            assert_eq!(side.proofs.len(), transfers.len(), "one proof per transfer");
+        }
+
+        let mut src_var = TransferSideVar::new_witness(cs.clone(), src)?;
+        let mut dst_var = TransferSideVar::new_witness(cs.clone(), dst)?;
+
+        // === Constraint 1: Apply each transfer to both inventories ===
+        let mut hash_inputs = Vec::with_capacity(3 * transfers.len());
+        for (i, transfer) in transfers.iter().enumerate() {
+            let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(transfer.item_id)))?;
+            let amount_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(transfer.amount)))?;
+            let item_volume_var =
+                FpVar::new_witness(cs.clone(), || Ok(Fr::from(transfer.item_volume)))?;
+
+            src_var.apply(
+                cs.clone(),
+                &item_id_var,
+                &amount_var,
+                &item_volume_var,
+                src.old_quantities[i],
+                &src.proofs[i],
+                false,
+            )?;
+            dst_var.apply(
+                cs.clone(),
+                &item_id_var,
+                &amount_var,
+                &item_volume_var,
+                dst.old_quantities[i],
+                &dst.proofs[i],
+                true,
+            )?;
+
+            hash_inputs.extend([item_id_var, amount_var, item_volume_var]);
+        }
+
+        // === Constraint 2: No other item changed, volumes match ===
+        let (src_old_commitment, src_new_commitment) = src_var.finish(cs.clone())?;
+        let (dst_old_commitment, dst_new_commitment) = dst_var.finish(cs.clone())?;
+
+        // === Constraint 3: Commitments match the public inputs ===
+        src_old_commitment.enforce_equal(&src_old_var)?;
+        src_new_commitment.enforce_equal(&src_new_var)?;
+        dst_old_commitment.enforce_equal(&dst_old_var)?;
+        dst_new_commitment.enforce_equal(&dst_new_var)?;
+
+        // === Constraint 4: Compute and verify transfer list hash ===
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &hash_inputs)?;
+
+        computed_hash.enforce_equal(&transfers_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Three distinct items: 30 of item 1 (volume 2), 5 of item 2, all 4 of item 3.
+    fn transfers() -> Vec<ItemTransfer> {
+        vec![
+            ItemTransfer { item_id: 1, amount: 30, item_volume: 2 },
+            ItemTransfer { item_id: 2, amount: 5, item_volume: 1 },
+            ItemTransfer { item_id: 3, amount: 4, item_volume: 10 },
+        ]
+    }
+
+    fn multi_transfer(transfers: Vec<ItemTransfer>) -> MultiTransferCircuit {
+        let mut src_tree = SparseMerkleTree::from_items(&[(1, 100), (2, 5), (3, 4)], DEFAULT_DEPTH);
+        let mut dst_tree = SparseMerkleTree::from_items(&[(1, 10), (9, 1)], DEFAULT_DEPTH);
+
+        let src =
+            TransferSide::debit(&mut src_tree, 245, Fr::from(1u64), Fr::from(2u64), &transfers);
+        let dst =
+            TransferSide::credit(&mut dst_tree, 21, Fr::from(3u64), Fr::from(4u64), &transfers);
+        MultiTransferCircuit::new(transfers, src, dst)
+    }
+
+    fn is_satisfied(circuit: MultiTransferCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_multi_transfer_three_items() {
+        let circuit = multi_transfer(transfers());
+        assert_eq!(circuit.src.as_ref().unwrap().new_state.current_volume, 140);
+        assert_eq!(circuit.dst.as_ref().unwrap().new_state.current_volume, 126);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("MultiTransfer (3 items) constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_amount_exceeds_source_balance_fails() {
+        let mut circuit = multi_transfer(transfers());
+
+        // The source held 5 of item 2; claim 6 moved
+        let transfers = circuit.transfers.as_mut().unwrap();
+        transfers[1].amount = 6;
+        circuit.transfers_hash = Some(compute_transfers_hash(transfers));
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer would cause quantity overflow")]
+    fn test_credit_quantity_overflow_panics() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, u64::MAX)], DEFAULT_DEPTH);
+        let transfer = ItemTransfer { item_id: 1, amount: 1, item_volume: 0 };
+
+        TransferSide::credit(&mut tree, 0, Fr::from(1u64), Fr::from(2u64), &[transfer]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer would cause volume overflow")]
+    fn test_credit_volume_overflow_panics() {
+        let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let transfer = ItemTransfer { item_id: 1, amount: 2, item_volume: u64::MAX / 2 + 1 };
+
+        TransferSide::credit(&mut tree, 0, Fr::from(1u64), Fr::from(2u64), &[transfer]);
+    }
+}
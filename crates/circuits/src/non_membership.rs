@@ -0,0 +1,148 @@
+//! NonMembership Circuit for SMT-based inventory.
+//!
+//! Proves that an inventory holds none of an item, e.g. for anti-cheat audits
+//! of banned items.
+//!
+//! Absent items have the empty default leaf in their own slot of the SMT, so
+//! the proof opens item_id's slot (bound to item_id's position) to that leaf.
+//!
+//! Public input: Poseidon(commitment, item_id)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::smt::{
+    verify_non_membership, MerkleProof, MerkleProofVar, SparseMerkleTree, DEFAULT_DEPTH,
+};
+use crate::smt_commitment::{InventoryState, InventoryStateVar};
+
+/// Compute the public input hash for NonMembership proof.
+pub fn compute_non_membership_hash(commitment: Fr, item_id: u64) -> Fr {
+    poseidon_hash_many(&[commitment, Fr::from(item_id)])
+}
+
+/// NonMembership Circuit.
+#[derive(Clone)]
+pub struct NonMembershipCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Public data (bound via hash)
+    /// Item ID that must be absent
+    pub item_id: Option<u64>,
+
+    // Private witnesses
+    /// Inventory state the commitment opens to
+    pub state: Option<InventoryState>,
+    /// Proof for item_id's slot in the inventory SMT
+    pub proof: Option<MerkleProof<Fr>>,
+}
+
+impl NonMembershipCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        let dummy_proof = MerkleProof::new(
+            vec![Fr::from(0u64); DEFAULT_DEPTH],
+            vec![false; DEFAULT_DEPTH],
+        );
+
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            item_id: Some(0),
+            state: Some(InventoryState::new(Fr::from(0u64), 0, Fr::from(0u64))),
+            proof: Some(dummy_proof),
+        }
+    }
+
+    /// Prove that `tree` holds none of `item_id`.
+    ///
+    /// The circuit is only satisfiable if the item is actually absent.
+    pub fn new(tree: &SparseMerkleTree, current_volume: u64, blinding: Fr, item_id: u64) -> Self {
+        let state = InventoryState::new(tree.root(), current_volume, blinding);
+        let public_hash = compute_non_membership_hash(state.commitment(), item_id);
+
+        Self {
+            public_hash: Some(public_hash),
+            item_id: Some(item_id),
+            state: Some(state),
+            proof: Some(tree.get_proof(item_id)),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for NonMembershipCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate witnesses ===
+        let item_id_var = FpVar::new_witness(cs.clone(), || {
+            self.item_id
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let state = InventoryStateVar::new_witness(cs.clone(), self.state.as_ref().unwrap())?;
+        let proof_var = MerkleProofVar::new_witness(cs.clone(), self.proof.as_ref().unwrap())?;
+
+        // === Constraint 1: item_id's slot holds the empty leaf ===
+        verify_non_membership(cs.clone(), &state.inventory_root, &item_id_var, &proof_var)?;
+
+        // === Constraint 2: Compute and verify public hash ===
+        let commitment_var = state.commitment(cs.clone())?;
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &[commitment_var, item_id_var])?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn inventory() -> SparseMerkleTree {
+        SparseMerkleTree::from_items(&[(1, 100), (2, 50)], DEFAULT_DEPTH)
+    }
+
+    fn is_satisfied(circuit: NonMembershipCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_item_absent() {
+        let circuit = NonMembershipCircuit::new(&inventory(), 150, Fr::from(1u64), 99);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("NonMembership constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_item_present_fails() {
+        let circuit = NonMembershipCircuit::new(&inventory(), 150, Fr::from(1u64), 2);
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_other_empty_slot_fails() {
+        // Open item 99's empty slot while claiming item 2 is absent
+        let tree = inventory();
+        let mut circuit = NonMembershipCircuit::new(&tree, 150, Fr::from(1u64), 2);
+        circuit.proof = Some(tree.get_proof(99));
+
+        assert!(!is_satisfied(circuit));
+    }
+}
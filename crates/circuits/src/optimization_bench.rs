@@ -3,18 +3,21 @@
 //! Run with: cargo test -p circuits optimization_bench --release -- --nocapture
 
 use ark_bn254::Fr;
-use ark_ff::{BigInteger, PrimeField};
+use ark_ff::PrimeField;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::prelude::*;
 use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef, SynthesisError};
 
 use crate::poseidon::poseidon_hash_two_var;
+use crate::range_check::enforce_range;
 
 // ============================================================================
 // RANGE CHECK COMPARISON
 // ============================================================================
 
-/// Current implementation: decomposes ALL 254 bits
+/// Naive implementation: decomposes ALL 254 bits.
+///
+/// Kept as the baseline for `enforce_range`, which allocates only `num_bits` bits.
 fn range_check_current<F: PrimeField>(
     _cs: ConstraintSystemRef<F>,
     value: &FpVar<F>,
@@ -29,50 +32,6 @@ fn range_check_current<F: PrimeField>(
     Ok(())
 }
 
-/// Optimized: only allocate bits we need, reconstruct and verify
-fn range_check_optimized<F: PrimeField>(
-    cs: ConstraintSystemRef<F>,
-    value: &FpVar<F>,
-    num_bits: usize,
-) -> Result<(), SynthesisError> {
-    // Allocate only the bits we need as witnesses
-    let bits: Vec<Boolean<F>> = (0..num_bits)
-        .map(|i| {
-            Boolean::new_witness(cs.clone(), || {
-                let v = value.value().unwrap_or_default();
-                let bigint = v.into_bigint();
-                Ok(bigint.get_bit(i))
-            })
-        })
-        .collect::<Result<Vec<_>, _>>()?;
-
-    // Reconstruct value from bits using linear combination
-    // value = sum(bit[i] * 2^i)
-    let mut coeff = F::one();
-    let two = F::from(2u64);
-    let mut lc_terms = Vec::with_capacity(num_bits);
-
-    for bit in &bits {
-        lc_terms.push((bit.clone(), coeff));
-        coeff *= two;
-    }
-
-    // Build reconstructed value as linear combination
-    let reconstructed = {
-        let mut result = FpVar::zero();
-        for (bit, coeff) in lc_terms {
-            let term = FpVar::constant(coeff);
-            result += bit.select(&term, &FpVar::zero())?;
-        }
-        result
-    };
-
-    // Enforce equality: if value >= 2^num_bits, this fails
-    value.enforce_equal(&reconstructed)?;
-
-    Ok(())
-}
-
 #[test]
 fn bench_range_check_comparison() {
     println!("\n========================================");
@@ -92,7 +51,7 @@ fn bench_range_check_comparison() {
     // Optimized implementation
     let cs_optimized = ConstraintSystem::<Fr>::new_ref();
     let value_optimized = FpVar::new_witness(cs_optimized.clone(), || Ok(test_value)).unwrap();
-    range_check_optimized(cs_optimized.clone(), &value_optimized, 32).unwrap();
+    enforce_range(cs_optimized.clone(), &value_optimized, 32).unwrap();
     let optimized_constraints = cs_optimized.num_constraints();
     assert!(cs_optimized.is_satisfied().unwrap(), "Optimized implementation should be satisfied");
 
@@ -108,7 +67,7 @@ fn bench_range_check_comparison() {
     let cs_overflow = ConstraintSystem::<Fr>::new_ref();
     let overflow_value = Fr::from(1u64 << 32); // 2^32, doesn't fit in 32 bits
     let value_overflow = FpVar::new_witness(cs_overflow.clone(), || Ok(overflow_value)).unwrap();
-    range_check_optimized(cs_overflow.clone(), &value_overflow, 32).unwrap();
+    enforce_range(cs_overflow.clone(), &value_overflow, 32).unwrap();
     assert!(!cs_overflow.is_satisfied().unwrap(), "Optimized should reject overflow");
     println!("  Overflow rejection: ✓");
 
@@ -116,7 +75,7 @@ fn bench_range_check_comparison() {
     let cs_negative = ConstraintSystem::<Fr>::new_ref();
     let negative_value = Fr::from(0u64) - Fr::from(1u64); // -1 in field = huge positive
     let value_negative = FpVar::new_witness(cs_negative.clone(), || Ok(negative_value)).unwrap();
-    range_check_optimized(cs_negative.clone(), &value_negative, 32).unwrap();
+    enforce_range(cs_negative.clone(), &value_negative, 32).unwrap();
     assert!(!cs_negative.is_satisfied().unwrap(), "Optimized should reject negative wrap");
     println!("  Negative wrap rejection: ✓");
 
@@ -160,7 +119,7 @@ fn bench_smt_depth(depth: usize) -> usize {
     let leaf_hash = FpVar::new_witness(cs.clone(), || Ok(Fr::from(456u64))).unwrap();
 
     let before = cs.num_constraints();
-    compute_root_from_path_depth(cs.clone(), &leaf_hash, &siblings, &indices).unwrap();
+    let _ = compute_root_from_path_depth(cs.clone(), &leaf_hash, &siblings, &indices).unwrap();
     let after = cs.num_constraints();
 
     after - before
@@ -259,7 +218,7 @@ fn bench_full_circuit_breakdown() {
     let cs = ConstraintSystem::<Fr>::new_ref();
     let x = FpVar::new_witness(cs.clone(), || Ok(Fr::from(1000u64))).unwrap();
     let before = cs.num_constraints();
-    range_check_optimized(cs.clone(), &x, 32).unwrap();
+    enforce_range(cs.clone(), &x, 32).unwrap();
     let range_optimized = cs.num_constraints() - before;
 
     println!("Component Constraint Costs:");
@@ -333,8 +292,8 @@ fn bench_combined_optimizations() {
         let val2 = FpVar::new_witness(cs_optimized.clone(), || Ok(Fr::from(500u64))).unwrap();
 
         // Two range checks (optimized)
-        range_check_optimized(cs_optimized.clone(), &val1, 32).unwrap();
-        range_check_optimized(cs_optimized.clone(), &val2, 32).unwrap();
+        enforce_range(cs_optimized.clone(), &val1, 32).unwrap();
+        enforce_range(cs_optimized.clone(), &val2, 32).unwrap();
 
         // SMT path (depth 12 - same for fair comparison)
         let siblings: Vec<FpVar<Fr>> = (0..12)
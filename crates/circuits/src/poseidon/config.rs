@@ -1,10 +1,15 @@
 //! Poseidon configuration for BN254.
 //!
-//! Uses standard parameters for 128-bit security.
+//! Uses standard parameters for 128-bit security. Round constants and the MDS
+//! matrix come from the Grain LFSR of the Poseidon reference implementation
+//! (`generate_parameters_grain.sage`); at rate 2 they are the same constants
+//! circomlib uses for width 3.
 
 use ark_bn254::Fr;
-use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
-use ark_ff::MontFp;
+use ark_crypto_primitives::sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig};
+use ark_ff::{Field, PrimeField, Zero};
+use std::fmt;
+use std::sync::{Arc, OnceLock};
 
 /// Number of full rounds (beginning + end)
 const FULL_ROUNDS: usize = 8;
@@ -15,6 +20,99 @@ const PARTIAL_ROUNDS: usize = 57;
 /// S-box exponent
 const ALPHA: u64 = 5;
 
+/// Sponge rate. Every hash in the crate (leaves, nodes, commitments, public
+/// hashes) is defined by absorbing at this rate; changing it changes all of them.
+const RATE: usize = 2;
+
+/// Sponge capacity
+const CAPACITY: usize = 1;
+
+/// Partial rounds for 128-bit security with the x^5 S-box over BN254, indexed by
+/// rate (state width = rate + 1), as in the Poseidon paper's parameter tables.
+const PARTIAL_ROUNDS_BY_RATE: [(usize, usize); 7] =
+    [(2, 57), (3, 56), (4, 60), (5, 60), (6, 63), (7, 64), (8, 63)];
+
+/// A Poseidon configuration that does not match the layout the crate's hashes use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// Rate or capacity differs from the expected sponge shape.
+    SpongeShape { rate: usize, capacity: usize },
+    /// MDS matrix is not (rate + capacity) square.
+    MdsWidth { expected: usize, actual: usize },
+    /// Wrong number of round-constant rows, or a row of the wrong width.
+    RoundConstants { round: usize, expected: usize, actual: usize },
+    /// No round parameters for this rate.
+    UnsupportedRate { rate: usize },
+    /// MDS matrix has a singular square submatrix.
+    NotMds,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::SpongeShape { rate, capacity } => write!(
+                f,
+                "sponge rate {} / capacity {} does not match expected {} / {}",
+                rate, capacity, RATE, CAPACITY
+            ),
+            ConfigError::MdsWidth { expected, actual } => {
+                write!(f, "MDS matrix width {} does not match state width {}", actual, expected)
+            }
+            ConfigError::RoundConstants { round, expected, actual } => write!(
+                f,
+                "round constants at round {}: expected {} elements, got {}",
+                round, expected, actual
+            ),
+            ConfigError::UnsupportedRate { rate } => {
+                write!(f, "no round parameters for sponge rate {}", rate)
+            }
+            ConfigError::NotMds => write!(f, "MDS matrix has a singular square submatrix"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Check that `config` has the sponge shape the crate's hashes are defined with.
+///
+/// A different rate does not fail loudly: the sponge still works, but every
+/// commitment and Merkle root comes out different.
+pub fn validate_config(config: &PoseidonConfig<Fr>) -> Result<(), ConfigError> {
+    if config.rate != RATE || config.capacity != CAPACITY {
+        return Err(ConfigError::SpongeShape {
+            rate: config.rate,
+            capacity: config.capacity,
+        });
+    }
+
+    check_dimensions(config)
+}
+
+/// Check that the MDS matrix and round constants match the state width.
+fn check_dimensions(config: &PoseidonConfig<Fr>) -> Result<(), ConfigError> {
+    let width = config.rate + config.capacity;
+    if config.mds.len() != width {
+        return Err(ConfigError::MdsWidth { expected: width, actual: config.mds.len() });
+    }
+    if let Some(row) = config.mds.iter().find(|row| row.len() != width) {
+        return Err(ConfigError::MdsWidth { expected: width, actual: row.len() });
+    }
+
+    let num_rounds = config.full_rounds + config.partial_rounds;
+    if config.ark.len() != num_rounds {
+        return Err(ConfigError::RoundConstants {
+            round: config.ark.len(),
+            expected: num_rounds,
+            actual: config.ark.len(),
+        });
+    }
+    if let Some((round, row)) = config.ark.iter().enumerate().find(|(_, row)| row.len() != width) {
+        return Err(ConfigError::RoundConstants { round, expected: width, actual: row.len() });
+    }
+
+    Ok(())
+}
+
 /// Get the standard Poseidon configuration for BN254 scalar field.
 ///
 /// Parameters:
@@ -23,68 +121,134 @@ const ALPHA: u64 = 5;
 /// - Full rounds: 8 (4 at start, 4 at end)
 /// - Partial rounds: 57
 /// - Alpha: 5 (x^5 S-box)
+///
+/// Returns an owned copy of [`poseidon_config_cached`].
 pub fn poseidon_config() -> PoseidonConfig<Fr> {
-    // MDS matrix (3x3 for rate=2, capacity=1)
-    let mds = vec![
-        vec![
-            MontFp!("7511745149465107256748700652201246547602992235352608707588321460060273774987"),
-            MontFp!("10370080108974718697676803824769673834027675643658433702224577712625900127200"),
-            MontFp!("19705173408229649878903981084052839426532978878058043055305024233888854471533"),
-        ],
-        vec![
-            MontFp!("18732019378264290557468133440468564866454307626475683536618613112504878618481"),
-            MontFp!("20870176810702568768751421378473869562658540583882454726129544628203806653987"),
-            MontFp!("7266061498423634438932006217945904744987532209093972706694887950396501989428"),
-        ],
-        vec![
-            MontFp!("9131299761947733513298312097611845208338517739621853568979632113419485819303"),
-            MontFp!("10595341252162738537912664445405114076324478519622938027420701542910180337937"),
-            MontFp!("11597556804922396090267472882856054602429588299176362916247939723151043581408"),
-        ],
-    ];
-
-    // Round constants (ARK) - generated using standard Poseidon method
-    let ark = generate_round_constants();
+    (*poseidon_config_cached()).clone()
+}
+
+/// The standard configuration, generated once per process and shared.
+///
+/// Prefer this over [`poseidon_config`] on hot paths: it only bumps a
+/// reference count instead of copying the round constants.
+pub fn poseidon_config_cached() -> Arc<PoseidonConfig<Fr>> {
+    static CONFIG: OnceLock<Arc<PoseidonConfig<Fr>>> = OnceLock::new();
+
+    CONFIG
+        .get_or_init(|| {
+            let config = grain_config(RATE, PARTIAL_ROUNDS);
+            debug_assert_eq!(validate_config(&config), Ok(()));
+            Arc::new(config)
+        })
+        .clone()
+}
+
+/// Get a Poseidon configuration with the given sponge rate (state width `rate + 1`).
+///
+/// Rate 2 returns [`poseidon_config`]. Other rates use Grain LFSR constants
+/// generated for their width and round counts; the MDS matrix is checked to be MDS.
+///
+/// Every hash in the crate is defined at rate 2, so other rates are only for
+/// experiments with the `*_with` hash functions: they produce different hashes,
+/// commitments and keys.
+pub fn poseidon_config_with_rate(rate: usize) -> Result<PoseidonConfig<Fr>, ConfigError> {
+    if rate == RATE {
+        return Ok(poseidon_config());
+    }
+
+    let &(_, partial_rounds) = PARTIAL_ROUNDS_BY_RATE
+        .iter()
+        .find(|&&(r, _)| r == rate)
+        .ok_or(ConfigError::UnsupportedRate { rate })?;
+
+    let config = grain_config(rate, partial_rounds);
+    if !is_mds(&config.mds) {
+        return Err(ConfigError::NotMds);
+    }
+    check_dimensions(&config)?;
+
+    Ok(config)
+}
+
+/// Configuration with Grain LFSR round constants and MDS matrix.
+///
+/// The MDS matrix is the Cauchy matrix 1 / (x_i + y_j) over LFSR-sampled x and
+/// y, taking the first candidate as the reference generator does for these
+/// widths.
+fn grain_config(rate: usize, partial_rounds: usize) -> PoseidonConfig<Fr> {
+    let (ark, mds) = find_poseidon_ark_and_mds::<Fr>(
+        Fr::MODULUS_BIT_SIZE as u64,
+        rate,
+        FULL_ROUNDS as u64,
+        partial_rounds as u64,
+        0,
+    );
 
     PoseidonConfig {
         full_rounds: FULL_ROUNDS,
-        partial_rounds: PARTIAL_ROUNDS,
+        partial_rounds,
         alpha: ALPHA,
         ark,
         mds,
-        rate: 2,
-        capacity: 1,
+        rate,
+        capacity: CAPACITY,
     }
 }
 
-/// Generate round constants using a simple deterministic method.
-/// In production, these should come from a proper generation ceremony.
-fn generate_round_constants() -> Vec<Vec<Fr>> {
-    let num_rounds = FULL_ROUNDS + PARTIAL_ROUNDS;
-    let width = 3; // rate + capacity
+/// Whether every square submatrix of `matrix` is nonsingular.
+fn is_mds(matrix: &[Vec<Fr>]) -> bool {
+    let n = matrix.len();
+    let subsets_of_size =
+        |k: u32| (1u32..1 << n).filter(move |subset| subset.count_ones() == k);
 
-    let mut ark = Vec::with_capacity(num_rounds);
-
-    // Use a simple hash-based generation for reproducibility
-    // In production, use proper Poseidon constant generation
-    let mut state = Fr::from(0x504f534549444f4eu64); // "POSEIDON" in hex
+    (1..=n as u32).all(|k| {
+        subsets_of_size(k).all(|rows| {
+            subsets_of_size(k).all(|cols| {
+                let submatrix: Vec<Vec<Fr>> = (0..n)
+                    .filter(|i| rows & (1 << i) != 0)
+                    .map(|i| {
+                        (0..n)
+                            .filter(|j| cols & (1 << j) != 0)
+                            .map(|j| matrix[i][j])
+                            .collect()
+                    })
+                    .collect();
+                !determinant(submatrix).is_zero()
+            })
+        })
+    })
+}
 
-    for _ in 0..num_rounds {
-        let mut round_constants = Vec::with_capacity(width);
-        for _ in 0..width {
-            // Simple deterministic generation
-            state = state * state + Fr::from(7u64);
-            round_constants.push(state);
+/// Determinant by Gaussian elimination.
+fn determinant(mut m: Vec<Vec<Fr>>) -> Fr {
+    let n = m.len();
+    let mut det = Fr::from(1u64);
+    for col in 0..n {
+        let Some(pivot) = (col..n).find(|&row| !m[row][col].is_zero()) else {
+            return Fr::zero();
+        };
+        if pivot != col {
+            m.swap(pivot, col);
+            det = -det;
+        }
+        det *= m[col][col];
+        let inv = m[col][col].inverse().unwrap();
+        let (upper, lower) = m.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for row in lower {
+            let factor = row[col] * inv;
+            for (entry, &pivot_entry) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *entry -= factor * pivot_entry;
+            }
         }
-        ark.push(round_constants);
     }
-
-    ark
+    det
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ark_ff::MontFp;
 
     #[test]
     fn test_config_valid() {
@@ -95,5 +259,80 @@ mod tests {
         assert_eq!(config.capacity, 1);
         assert_eq!(config.mds.len(), 3);
         assert_eq!(config.ark.len(), FULL_ROUNDS + PARTIAL_ROUNDS);
+        assert_eq!(validate_config(&config), Ok(()));
+    }
+
+    #[test]
+    fn test_incompatible_rate_flagged() {
+        let mut config = poseidon_config();
+        config.rate = 3;
+        config.capacity = 0;
+
+        assert_eq!(
+            validate_config(&config),
+            Err(ConfigError::SpongeShape { rate: 3, capacity: 0 })
+        );
+    }
+
+    #[test]
+    fn test_config_with_rate() {
+        assert_eq!(poseidon_config_with_rate(2).unwrap().mds, poseidon_config().mds);
+
+        let config = poseidon_config_with_rate(4).unwrap();
+        assert_eq!(config.rate, 4);
+        assert_eq!(config.mds.len(), 5);
+        assert_eq!(config.ark.len(), FULL_ROUNDS + 60);
+        assert!(is_mds(&config.mds));
+
+        assert_eq!(
+            poseidon_config_with_rate(16).unwrap_err(),
+            ConfigError::UnsupportedRate { rate: 16 }
+        );
+    }
+
+    #[test]
+    fn test_config_cached_once() {
+        let first = poseidon_config_cached();
+        let second = poseidon_config_cached();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(poseidon_config().ark, first.ark);
+    }
+
+    #[test]
+    fn test_default_mds_is_mds() {
+        assert!(is_mds(&poseidon_config().mds));
+    }
+
+    #[test]
+    fn test_matches_reference_constants() {
+        // First round constant and MDS entry of the reference width-3 parameters
+        let config = poseidon_config();
+        assert_eq!(
+            config.ark[0][0],
+            MontFp!("6745197990210204598374042828761989596302876299545964402857411729872131034734")
+        );
+        assert_eq!(
+            config.mds[0][0],
+            MontFp!("7511745149465107256748700652201246547602992235352608707588321460060273774987")
+        );
+    }
+
+    #[test]
+    fn test_singular_matrix_not_mds() {
+        let mut mds = poseidon_config().mds;
+        // A zero entry is a singular 1x1 submatrix
+        mds[1][2] = Fr::zero();
+        assert!(!is_mds(&mds));
+    }
+
+    #[test]
+    fn test_truncated_round_constants_flagged() {
+        let mut config = poseidon_config();
+        config.ark[5].pop();
+
+        assert_eq!(
+            validate_config(&config),
+            Err(ConfigError::RoundConstants { round: 5, expected: 3, actual: 2 })
+        );
     }
 }
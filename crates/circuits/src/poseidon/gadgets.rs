@@ -4,16 +4,17 @@ use ark_bn254::Fr;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
 use ark_crypto_primitives::sponge::poseidon::constraints::PoseidonSpongeVar;
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
 use ark_crypto_primitives::sponge::constraints::CryptographicSpongeVar;
 
-use super::config::poseidon_config;
+use super::config::poseidon_config_cached;
 
 /// Hash a single field element in-circuit.
 pub fn poseidon_hash_var(
     cs: ConstraintSystemRef<Fr>,
     input: &FpVar<Fr>,
 ) -> Result<FpVar<Fr>, SynthesisError> {
-    let config = poseidon_config();
+    let config = poseidon_config_cached();
     let mut sponge = PoseidonSpongeVar::new(cs, &config);
     sponge.absorb(input)?;
     let result = sponge.squeeze_field_elements(1)?;
@@ -26,7 +27,7 @@ pub fn poseidon_hash_two_var(
     a: &FpVar<Fr>,
     b: &FpVar<Fr>,
 ) -> Result<FpVar<Fr>, SynthesisError> {
-    let config = poseidon_config();
+    let config = poseidon_config_cached();
     let mut sponge = PoseidonSpongeVar::new(cs, &config);
     sponge.absorb(a)?;
     sponge.absorb(b)?;
@@ -39,8 +40,17 @@ pub fn poseidon_hash_many_var(
     cs: ConstraintSystemRef<Fr>,
     inputs: &[FpVar<Fr>],
 ) -> Result<FpVar<Fr>, SynthesisError> {
-    let config = poseidon_config();
-    let mut sponge = PoseidonSpongeVar::new(cs, &config);
+    poseidon_hash_many_var_with(cs, &poseidon_config_cached(), inputs)
+}
+
+/// Hash multiple field elements in-circuit with a specific configuration
+/// (see `poseidon_config_with_rate`).
+pub fn poseidon_hash_many_var_with(
+    cs: ConstraintSystemRef<Fr>,
+    config: &PoseidonConfig<Fr>,
+    inputs: &[FpVar<Fr>],
+) -> Result<FpVar<Fr>, SynthesisError> {
+    let mut sponge = PoseidonSpongeVar::new(cs, config);
     for input in inputs {
         sponge.absorb(input)?;
     }
@@ -10,6 +10,11 @@ mod gadgets;
 #[cfg(test)]
 mod tests;
 
-pub use native::{poseidon_hash, poseidon_hash_two, poseidon_hash_many};
-pub use gadgets::{poseidon_hash_var, poseidon_hash_two_var, poseidon_hash_many_var};
-pub use config::poseidon_config;
+pub use native::{poseidon_hash, poseidon_hash_two, poseidon_hash_many, poseidon_hash_many_with};
+pub use gadgets::{
+    poseidon_hash_var, poseidon_hash_two_var, poseidon_hash_many_var, poseidon_hash_many_var_with,
+};
+pub use config::{
+    poseidon_config, poseidon_config_cached, poseidon_config_with_rate, validate_config,
+    ConfigError,
+};
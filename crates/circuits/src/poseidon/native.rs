@@ -1,14 +1,14 @@
 //! Native Poseidon hash functions (outside circuits).
 
 use ark_bn254::Fr;
-use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+use ark_crypto_primitives::sponge::poseidon::{PoseidonConfig, PoseidonSponge};
 use ark_crypto_primitives::sponge::CryptographicSponge;
 
-use super::config::poseidon_config;
+use super::config::poseidon_config_cached;
 
 /// Hash a single field element.
 pub fn poseidon_hash(input: Fr) -> Fr {
-    let config = poseidon_config();
+    let config = poseidon_config_cached();
     let mut sponge = PoseidonSponge::new(&config);
     sponge.absorb(&input);
     sponge.squeeze_field_elements(1)[0]
@@ -16,7 +16,7 @@ pub fn poseidon_hash(input: Fr) -> Fr {
 
 /// Hash two field elements.
 pub fn poseidon_hash_two(a: Fr, b: Fr) -> Fr {
-    let config = poseidon_config();
+    let config = poseidon_config_cached();
     let mut sponge = PoseidonSponge::new(&config);
     sponge.absorb(&a);
     sponge.absorb(&b);
@@ -25,8 +25,13 @@ pub fn poseidon_hash_two(a: Fr, b: Fr) -> Fr {
 
 /// Hash multiple field elements.
 pub fn poseidon_hash_many(inputs: &[Fr]) -> Fr {
-    let config = poseidon_config();
-    let mut sponge = PoseidonSponge::new(&config);
+    poseidon_hash_many_with(&poseidon_config_cached(), inputs)
+}
+
+/// Hash multiple field elements with a specific configuration
+/// (see `poseidon_config_with_rate`).
+pub fn poseidon_hash_many_with(config: &PoseidonConfig<Fr>, inputs: &[Fr]) -> Fr {
+    let mut sponge = PoseidonSponge::new(config);
     for input in inputs {
         sponge.absorb(input);
     }
@@ -0,0 +1,409 @@
+//! Predicate Circuit for SMT-based inventory.
+//!
+//! Proves that an inventory satisfies a boolean formula over item thresholds,
+//! e.g. "(>= 3 of A AND >= 1 of B) OR >= 5 of C" for quest requirements, without
+//! revealing which branch holds or any quantities.
+//!
+//! The shape of the formula (which nodes are AND/OR and how many children each
+//! has) is fixed when the circuit is built, so it is baked into the keys. The
+//! item_ids and thresholds at the leaves are witnesses bound into the public hash,
+//! so one key serves every formula of the same shape.
+//!
+//! Public input: Poseidon(commitment, formula encoding)
+//! (see [`Predicate::encode`])
+
+use std::fmt;
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::{enforce_u32_range, is_geq, MAX_RANGE_VALUE};
+use crate::smt::{verify_quantity, MerkleProof, MerkleProofVar, SparseMerkleTree};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+
+/// Encoding tags for formula nodes.
+const TAG_THRESHOLD: u64 = 0;
+const TAG_AND: u64 = 1;
+const TAG_OR: u64 = 2;
+
+/// A formula that cannot be proven.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PredicateError {
+    /// A threshold exceeds `MAX_RANGE_VALUE`, the largest value the circuit compares.
+    ThresholdOutOfRange { item_id: u64, min_quantity: u64 },
+}
+
+impl fmt::Display for PredicateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PredicateError::ThresholdOutOfRange { item_id, min_quantity } => write!(
+                f,
+                "threshold {} for item {} exceeds the supported maximum {}",
+                min_quantity, item_id, MAX_RANGE_VALUE
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PredicateError {}
+
+/// A boolean formula over item thresholds.
+///
+/// `And` and `Or` must have at least one child.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Predicate {
+    /// Inventory holds at least `min_quantity` of `item_id`
+    Threshold { item_id: u64, min_quantity: u64 },
+    /// All children hold
+    And(Vec<Predicate>),
+    /// At least one child holds
+    Or(Vec<Predicate>),
+}
+
+impl Predicate {
+    /// Encode the formula as field elements, in prefix order.
+    ///
+    /// A threshold is `[0, item_id, min_quantity]`; an AND/OR node is
+    /// `[1 or 2, child count]` followed by its children.
+    pub fn encode(&self) -> Vec<Fr> {
+        let mut out = Vec::new();
+        self.encode_into(&mut out);
+        out
+    }
+
+    fn encode_into(&self, out: &mut Vec<Fr>) {
+        match self {
+            Predicate::Threshold { item_id, min_quantity } => {
+                out.extend([Fr::from(TAG_THRESHOLD), Fr::from(*item_id), Fr::from(*min_quantity)]);
+            }
+            Predicate::And(children) | Predicate::Or(children) => {
+                let tag = if matches!(self, Predicate::And(_)) { TAG_AND } else { TAG_OR };
+                out.extend([Fr::from(tag), Fr::from(children.len() as u64)]);
+                for child in children {
+                    child.encode_into(out);
+                }
+            }
+        }
+    }
+
+    /// Check that every threshold fits the range the circuit compares.
+    ///
+    /// Each leaf is range checked on its own, so an out-of-range threshold
+    /// would make the whole formula unprovable, even through another branch
+    /// of an OR that holds.
+    pub fn validate(&self) -> Result<(), PredicateError> {
+        match self {
+            Predicate::Threshold { item_id, min_quantity } if *min_quantity > MAX_RANGE_VALUE => {
+                Err(PredicateError::ThresholdOutOfRange {
+                    item_id: *item_id,
+                    min_quantity: *min_quantity,
+                })
+            }
+            Predicate::Threshold { .. } => Ok(()),
+            Predicate::And(children) | Predicate::Or(children) => {
+                children.iter().try_for_each(Predicate::validate)
+            }
+        }
+    }
+
+    /// Item IDs at the threshold leaves, in prefix order.
+    pub fn item_ids(&self) -> Vec<u64> {
+        match self {
+            Predicate::Threshold { item_id, .. } => vec![*item_id],
+            Predicate::And(children) | Predicate::Or(children) => {
+                children.iter().flat_map(Predicate::item_ids).collect()
+            }
+        }
+    }
+}
+
+/// Compute the public input hash for Predicate proof.
+pub fn compute_predicate_hash(commitment: Fr, predicate: &Predicate) -> Fr {
+    let mut inputs = vec![commitment];
+    inputs.extend(predicate.encode());
+    poseidon_hash_many(&inputs)
+}
+
+/// Predicate Circuit.
+#[derive(Clone)]
+pub struct PredicateCircuit {
+    /// Formula to prove (shape is a circuit constant, leaf values are witnesses)
+    pub predicate: Predicate,
+
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Commitment components (witnesses)
+    /// Inventory SMT root
+    pub inventory_root: Option<Fr>,
+    /// Current volume
+    pub current_volume: Option<u64>,
+    /// Blinding factor
+    pub blinding: Option<Fr>,
+
+    // Leaf witnesses, in the order of `predicate.item_ids()`
+    /// Quantity held of each leaf's item (0 if absent)
+    pub quantities: Option<Vec<u64>>,
+    /// Proof for each leaf's item in the inventory SMT
+    pub proofs: Option<Vec<MerkleProof<Fr>>>,
+}
+
+impl PredicateCircuit {
+    /// Create an empty circuit for setup, for formulas shaped like `predicate`.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty(predicate: Predicate) -> Self {
+        use crate::smt::DEFAULT_DEPTH;
+
+        let dummy_proof = MerkleProof::new(
+            vec![Fr::from(0u64); DEFAULT_DEPTH],
+            vec![false; DEFAULT_DEPTH],
+        );
+        let num_leaves = predicate.item_ids().len();
+
+        Self {
+            predicate,
+            public_hash: Some(Fr::from(0u64)),
+            inventory_root: Some(Fr::from(0u64)),
+            current_volume: Some(0),
+            blinding: Some(Fr::from(0u64)),
+            quantities: Some(vec![0; num_leaves]),
+            proofs: Some(vec![dummy_proof; num_leaves]),
+        }
+    }
+
+    /// Create a new circuit, reading each leaf item's quantity and proof from `tree`.
+    ///
+    /// Fails if a threshold is out of range (see [`Predicate::validate`]).
+    pub fn new(
+        predicate: Predicate,
+        tree: &SparseMerkleTree,
+        current_volume: u64,
+        blinding: Fr,
+    ) -> Result<Self, PredicateError> {
+        predicate.validate()?;
+
+        let inventory_root = tree.root();
+        let item_ids = predicate.item_ids();
+        let quantities = item_ids.iter().map(|&id| tree.get(id)).collect();
+        let proofs = item_ids.iter().map(|&id| tree.get_proof(id)).collect();
+
+        let commitment = create_smt_commitment(inventory_root, current_volume, blinding);
+        let public_hash = compute_predicate_hash(commitment, &predicate);
+
+        Ok(Self {
+            predicate,
+            public_hash: Some(public_hash),
+            inventory_root: Some(inventory_root),
+            current_volume: Some(current_volume),
+            blinding: Some(blinding),
+            quantities: Some(quantities),
+            proofs: Some(proofs),
+        })
+    }
+}
+
+/// Evaluate `predicate` in-circuit, consuming one leaf witness per threshold
+/// and appending the formula encoding to `encoding`.
+fn evaluate_var<'a>(
+    cs: ConstraintSystemRef<Fr>,
+    predicate: &Predicate,
+    root: &FpVar<Fr>,
+    leaves: &mut impl Iterator<Item = (&'a u64, &'a MerkleProof<Fr>)>,
+    encoding: &mut Vec<FpVar<Fr>>,
+) -> Result<Boolean<Fr>, SynthesisError> {
+    match predicate {
+        Predicate::Threshold { item_id, min_quantity } => {
+            let (&quantity, proof) = leaves.next().expect("one leaf witness per threshold");
+
+            let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(*item_id)))?;
+            let min_quantity_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(*min_quantity)))?;
+            let quantity_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(quantity)))?;
+            let proof_var = MerkleProofVar::new_witness(cs.clone(), proof)?;
+
+            verify_quantity(cs.clone(), root, &item_id_var, &quantity_var, &proof_var)?;
+
+            // is_geq needs both sides to fit in 32 bits
+            enforce_u32_range(cs.clone(), &quantity_var)?;
+            enforce_u32_range(cs.clone(), &min_quantity_var)?;
+            let holds = is_geq(cs, &quantity_var, &min_quantity_var)?;
+
+            encoding.extend([
+                FpVar::constant(Fr::from(TAG_THRESHOLD)),
+                item_id_var,
+                min_quantity_var,
+            ]);
+            Ok(holds)
+        }
+        Predicate::And(children) | Predicate::Or(children) => {
+            assert!(!children.is_empty(), "AND/OR nodes need at least one child");
+
+            let is_and = matches!(predicate, Predicate::And(_));
+            let tag = if is_and { TAG_AND } else { TAG_OR };
+            encoding.extend([
+                FpVar::constant(Fr::from(tag)),
+                FpVar::constant(Fr::from(children.len() as u64)),
+            ]);
+
+            let results = children
+                .iter()
+                .map(|child| evaluate_var(cs.clone(), child, root, leaves, encoding))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if is_and {
+                Boolean::kary_and(&results)
+            } else {
+                Boolean::kary_or(&results)
+            }
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for PredicateCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate commitment witnesses ===
+        let root_var = FpVar::new_witness(cs.clone(), || {
+            self.inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let volume_var = FpVar::new_witness(cs.clone(), || {
+            self.current_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let quantities = self.quantities.as_ref().unwrap();
+        let proofs = self.proofs.as_ref().unwrap();
+        let num_leaves = self.predicate.item_ids().len();
+        assert_eq!(quantities.len(), num_leaves, "one quantity per threshold");
+        assert_eq!(proofs.len(), num_leaves, "one proof per threshold");
+
+        // === Constraint 1: Evaluate the formula over the inventory ===
+        let mut leaves = quantities.iter().zip(proofs);
+        let mut encoding = Vec::new();
+        let satisfied = evaluate_var(
+            cs.clone(),
+            &self.predicate,
+            &root_var,
+            &mut leaves,
+            &mut encoding,
+        )?;
+
+        // === Constraint 2: Formula holds ===
+        satisfied.enforce_equal(&Boolean::TRUE)?;
+
+        // === Constraint 3: Compute commitment using Poseidon ===
+        let commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &root_var,
+            &volume_var,
+            &blinding_var,
+        )?;
+
+        // === Constraint 4: Compute and verify public hash ===
+        let mut inputs = vec![commitment_var];
+        inputs.extend(encoding);
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::DEFAULT_DEPTH;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    const A: u64 = 1;
+    const B: u64 = 2;
+    const C: u64 = 3;
+
+    /// (>= 3 of A AND >= 1 of B) OR >= 5 of C
+    fn quest() -> Predicate {
+        Predicate::Or(vec![
+            Predicate::And(vec![
+                Predicate::Threshold { item_id: A, min_quantity: 3 },
+                Predicate::Threshold { item_id: B, min_quantity: 1 },
+            ]),
+            Predicate::Threshold { item_id: C, min_quantity: 5 },
+        ])
+    }
+
+    fn is_satisfied(items: &[(u64, u64)]) -> bool {
+        let tree = SparseMerkleTree::from_items(items, DEFAULT_DEPTH);
+        let circuit = PredicateCircuit::new(quest(), &tree, 0, Fr::from(12345u64)).unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        println!("Predicate (3 thresholds) constraints: {}", cs.num_constraints());
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_predicate_satisfied_by_and_branch() {
+        assert!(is_satisfied(&[(A, 3), (B, 1)]));
+    }
+
+    #[test]
+    fn test_predicate_satisfied_by_or_branch() {
+        assert!(is_satisfied(&[(C, 5)]));
+    }
+
+    #[test]
+    fn test_predicate_unsatisfiable() {
+        // Enough A but no B, and one C short
+        assert!(!is_satisfied(&[(A, 10), (C, 4)]));
+    }
+
+    #[test]
+    fn test_out_of_range_threshold_rejected() {
+        // The first branch holds, but the second cannot be compared in-circuit
+        let too_large = MAX_RANGE_VALUE + 1;
+        let predicate = Predicate::Or(vec![
+            Predicate::Threshold { item_id: A, min_quantity: 3 },
+            Predicate::Threshold { item_id: C, min_quantity: too_large },
+        ]);
+        let tree = SparseMerkleTree::from_items(&[(A, 3)], DEFAULT_DEPTH);
+
+        let result = PredicateCircuit::new(predicate, &tree, 0, Fr::from(12345u64));
+        assert_eq!(
+            result.err(),
+            Some(PredicateError::ThresholdOutOfRange { item_id: C, min_quantity: too_large })
+        );
+
+        // The largest comparable threshold is still accepted
+        let predicate = Predicate::Or(vec![
+            Predicate::Threshold { item_id: A, min_quantity: 3 },
+            Predicate::Threshold { item_id: C, min_quantity: MAX_RANGE_VALUE },
+        ]);
+        let circuit = PredicateCircuit::new(predicate, &tree, 0, Fr::from(12345u64)).unwrap();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_predicate_encoding_binds_thresholds() {
+        let mut easier = quest();
+        if let Predicate::Or(children) = &mut easier {
+            children[1] = Predicate::Threshold { item_id: C, min_quantity: 4 };
+        }
+
+        assert_ne!(
+            compute_predicate_hash(Fr::from(1u64), &quest()),
+            compute_predicate_hash(Fr::from(1u64), &easier)
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! QuantityRange Circuit for SMT-based inventory.
+//!
+//! Proves that an inventory holds between `min_quantity` and `max_quantity`
+//! (inclusive) of an item without revealing the exact amount, e.g. for
+//! marketplace escrow. `min_quantity == max_quantity` proves the exact quantity.
+//!
+//! Both bounds are checked with `enforce_geq`, so they should not exceed
+//! `MAX_RANGE_VALUE`.
+//!
+//! Public input: Poseidon(commitment, item_id, min_quantity, max_quantity)
+
+use std::fmt;
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::enforce_geq;
+use crate::smt::{verify_quantity, MerkleProof, MerkleProofVar, SparseMerkleTree, DEFAULT_DEPTH};
+use crate::smt_commitment::{InventoryState, InventoryStateVar};
+
+/// Compute the public input hash for QuantityRange proof.
+pub fn compute_quantity_range_hash(
+    commitment: Fr,
+    item_id: u64,
+    min_quantity: u64,
+    max_quantity: u64,
+) -> Fr {
+    let inputs = vec![
+        commitment,
+        Fr::from(item_id),
+        Fr::from(min_quantity),
+        Fr::from(max_quantity),
+    ];
+    poseidon_hash_many(&inputs)
+}
+
+/// A quantity range whose maximum is below its minimum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidQuantityRange {
+    pub min_quantity: u64,
+    pub max_quantity: u64,
+}
+
+impl fmt::Display for InvalidQuantityRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "max_quantity {} is below min_quantity {}",
+            self.max_quantity, self.min_quantity
+        )
+    }
+}
+
+impl std::error::Error for InvalidQuantityRange {}
+
+/// QuantityRange Circuit.
+#[derive(Clone)]
+pub struct QuantityRangeCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Public data (bound via hash)
+    /// Item ID whose quantity is bounded
+    pub item_id: Option<u64>,
+    /// Inclusive lower bound
+    pub min_quantity: Option<u64>,
+    /// Inclusive upper bound
+    pub max_quantity: Option<u64>,
+
+    // Private witnesses
+    /// Inventory state the commitment opens to
+    pub state: Option<InventoryState>,
+    /// Quantity of item_id held
+    pub quantity: Option<u64>,
+    /// Proof for item_id in the inventory SMT
+    pub proof: Option<MerkleProof<Fr>>,
+}
+
+impl QuantityRangeCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        let dummy_proof = MerkleProof::new(
+            vec![Fr::from(0u64); DEFAULT_DEPTH],
+            vec![false; DEFAULT_DEPTH],
+        );
+
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            item_id: Some(0),
+            min_quantity: Some(0),
+            max_quantity: Some(0),
+            state: Some(InventoryState::new(Fr::from(0u64), 0, Fr::from(0u64))),
+            quantity: Some(0),
+            proof: Some(dummy_proof),
+        }
+    }
+
+    /// Prove that `tree` holds between `min_quantity` and `max_quantity` of `item_id`.
+    ///
+    /// Returns an error if `max_quantity < min_quantity`. A quantity outside the
+    /// range is not rejected here; the circuit is then unsatisfiable.
+    pub fn new(
+        tree: &SparseMerkleTree,
+        current_volume: u64,
+        blinding: Fr,
+        item_id: u64,
+        min_quantity: u64,
+        max_quantity: u64,
+    ) -> Result<Self, InvalidQuantityRange> {
+        if max_quantity < min_quantity {
+            return Err(InvalidQuantityRange { min_quantity, max_quantity });
+        }
+
+        let state = InventoryState::new(tree.root(), current_volume, blinding);
+        let public_hash =
+            compute_quantity_range_hash(state.commitment(), item_id, min_quantity, max_quantity);
+
+        Ok(Self {
+            public_hash: Some(public_hash),
+            item_id: Some(item_id),
+            min_quantity: Some(min_quantity),
+            max_quantity: Some(max_quantity),
+            state: Some(state),
+            quantity: Some(tree.get(item_id)),
+            proof: Some(tree.get_proof(item_id)),
+        })
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for QuantityRangeCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate public data (bound via hash) ===
+        let item_id_var = FpVar::new_witness(cs.clone(), || {
+            self.item_id
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let min_var = FpVar::new_witness(cs.clone(), || {
+            self.min_quantity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let max_var = FpVar::new_witness(cs.clone(), || {
+            self.max_quantity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate private witnesses ===
+        let state = InventoryStateVar::new_witness(cs.clone(), self.state.as_ref().unwrap())?;
+        let quantity_var = FpVar::new_witness(cs.clone(), || {
+            self.quantity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let proof_var = MerkleProofVar::new_witness(cs.clone(), self.proof.as_ref().unwrap())?;
+
+        // === Constraint 1: The inventory holds exactly `quantity` of item_id ===
+        verify_quantity(
+            cs.clone(),
+            &state.inventory_root,
+            &item_id_var,
+            &quantity_var,
+            &proof_var,
+        )?;
+
+        // === Constraint 2: quantity >= min_quantity ===
+        enforce_geq(cs.clone(), &quantity_var, &min_var)?;
+
+        // === Constraint 3: max_quantity >= quantity ===
+        enforce_geq(cs.clone(), &max_var, &quantity_var)?;
+
+        // === Constraint 4: Compute and verify public hash ===
+        let commitment_var = state.commitment(cs.clone())?;
+        let inputs = vec![commitment_var, item_id_var, min_var, max_var];
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Holds 100 of item 1.
+    fn inventory() -> SparseMerkleTree {
+        SparseMerkleTree::from_items(&[(1, 100), (2, 50)], DEFAULT_DEPTH)
+    }
+
+    fn is_satisfied(circuit: QuantityRangeCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    fn range(min_quantity: u64, max_quantity: u64) -> QuantityRangeCircuit {
+        QuantityRangeCircuit::new(&inventory(), 150, Fr::from(1u64), 1, min_quantity, max_quantity)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_quantity_in_range() {
+        let circuit = range(50, 200);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("QuantityRange constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_quantity_range_boundaries() {
+        assert!(is_satisfied(range(100, 200)));
+        assert!(is_satisfied(range(0, 100)));
+        assert!(!is_satisfied(range(101, 200)));
+        assert!(!is_satisfied(range(0, 99)));
+    }
+
+    #[test]
+    fn test_exact_quantity() {
+        assert!(is_satisfied(range(100, 100)));
+        assert!(!is_satisfied(range(99, 99)));
+        assert!(!is_satisfied(range(101, 101)));
+    }
+
+    #[test]
+    fn test_inverted_range_rejected() {
+        let result = QuantityRangeCircuit::new(&inventory(), 150, Fr::from(1u64), 1, 200, 50);
+
+        assert_eq!(
+            result.err(),
+            Some(InvalidQuantityRange { min_quantity: 200, max_quantity: 50 })
+        );
+    }
+}
@@ -22,6 +22,9 @@ use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
 /// Supports quantities up to 4,294,967,295 (~4.29 billion)
 pub const RANGE_BITS: usize = 32;
 
+/// Largest value that passes a [`RANGE_BITS`] range check
+pub const MAX_RANGE_VALUE: u64 = (1 << RANGE_BITS) - 1;
+
 /// Enforce that a field element fits in `num_bits` bits.
 ///
 /// This uses an optimized approach that only allocates the bits we need:
@@ -32,12 +35,37 @@ pub const RANGE_BITS: usize = 32;
 /// If the input value >= 2^num_bits, the constraint cannot be satisfied because
 /// the reconstruction will produce a different value.
 ///
+/// Accepted interval: exactly `[0, 2^num_bits)`, viewing field elements as
+/// integers in `[0, modulus)`. Whatever bits the prover picks, their sum is an
+/// integer below `2^num_bits`, which is below the modulus, so it cannot wrap onto
+/// a larger field element. That only holds while `num_bits` is smaller than the
+/// modulus bit size, which is asserted.
+///
 /// Constraint cost: ~num_bits constraints (vs ~254 for naive approach)
 pub fn enforce_range<F: PrimeField>(
     cs: ConstraintSystemRef<F>,
     value: &FpVar<F>,
     num_bits: usize,
 ) -> Result<(), SynthesisError> {
+    range_bits_le(cs, value, num_bits)?;
+    Ok(())
+}
+
+/// Decompose a field element into `num_bits` little-endian bits.
+///
+/// Enforces the same range as [`enforce_range`] and returns the bits, for
+/// gadgets that go on to use them (e.g. as a scalar).
+pub fn range_bits_le<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    value: &FpVar<F>,
+    num_bits: usize,
+) -> Result<Vec<Boolean<F>>, SynthesisError> {
+    assert!(
+        num_bits < F::MODULUS_BIT_SIZE as usize,
+        "range check of {} bits could wrap around the field",
+        num_bits
+    );
+
     // Allocate only the bits we need as witnesses
     let bits: Vec<Boolean<F>> = (0..num_bits)
         .map(|i| {
@@ -66,7 +94,7 @@ pub fn enforce_range<F: PrimeField>(
     // because the reconstructed value will differ
     value.enforce_equal(&reconstructed)?;
 
-    Ok(())
+    Ok(bits)
 }
 
 /// Enforce that a value is non-negative and fits in 32 bits.
@@ -101,9 +129,51 @@ pub fn enforce_geq<F: PrimeField>(
     cs: ConstraintSystemRef<F>,
     a: &FpVar<F>,
     b: &FpVar<F>,
+) -> Result<(), SynthesisError> {
+    enforce_geq_bits(cs, a, b, RANGE_BITS)
+}
+
+/// Enforce that a >= b, checking that (a - b) fits in `num_bits` bits.
+///
+/// Narrower widths save constraints when the values are known to be small;
+/// wider ones accept differences up to `2^num_bits - 1`.
+///
+/// Constraint cost: ~num_bits + 1 constraints
+pub fn enforce_geq_bits<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+    num_bits: usize,
 ) -> Result<(), SynthesisError> {
     let diff = a - b;
-    enforce_u32_range(cs, &diff)
+    enforce_range(cs, &diff, num_bits)
+}
+
+/// Return whether a >= b as a Boolean, without enforcing it.
+///
+/// Both inputs must already be known to fit in 32 bits. Then `a - b + 2^32`
+/// lies in [1, 2^33), and its bit 32 is set exactly when a >= b.
+///
+/// Constraint cost: ~34 constraints
+pub fn is_geq<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+) -> Result<Boolean<F>, SynthesisError> {
+    let offset = FpVar::constant(F::from(1u64 << RANGE_BITS));
+    let shifted = a - b + offset;
+
+    let bits: Vec<Boolean<F>> = (0..=RANGE_BITS)
+        .map(|i| {
+            Boolean::new_witness(cs.clone(), || {
+                let v = shifted.value().unwrap_or_default();
+                Ok(v.into_bigint().get_bit(i))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Boolean::le_bits_to_fp_var(&bits)?.enforce_equal(&shifted)?;
+
+    Ok(bits[RANGE_BITS].clone())
 }
 
 #[cfg(test)]
@@ -166,6 +236,39 @@ mod tests {
         assert!(!cs.is_satisfied().unwrap());
     }
 
+    /// Whether an honestly assigned `value` passes a `num_bits` range check.
+    fn passes_range(value: Fr, num_bits: usize) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let value = FpVar::new_witness(cs.clone(), || Ok(value)).unwrap();
+        enforce_range(cs.clone(), &value, num_bits).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_range_check_boundaries() {
+        let two_pow_64 = Fr::from(u64::MAX) + Fr::from(1u64);
+        let half_modulus = Fr::from_bigint(Fr::MODULUS_MINUS_ONE_DIV_TWO).unwrap();
+
+        let cases = [
+            ("0", Fr::from(0u64), true),
+            ("2^64 - 1", Fr::from(u64::MAX), true),
+            ("2^64", two_pow_64, false),
+            ("2^64 + 1", two_pow_64 + Fr::from(1u64), false),
+            ("(modulus - 1) / 2", half_modulus, false),
+            ("(modulus - 1) / 2 + 1", half_modulus + Fr::from(1u64), false),
+            ("modulus - 1", Fr::from(1u64).neg(), false),
+        ];
+        for (name, value, expected) in cases {
+            assert_eq!(passes_range(value, 64), expected, "64-bit range check of {}", name);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "could wrap around the field")]
+    fn test_range_check_rejects_field_sized_width() {
+        passes_range(Fr::from(1u64), Fr::MODULUS_BIT_SIZE as usize);
+    }
+
     #[test]
     fn test_geq_valid() {
         let cs = ConstraintSystem::<Fr>::new_ref();
@@ -204,6 +307,20 @@ mod tests {
         assert!(!cs.is_satisfied().unwrap());
     }
 
+    #[test]
+    fn test_is_geq() {
+        for (a, b, expected) in [(100u64, 50u64, true), (100, 100, true), (50, 100, false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let a = FpVar::new_witness(cs.clone(), || Ok(Fr::from(a))).unwrap();
+            let b = FpVar::new_witness(cs.clone(), || Ok(Fr::from(b))).unwrap();
+
+            let result = is_geq(cs.clone(), &a, &b).unwrap();
+
+            assert_eq!(result.value().unwrap(), expected);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
     #[test]
     fn test_constraint_count() {
         let cs = ConstraintSystem::<Fr>::new_ref();
@@ -0,0 +1,230 @@
+//! Reblind Circuit for SMT-based inventory.
+//!
+//! Proves that an inventory was handed to a new owner untouched: the new
+//! commitment opens to the same contents as the old one under a fresh
+//! blinding, so the new owner's commitment cannot be linked to the old one by
+//! anyone but the two parties.
+//!
+//! This is [`EqualityCircuit`](crate::equality::EqualityCircuit) specialized for
+//! ownership handoff. On top of the content check it requires the blinding to
+//! actually change, and it binds the proof to the new owner's key so it cannot
+//! be replayed for a different recipient. Pass [`NO_OWNER`] to leave the
+//! recipient unbound.
+//!
+//! Public input: Poseidon(old_commitment, new_commitment, new_owner)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+
+/// `new_owner` value for a reblind that is not bound to a recipient.
+pub const NO_OWNER: u64 = 0;
+
+/// Compute the public input hash for Reblind proof.
+pub fn compute_reblind_hash(old_commitment: Fr, new_commitment: Fr, new_owner: Fr) -> Fr {
+    poseidon_hash_many(&[old_commitment, new_commitment, new_owner])
+}
+
+/// Reblind Circuit.
+///
+/// Proves both commitments open to the same inventory root and volume under
+/// different blindings.
+#[derive(Clone)]
+pub struct ReblindCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Public data (bound via hash)
+    /// Key of the new owner, or [`NO_OWNER`]
+    pub new_owner: Option<Fr>,
+
+    // Private witnesses
+    /// Inventory SMT root, shared by both commitments
+    pub inventory_root: Option<Fr>,
+    /// Total volume, shared by both commitments
+    pub current_volume: Option<u64>,
+    /// Blinding of the old owner's commitment
+    pub old_blinding: Option<Fr>,
+    /// Blinding of the new owner's commitment
+    pub new_blinding: Option<Fr>,
+}
+
+impl ReblindCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            new_owner: Some(Fr::from(NO_OWNER)),
+            inventory_root: Some(Fr::from(0u64)),
+            current_volume: Some(0),
+            old_blinding: Some(Fr::from(0u64)),
+            new_blinding: Some(Fr::from(1u64)),
+        }
+    }
+
+    /// Create a new circuit with witnesses.
+    pub fn new(
+        inventory_root: Fr,
+        current_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+        new_owner: Fr,
+    ) -> Self {
+        let old_commitment = create_smt_commitment(inventory_root, current_volume, old_blinding);
+        let new_commitment = create_smt_commitment(inventory_root, current_volume, new_blinding);
+        let public_hash = compute_reblind_hash(old_commitment, new_commitment, new_owner);
+
+        Self {
+            public_hash: Some(public_hash),
+            new_owner: Some(new_owner),
+            inventory_root: Some(inventory_root),
+            current_volume: Some(current_volume),
+            old_blinding: Some(old_blinding),
+            new_blinding: Some(new_blinding),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for ReblindCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate public data (bound via hash) ===
+        let new_owner_var = FpVar::new_witness(cs.clone(), || {
+            self.new_owner.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate private witnesses ===
+        let root_var = FpVar::new_witness(cs.clone(), || {
+            self.inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let volume_var = FpVar::new_witness(cs.clone(), || {
+            self.current_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let old_blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.old_blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let new_blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.new_blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Constraint 1: The blinding changed ===
+        // Reusing it would reproduce the old commitment and link the owners
+        old_blinding_var.enforce_not_equal(&new_blinding_var)?;
+
+        // === Constraint 2: Both commitments open to the same contents ===
+        // Sharing the root and volume witnesses makes the content check implicit
+        let old_commitment_var =
+            create_smt_commitment_var(cs.clone(), &root_var, &volume_var, &old_blinding_var)?;
+        let new_commitment_var =
+            create_smt_commitment_var(cs.clone(), &root_var, &volume_var, &new_blinding_var)?;
+
+        // === Constraint 3: Compute and verify public hash ===
+        let computed_hash = poseidon_hash_many_var(
+            cs.clone(),
+            &[old_commitment_var, new_commitment_var, new_owner_var],
+        )?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn is_satisfied(circuit: ReblindCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_reblind_valid() {
+        let tree = SparseMerkleTree::from_items(&[(1, 10), (2, 20)], DEFAULT_DEPTH);
+        let circuit = ReblindCircuit::new(
+            tree.root(),
+            500,
+            Fr::from(12345u64),
+            Fr::from(67890u64),
+            Fr::from(777u64),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("Reblind constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_reblind_changed_contents_fails() {
+        let old = SparseMerkleTree::from_items(&[(1, 10), (2, 20)], DEFAULT_DEPTH);
+        let new = SparseMerkleTree::from_items(&[(1, 10), (2, 19)], DEFAULT_DEPTH);
+        let (old_blinding, new_blinding) = (Fr::from(12345u64), Fr::from(67890u64));
+        let new_owner = Fr::from(777u64);
+
+        // Honest opening of the old commitment, but the statement names a new
+        // commitment over different contents
+        let mut circuit =
+            ReblindCircuit::new(old.root(), 500, old_blinding, new_blinding, new_owner);
+        circuit.public_hash = Some(compute_reblind_hash(
+            create_smt_commitment(old.root(), 500, old_blinding),
+            create_smt_commitment(new.root(), 500, new_blinding),
+            new_owner,
+        ));
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_reblind_same_blinding_fails() {
+        let tree = SparseMerkleTree::from_items(&[(1, 10)], DEFAULT_DEPTH);
+        let blinding = Fr::from(12345u64);
+        let circuit = ReblindCircuit::new(tree.root(), 500, blinding, blinding, Fr::from(777u64));
+
+        // The inequality has no witness when the blindings match
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(circuit.generate_constraints(cs).is_err());
+    }
+
+    #[test]
+    fn test_reblind_bound_to_owner() {
+        let tree = SparseMerkleTree::from_items(&[(1, 10)], DEFAULT_DEPTH);
+        let (old_blinding, new_blinding) = (Fr::from(12345u64), Fr::from(67890u64));
+
+        // A proof for one recipient does not verify for another
+        let mut circuit =
+            ReblindCircuit::new(tree.root(), 500, old_blinding, new_blinding, Fr::from(777u64));
+        circuit.public_hash = Some(compute_reblind_hash(
+            create_smt_commitment(tree.root(), 500, old_blinding),
+            create_smt_commitment(tree.root(), 500, new_blinding),
+            Fr::from(888u64),
+        ));
+        assert!(!is_satisfied(circuit));
+
+        // Unbound handoffs are still valid
+        let unbound = ReblindCircuit::new(
+            tree.root(),
+            500,
+            old_blinding,
+            new_blinding,
+            Fr::from(NO_OWNER),
+        );
+        assert!(is_satisfied(unbound));
+    }
+}
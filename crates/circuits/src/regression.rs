@@ -0,0 +1,158 @@
+//! Constraint count baselines for every exposed circuit.
+//!
+//! Counts are taken from the setup (`empty()`) circuits, which have the same
+//! structure as proving circuits. After an intentional optimization, update the
+//! circuit's row in [`EXPECTED_CONSTRAINTS`].
+
+use ark_bn254::Fr;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisMode};
+
+use crate::{
+    AtomicBasketCircuit, BurnCircuit, CapacitySMTCircuit, CategoryThresholdCircuit,
+    ChainProvenanceCircuit, CraftingCircuit, DeltaCircuit, EqualityCircuit, FeeTransferCircuit,
+    FirstAcquisitionCircuit, GiftCircuit, ItemExistsInRegistryCircuit, ItemExistsSMTCircuit,
+    MintDepositCircuit, ModuloCircuit, MultiTransferCircuit, NonMembershipCircuit, Predicate,
+    PredicateCircuit, QuantityRangeCircuit, ReblindCircuit, StackLimitCircuit,
+    StateTransitionCircuit, SumRevealCircuit, WhitelistCircuit, WithdrawHiddenCircuit,
+};
+
+/// Circuits with a constraint baseline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CircuitKind {
+    StateTransition,
+    ItemExists,
+    ItemExistsInRegistry,
+    Capacity,
+    FirstAcquisition,
+    FeeTransfer,
+    Equality,
+    StackLimit,
+    /// With a 3-item category
+    CategoryThreshold,
+    MintDeposit,
+    /// With 2 deltas
+    Delta,
+    /// With (A AND B) OR C
+    Predicate,
+    /// With 3 items
+    SumReveal,
+    WithdrawHidden,
+    /// With 2 links
+    ChainProvenance,
+    Burn,
+    /// With 2 transfers
+    MultiTransfer,
+    Modulo,
+    /// With 2 inputs and 1 output
+    Crafting,
+    NonMembership,
+    QuantityRange,
+    /// With 3 items
+    AtomicBasket,
+    Whitelist,
+    Gift,
+    Reblind,
+}
+
+/// (circuit, expected constraints, allowed deviation of roughly 2%)
+const EXPECTED_CONSTRAINTS: &[(CircuitKind, usize, usize)] = &[
+    (CircuitKind::StateTransition, 8596, 200),
+    (CircuitKind::ItemExists, 4157, 100),
+    (CircuitKind::ItemExistsInRegistry, 7074, 150),
+    (CircuitKind::Capacity, 724, 20),
+    (CircuitKind::FirstAcquisition, 8092, 200),
+    (CircuitKind::FeeTransfer, 23024, 500),
+    (CircuitKind::Equality, 1209, 30),
+    (CircuitKind::StackLimit, 7320, 150),
+    (CircuitKind::CategoryThreshold, 10266, 250),
+    (CircuitKind::MintDeposit, 9065, 200),
+    (CircuitKind::Delta, 14653, 300),
+    (CircuitKind::Predicate, 11968, 250),
+    (CircuitKind::SumReveal, 10697, 250),
+    (CircuitKind::WithdrawHidden, 11227, 250),
+    (CircuitKind::ChainProvenance, 21974, 450),
+    (CircuitKind::Burn, 8548, 200),
+    (CircuitKind::MultiTransfer, 28097, 550),
+    (CircuitKind::Modulo, 4263, 100),
+    (CircuitKind::Crafting, 21242, 450),
+    (CircuitKind::NonMembership, 3642, 100),
+    (CircuitKind::QuantityRange, 4195, 100),
+    (CircuitKind::AtomicBasket, 21489, 450),
+    (CircuitKind::Whitelist, 7070, 150),
+    (CircuitKind::Gift, 19047, 400),
+    (CircuitKind::Reblind, 1445, 30),
+];
+
+/// Assert that `actual` is within the baseline tolerance for `kind`.
+fn assert_constraint_regression(kind: CircuitKind, actual: usize) {
+    let &(_, expected, tolerance) = EXPECTED_CONSTRAINTS
+        .iter()
+        .find(|(k, _, _)| *k == kind)
+        .unwrap_or_else(|| panic!("no constraint baseline for {:?}", kind));
+
+    assert!(
+        actual.abs_diff(expected) <= tolerance,
+        "{:?}: {} constraints, expected {} ± {}",
+        kind,
+        actual,
+        expected,
+        tolerance
+    );
+}
+
+fn count_constraints<C: ConstraintSynthesizer<Fr>>(circuit: C) -> usize {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+    circuit.generate_constraints(cs.clone()).unwrap();
+    cs.num_constraints()
+}
+
+fn count_for(kind: CircuitKind) -> usize {
+    match kind {
+        CircuitKind::StateTransition => count_constraints(StateTransitionCircuit::empty()),
+        CircuitKind::ItemExists => count_constraints(ItemExistsSMTCircuit::empty()),
+        CircuitKind::ItemExistsInRegistry => {
+            count_constraints(ItemExistsInRegistryCircuit::empty())
+        }
+        CircuitKind::Capacity => count_constraints(CapacitySMTCircuit::empty()),
+        CircuitKind::FirstAcquisition => count_constraints(FirstAcquisitionCircuit::empty()),
+        CircuitKind::FeeTransfer => count_constraints(FeeTransferCircuit::empty()),
+        CircuitKind::Equality => count_constraints(EqualityCircuit::empty()),
+        CircuitKind::StackLimit => count_constraints(StackLimitCircuit::empty()),
+        CircuitKind::CategoryThreshold => {
+            count_constraints(CategoryThresholdCircuit::empty(vec![1, 2, 3]))
+        }
+        CircuitKind::MintDeposit => count_constraints(MintDepositCircuit::empty()),
+        CircuitKind::Delta => count_constraints(DeltaCircuit::empty(2)),
+        CircuitKind::Predicate => {
+            let threshold = Predicate::Threshold { item_id: 0, min_quantity: 0 };
+            let shape = Predicate::Or(vec![
+                Predicate::And(vec![threshold.clone(), threshold.clone()]),
+                threshold,
+            ]);
+            count_constraints(PredicateCircuit::empty(shape))
+        }
+        CircuitKind::SumReveal => count_constraints(SumRevealCircuit::empty(3)),
+        CircuitKind::WithdrawHidden => count_constraints(WithdrawHiddenCircuit::empty()),
+        CircuitKind::ChainProvenance => count_constraints(ChainProvenanceCircuit::empty(2)),
+        CircuitKind::Burn => count_constraints(BurnCircuit::empty()),
+        CircuitKind::MultiTransfer => count_constraints(MultiTransferCircuit::empty(2)),
+        CircuitKind::Modulo => count_constraints(ModuloCircuit::empty()),
+        CircuitKind::Crafting => count_constraints(CraftingCircuit::empty(2, 1)),
+        CircuitKind::NonMembership => count_constraints(NonMembershipCircuit::empty()),
+        CircuitKind::QuantityRange => count_constraints(QuantityRangeCircuit::empty()),
+        CircuitKind::AtomicBasket => count_constraints(AtomicBasketCircuit::empty(3)),
+        CircuitKind::Whitelist => count_constraints(WhitelistCircuit::empty()),
+        CircuitKind::Gift => count_constraints(GiftCircuit::empty()),
+        CircuitKind::Reblind => count_constraints(ReblindCircuit::empty()),
+    }
+}
+
+#[test]
+fn regression_inventory_circuits() {
+    for &(kind, _, _) in EXPECTED_CONSTRAINTS {
+        let actual = count_for(kind);
+        println!("{:?}: {} constraints", kind, actual);
+        assert_constraint_regression(kind, actual);
+    }
+}
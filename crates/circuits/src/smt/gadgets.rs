@@ -65,6 +65,15 @@ impl MerkleProofVar {
     pub fn depth(&self) -> usize {
         self.path.len()
     }
+
+    /// Enforce that this proof is for the leaf at `index`.
+    ///
+    /// The direction bits are the little-endian bits of the leaf index. A
+    /// non-empty leaf already binds its item_id through the leaf hash, but an
+    /// empty leaf H(0, 0) does not, so proofs about absent items must call this.
+    pub fn enforce_index(&self, index: &FpVar<Fr>) -> Result<(), SynthesisError> {
+        Boolean::le_bits_to_fp_var(&self.indices)?.enforce_equal(index)
+    }
 }
 
 /// Hash two field elements using Poseidon in-circuit.
@@ -87,7 +96,9 @@ pub fn hash_leaf(
 
 /// Compute the root hash from a leaf and Merkle path in-circuit.
 ///
-/// This is the core membership verification gadget.
+/// This is the core membership verification gadget. It hashes once per level
+/// of `proof`, so it works for trees of any depth and costs less for
+/// shallower ones.
 pub fn compute_root_from_path(
     cs: ConstraintSystemRef<Fr>,
     leaf_hash: &FpVar<Fr>,
@@ -128,13 +139,40 @@ pub fn verify_membership(
     Ok(())
 }
 
+/// Verify that the tree holds exactly `quantity` of `item_id`, where 0 means absent.
+///
+/// Unlike [`verify_membership`], a zero quantity is checked against the empty
+/// default leaf, with the proof bound to item_id's position.
+pub fn verify_quantity(
+    cs: ConstraintSystemRef<Fr>,
+    expected_root: &FpVar<Fr>,
+    item_id: &FpVar<Fr>,
+    quantity: &FpVar<Fr>,
+    proof: &MerkleProofVar,
+) -> Result<(), SynthesisError> {
+    proof.enforce_index(item_id)?;
+
+    let is_absent = quantity.is_eq(&FpVar::zero())?;
+    let default_leaf_hash_var = FpVar::constant(compute_default_leaf_hash());
+    let regular_hash = hash_leaf(cs.clone(), item_id, quantity)?;
+    let leaf_hash = is_absent.select(&default_leaf_hash_var, &regular_hash)?;
+
+    let computed_root = compute_root_from_path(cs, &leaf_hash, proof)?;
+    computed_root.enforce_equal(expected_root)
+}
+
 /// Verify membership and compute the new root after updating the leaf.
 ///
 /// This is used for state transitions (deposit/withdraw).
 ///
 /// Handles insertions specially: when old_quantity == 0, verifies against
 /// the default leaf hash H(0, 0) instead of H(item_id, 0). This allows
-/// adding new items to empty slots.
+/// adding new items to empty slots. Likewise a new_quantity of 0 writes the
+/// default leaf, matching `SparseMerkleTree::update`, so an emptied item can
+/// later be re-inserted.
+///
+/// The empty leaf does not name its item, so the proof is bound to item_id's
+/// position; otherwise an insertion could land in any empty slot.
 ///
 /// Returns the new root after setting the leaf to new_quantity.
 pub fn verify_and_update(
@@ -145,6 +183,8 @@ pub fn verify_and_update(
     new_quantity: &FpVar<Fr>,
     proof: &MerkleProofVar,
 ) -> Result<FpVar<Fr>, SynthesisError> {
+    proof.enforce_index(item_id)?;
+
     // For insertions (old_quantity == 0), use precomputed default leaf hash H(0, 0)
     // For updates (old_quantity > 0), use regular hash H(item_id, old_quantity)
     let zero = FpVar::zero();
@@ -160,8 +200,10 @@ pub fn verify_and_update(
     let computed_old_root = compute_root_from_path(cs.clone(), &old_leaf_hash, proof)?;
     computed_old_root.enforce_equal(old_root)?;
 
-    // Compute new leaf hash (always uses item_id)
-    let new_leaf_hash = hash_leaf(cs.clone(), item_id, new_quantity)?;
+    // Compute new leaf hash (default leaf when the item is removed)
+    let is_removal = new_quantity.is_eq(&zero)?;
+    let regular_new_hash = hash_leaf(cs.clone(), item_id, new_quantity)?;
+    let new_leaf_hash = is_removal.select(&default_leaf_hash_var, &regular_new_hash)?;
 
     // Compute new root using the same path (siblings unchanged)
     let new_root = compute_root_from_path(cs, &new_leaf_hash, proof)?;
@@ -169,17 +211,61 @@ pub fn verify_and_update(
     Ok(new_root)
 }
 
+/// A single leaf update for [`verify_and_update_batch`].
+#[derive(Clone)]
+pub struct LeafUpdateVar {
+    /// Item ID (leaf index)
+    pub item_id: FpVar<Fr>,
+    /// Quantity before the update
+    pub old_quantity: FpVar<Fr>,
+    /// Quantity after the update
+    pub new_quantity: FpVar<Fr>,
+    /// Merkle proof against the root produced by the preceding updates
+    pub proof: MerkleProofVar,
+}
+
+/// Apply several leaf updates in sequence, threading the root through each one.
+///
+/// The output root of update `i` is the input root of update `i + 1`, and the
+/// final root is returned. Because siblings can be shared between updates,
+/// each proof must be taken from the tree *after* all preceding updates were
+/// applied natively, not from the original tree.
+pub fn verify_and_update_batch(
+    cs: ConstraintSystemRef<Fr>,
+    old_root: &FpVar<Fr>,
+    updates: &[LeafUpdateVar],
+) -> Result<FpVar<Fr>, SynthesisError> {
+    let mut root = old_root.clone();
+
+    for update in updates {
+        root = verify_and_update(
+            cs.clone(),
+            &root,
+            &update.item_id,
+            &update.old_quantity,
+            &update.new_quantity,
+            &update.proof,
+        )?;
+    }
+
+    Ok(root)
+}
+
 /// Verify that an item is NOT in the tree (quantity = 0).
 ///
-/// This proves non-membership by showing the leaf at item_id is empty.
+/// This proves non-membership by showing the leaf at item_id is the default
+/// leaf H(0, 0), with the proof bound to item_id's position.
 pub fn verify_non_membership(
     cs: ConstraintSystemRef<Fr>,
     expected_root: &FpVar<Fr>,
     item_id: &FpVar<Fr>,
     proof: &MerkleProofVar,
 ) -> Result<(), SynthesisError> {
-    let zero = FpVar::zero();
-    verify_membership(cs, expected_root, item_id, &zero, proof)
+    proof.enforce_index(item_id)?;
+
+    let default_leaf_hash_var = FpVar::constant(compute_default_leaf_hash());
+    let computed_root = compute_root_from_path(cs, &default_leaf_hash_var, proof)?;
+    computed_root.enforce_equal(expected_root)
 }
 
 #[cfg(test)]
@@ -286,6 +372,40 @@ mod gadget_tests {
         assert!(cs.is_satisfied().unwrap());
     }
 
+    #[test]
+    fn test_verify_and_update_batch() {
+        let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        tree.update(1, 100);
+        tree.update(2, 50);
+        let old_root = tree.root();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let old_root_var = FpVar::new_input(cs.clone(), || Ok(old_root)).unwrap();
+
+        // Items 1 and 2 share a parent, item 3 is a new insertion
+        let changes = [(1u64, 100u64, 70u64), (2, 50, 80), (3, 0, 5)];
+        let mut updates = Vec::new();
+        for &(item_id, old_qty, new_qty) in &changes {
+            // Proof must reflect all earlier updates
+            let proof = tree.get_proof(item_id);
+            tree.update(item_id, new_qty);
+
+            updates.push(LeafUpdateVar {
+                item_id: FpVar::new_witness(cs.clone(), || Ok(Fr::from(item_id))).unwrap(),
+                old_quantity: FpVar::new_witness(cs.clone(), || Ok(Fr::from(old_qty))).unwrap(),
+                new_quantity: FpVar::new_witness(cs.clone(), || Ok(Fr::from(new_qty))).unwrap(),
+                proof: MerkleProofVar::new_witness(cs.clone(), &proof).unwrap(),
+            });
+        }
+
+        let final_root = verify_and_update_batch(cs.clone(), &old_root_var, &updates).unwrap();
+
+        let expected_var = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+        final_root.enforce_equal(&expected_var).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+    }
+
     #[test]
     fn test_constraint_count() {
         let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
@@ -324,4 +444,25 @@ mod gadget_tests {
             DEFAULT_DEPTH
         );
     }
+
+    #[test]
+    fn test_verify_non_membership() {
+        let tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+
+        let check = |item_id: u64, proof_for: u64| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let root_var = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+            let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(item_id))).unwrap();
+            let proof_var = MerkleProofVar::new_witness(cs.clone(), &tree.get_proof(proof_for)).unwrap();
+            verify_non_membership(cs.clone(), &root_var, &item_id_var, &proof_var).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        // Absent item
+        assert!(check(5, 5));
+        // Held item
+        assert!(!check(1, 1));
+        // Held item, using the proof of some other empty slot
+        assert!(!check(1, 5));
+    }
 }
@@ -12,9 +12,10 @@ mod gadgets;
 #[cfg(test)]
 mod tests;
 
-pub use tree::{SparseMerkleTree, DEFAULT_DEPTH};
+pub use tree::{MigrationError, SparseMerkleTree, DEFAULT_DEPTH};
 pub use proof::MerkleProof;
 pub use gadgets::{
-    MerkleProofVar, verify_membership, verify_and_update, compute_root_from_path,
-    compute_default_leaf_hash, hash_two, hash_leaf,
+    MerkleProofVar, LeafUpdateVar, verify_membership, verify_non_membership, verify_quantity,
+    verify_and_update, verify_and_update_batch, compute_root_from_path, compute_default_leaf_hash,
+    hash_two, hash_leaf,
 };
@@ -55,9 +55,15 @@ impl<F: PrimeField> MerkleProof<F> {
 // Poseidon hashing methods (specialized for Fr)
 impl MerkleProof<Fr> {
     /// Compute the root hash from this proof and the leaf value using Poseidon.
+    ///
+    /// A quantity of 0 uses the empty-leaf hash H(0, 0), matching the tree.
     pub fn compute_root(&self, item_id: u64, quantity: u64) -> Fr {
         // Start with leaf hash
-        let mut current = Self::hash_leaf(item_id, quantity);
+        let mut current = if quantity == 0 {
+            Self::hash_leaf(0, 0)
+        } else {
+            Self::hash_leaf(item_id, quantity)
+        };
 
         // Work up the tree
         for (sibling, &is_right) in self.path.iter().zip(self.indices.iter()) {
@@ -141,6 +141,40 @@ fn test_circuit_withdraw() {
     println!("Withdraw circuit satisfied with {} constraints", cs.num_constraints());
 }
 
+#[test]
+fn test_circuit_withdraw_all_then_redeposit() {
+    let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(1u64))).unwrap();
+
+    // Withdraw everything, then deposit again into the emptied leaf
+    for (old_qty, new_qty) in [(100u64, 0u64), (0, 20)] {
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+        tree.update(1, new_qty);
+
+        let old_root_var = FpVar::new_input(cs.clone(), || Ok(old_root)).unwrap();
+        let new_root_var = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+        let old_qty_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(old_qty))).unwrap();
+        let new_qty_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(new_qty))).unwrap();
+        let proof_var = MerkleProofVar::new_witness(cs.clone(), &proof).unwrap();
+
+        let computed_new_root = verify_and_update(
+            cs.clone(),
+            &old_root_var,
+            &item_id_var,
+            &old_qty_var,
+            &new_qty_var,
+            &proof_var,
+        ).unwrap();
+
+        computed_new_root.enforce_equal(&new_root_var).unwrap();
+    }
+
+    assert!(cs.is_satisfied().unwrap());
+}
+
 #[test]
 fn test_circuit_new_item() {
     // Start with empty tree
@@ -273,3 +307,64 @@ fn test_item_id_overflow() {
     // 4096 is out of bounds for depth 12 (max is 4095)
     tree.update(4096, 100);
 }
+
+#[test]
+fn test_prove_commitment_leaf_in_circuit() {
+    // Registry of commitments alongside ordinary items
+    let mut tree = SparseMerkleTree::from_items(&[(1, 100), (5, 50)], DEFAULT_DEPTH);
+    tree.update_leaf_hash(7, Fr::from(111u64));
+    let commitment = Fr::from(222u64);
+    tree.update_leaf_hash(42, commitment);
+
+    let proof = tree.prove(42);
+    assert_eq!(proof.compute_root_from_leaf(commitment), tree.root());
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let root_var = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+    let leaf_var = FpVar::new_witness(cs.clone(), || Ok(commitment)).unwrap();
+    let key_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(42u64))).unwrap();
+    let proof_var = MerkleProofVar::new_witness(cs.clone(), &proof).unwrap();
+
+    proof_var.enforce_index(&key_var).unwrap();
+    compute_root_from_path(cs.clone(), &leaf_var, &proof_var)
+        .unwrap()
+        .enforce_equal(&root_var)
+        .unwrap();
+
+    assert!(cs.is_satisfied().unwrap());
+}
+
+#[test]
+#[should_panic(expected = "key exceeds tree capacity")]
+fn test_prove_key_out_of_range() {
+    let tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+    tree.prove(1 << DEFAULT_DEPTH);
+}
+
+#[test]
+fn test_membership_at_smaller_depths() {
+    let mut counts = Vec::new();
+
+    for depth in [8, 10, 12] {
+        let tree = SparseMerkleTree::from_items(&[(5, 100)], depth);
+        let proof = tree.get_proof(5);
+        assert_eq!(proof.depth(), depth);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let root_var = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+        let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(5u64))).unwrap();
+        let quantity_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(100u64))).unwrap();
+        let proof_var = MerkleProofVar::new_witness(cs.clone(), &proof).unwrap();
+
+        verify_membership(cs.clone(), &root_var, &item_id_var, &quantity_var, &proof_var)
+            .unwrap();
+
+        assert!(cs.is_satisfied().unwrap(), "depth {}", depth);
+        println!("Membership constraints at depth {}: {}", depth, cs.num_constraints());
+        counts.push(cs.num_constraints());
+    }
+
+    // One node hash per level
+    assert!(counts[0] < counts[1] && counts[1] < counts[2]);
+    assert_eq!(counts[2] - counts[1], counts[1] - counts[0]);
+}
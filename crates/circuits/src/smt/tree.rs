@@ -7,7 +7,11 @@
 //! Uses Poseidon hash function for ZK-friendly hashing.
 
 use ark_bn254::Fr;
-use std::collections::HashMap;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
+use std::path::Path;
 
 use crate::poseidon::poseidon_hash_two;
 use super::proof::MerkleProof;
@@ -15,6 +19,27 @@ use super::proof::MerkleProof;
 /// Default tree depth (12 levels = 4,096 possible items)
 pub const DEFAULT_DEPTH: usize = 12;
 
+/// Error returned by [`SparseMerkleTree::migrate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationError {
+    /// A leaf index does not fit in the target tree.
+    ItemOutOfRange { item_id: u64, capacity: u64 },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::ItemOutOfRange { item_id, capacity } => write!(
+                f,
+                "item {} does not fit in a tree with {} slots",
+                item_id, capacity
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
 /// Sparse Merkle Tree for inventory storage.
 ///
 /// Keys are item IDs (0 to 2^depth - 1).
@@ -32,6 +57,9 @@ pub struct SparseMerkleTree {
     /// Leaf values: item_id -> quantity
     leaves: HashMap<u64, u64>,
 
+    /// Raw leaf hashes set via `update_leaf_hash` (e.g. registry commitments)
+    raw_leaves: HashMap<u64, Fr>,
+
     /// Precomputed default hashes for each level
     /// defaults[0] = hash of empty leaf
     /// defaults[i] = hash(defaults[i-1], defaults[i-1])
@@ -47,11 +75,14 @@ impl SparseMerkleTree {
             depth,
             nodes: HashMap::new(),
             leaves: HashMap::new(),
+            raw_leaves: HashMap::new(),
             defaults,
         }
     }
 
     /// Create an SMT from a list of (item_id, quantity) pairs.
+    ///
+    /// Pairs with a quantity of 0 leave the slot empty, as with [`Self::update`].
     pub fn from_items(items: &[(u64, u64)], depth: usize) -> Self {
         let mut tree = Self::new(depth);
         for &(item_id, quantity) in items {
@@ -95,24 +126,157 @@ impl SparseMerkleTree {
 
     /// Update the quantity for an item and recompute affected hashes.
     /// Returns the new root hash.
+    ///
+    /// Internal node hashes are cached, so only the `depth` nodes on the item's
+    /// path are rehashed; [`recompute_root`](Self::recompute_root) rebuilds the
+    /// root from the leaves alone to check the cache.
+    ///
+    /// Setting a quantity of 0 resets the leaf to the empty-leaf default, so a
+    /// tree's root depends only on its non-empty items.
     pub fn update(&mut self, item_id: u64, quantity: u64) -> Fr {
         assert!(item_id < (1u64 << self.depth), "item_id exceeds tree capacity");
 
-        // Update leaf value
+        self.raw_leaves.remove(&item_id);
+
+        // Update leaf value and hash
         if quantity == 0 {
             self.leaves.remove(&item_id);
+            self.nodes.remove(&(0, item_id));
         } else {
             self.leaves.insert(item_id, quantity);
+            let leaf_hash = Self::hash_leaf(item_id, quantity);
+            self.nodes.insert((0, item_id), leaf_hash);
         }
 
-        // Compute new leaf hash
-        let leaf_hash = Self::hash_leaf(item_id, quantity);
-        self.nodes.insert((0, item_id), leaf_hash);
-
         // Recompute hashes up to root
         self.recompute_path(item_id)
     }
 
+    /// Set a raw leaf hash at the given index and recompute affected hashes.
+    /// Returns the new root hash.
+    ///
+    /// Used for trees whose leaves are not (item_id, quantity) pairs, such as
+    /// a registry of inventory commitments. Raw leaves are not tracked by
+    /// `get`/`items`.
+    pub fn update_leaf_hash(&mut self, index: u64, leaf_hash: Fr) -> Fr {
+        assert!(index < (1u64 << self.depth), "index exceeds tree capacity");
+
+        self.leaves.remove(&index);
+        self.raw_leaves.insert(index, leaf_hash);
+        self.nodes.insert((0, index), leaf_hash);
+        self.recompute_path(index)
+    }
+
+    /// Save the tree to a file.
+    ///
+    /// Only the depth and the leaf set are written; internal nodes are
+    /// recomputed by [`SparseMerkleTree::load_from_file`].
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let items: BTreeMap<u64, u64> = self.leaves.iter().map(|(&k, &v)| (k, v)).collect();
+        let raw_leaves: BTreeMap<u64, Fr> =
+            self.raw_leaves.iter().map(|(&k, &v)| (k, v)).collect();
+
+        let mut bytes = Vec::new();
+        (self.depth as u64, items, raw_leaves)
+            .serialize_compressed(&mut bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+        std::fs::write(path, bytes)
+    }
+
+    /// Load a tree previously written by [`SparseMerkleTree::save_to_file`].
+    pub fn load_from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let (depth, items, raw_leaves) =
+            <(u64, BTreeMap<u64, u64>, BTreeMap<u64, Fr>)>::deserialize_compressed(&bytes[..])
+                .map_err(|e| invalid(e.to_string()))?;
+
+        if depth == 0 || depth >= 64 {
+            return Err(invalid(format!("unsupported tree depth {}", depth)));
+        }
+        let capacity = 1u64 << depth;
+        if let Some(&index) = items.keys().chain(raw_leaves.keys()).find(|&&i| i >= capacity) {
+            return Err(invalid(format!("leaf index {} exceeds tree capacity", index)));
+        }
+
+        let mut tree = Self::new(depth as usize);
+        for (item_id, quantity) in items {
+            tree.update(item_id, quantity);
+        }
+        for (index, leaf_hash) in raw_leaves {
+            tree.update_leaf_hash(index, leaf_hash);
+        }
+        Ok(tree)
+    }
+
+    /// Rebuild the tree at a different depth.
+    ///
+    /// Leaves are keyed by item_id, so they keep their index; the root changes
+    /// because the path length does. Fails instead of dropping leaves when any
+    /// index is outside the new capacity of `2^new_depth` slots.
+    pub fn migrate(&self, new_depth: usize) -> Result<Self, MigrationError> {
+        assert!(new_depth > 0 && new_depth < 64, "unsupported tree depth");
+
+        let capacity = 1u64 << new_depth;
+        if let Some(&item_id) = self
+            .leaves
+            .keys()
+            .chain(self.raw_leaves.keys())
+            .filter(|&&i| i >= capacity)
+            .min()
+        {
+            return Err(MigrationError::ItemOutOfRange { item_id, capacity });
+        }
+
+        let mut tree = Self::new(new_depth);
+        for (&item_id, &quantity) in &self.leaves {
+            tree.update(item_id, quantity);
+        }
+        for (&index, &leaf_hash) in &self.raw_leaves {
+            tree.update_leaf_hash(index, leaf_hash);
+        }
+        Ok(tree)
+    }
+
+    /// Compute the root from the leaves alone, ignoring cached internal nodes.
+    ///
+    /// Costs O(leaves * depth) hashes, against O(depth) for an incremental
+    /// update, so it is meant for checking the cache rather than regular use.
+    pub fn recompute_root(&self) -> Fr {
+        let mut level_nodes: BTreeMap<u64, Fr> = self
+            .leaves
+            .iter()
+            .map(|(&item_id, &quantity)| (item_id, Self::hash_leaf(item_id, quantity)))
+            .chain(self.raw_leaves.iter().map(|(&index, &hash)| (index, hash)))
+            .collect();
+
+        for level in 0..self.depth {
+            let mut parents = BTreeMap::new();
+            for (&index, &hash) in &level_nodes {
+                let parent_index = index >> 1;
+                if parents.contains_key(&parent_index) {
+                    // Already hashed together with its left sibling
+                    continue;
+                }
+                let sibling = level_nodes
+                    .get(&(index ^ 1))
+                    .copied()
+                    .unwrap_or(self.defaults[level]);
+                let parent = if index & 1 == 0 {
+                    Self::hash_nodes(hash, sibling)
+                } else {
+                    Self::hash_nodes(sibling, hash)
+                };
+                parents.insert(parent_index, parent);
+            }
+            level_nodes = parents;
+        }
+
+        level_nodes.get(&0).copied().unwrap_or(self.defaults[self.depth])
+    }
+
     /// Recompute hashes from a leaf up to the root.
     fn recompute_path(&mut self, item_id: u64) -> Fr {
         let mut current_index = item_id;
@@ -155,11 +319,23 @@ impl SparseMerkleTree {
     /// Generate a Merkle proof for the given item.
     pub fn get_proof(&self, item_id: u64) -> MerkleProof<Fr> {
         assert!(item_id < (1u64 << self.depth), "item_id exceeds tree capacity");
+        self.prove(item_id)
+    }
+
+    /// Generate a Merkle proof for the leaf at `key`, whatever it holds.
+    ///
+    /// Works for raw leaves set via `update_leaf_hash` (e.g. a commitment in a
+    /// registry) as well as items. The path runs from the leaf up, with each
+    /// index `true` when the node is a right child, as `MerkleProofVar` expects.
+    ///
+    /// Panics if `key` is not below 2^depth.
+    pub fn prove(&self, key: u64) -> MerkleProof<Fr> {
+        assert!(key < (1u64 << self.depth), "key exceeds tree capacity");
 
         let mut path = Vec::with_capacity(self.depth);
         let mut indices = Vec::with_capacity(self.depth);
 
-        let mut current_index = item_id;
+        let mut current_index = key;
         for level in 0..self.depth {
             let sibling_index = current_index ^ 1;
             let sibling = self.get_node(level, sibling_index);
@@ -202,6 +378,11 @@ impl SparseMerkleTree {
         self.leaves.len()
     }
 
+    /// Get the number of raw leaves set with `update_leaf_hash`.
+    pub fn raw_leaf_count(&self) -> usize {
+        self.raw_leaves.len()
+    }
+
     /// Check if the tree is empty.
     pub fn is_empty(&self) -> bool {
         self.leaves.is_empty()
@@ -287,6 +468,19 @@ mod tree_tests {
         assert_eq!(tree.len(), 3);
     }
 
+    #[test]
+    fn test_from_items_skips_zero_quantities() {
+        let tree = SparseMerkleTree::from_items(&[(1, 5), (2, 0), (3, 7)], DEFAULT_DEPTH);
+        let without_zero = SparseMerkleTree::from_items(&[(1, 5), (3, 7)], DEFAULT_DEPTH);
+
+        // A zero quantity is an empty slot, not an item
+        let mut items: Vec<_> = tree.items().collect();
+        items.sort();
+        assert_eq!(items, vec![(1, 5), (3, 7)]);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.root(), without_zero.root());
+    }
+
     #[test]
     fn test_proof_generation_and_verification() {
         let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
@@ -310,6 +504,58 @@ mod tree_tests {
         assert_eq!(tree1.root(), tree2.root());
     }
 
+    #[test]
+    fn test_removal_restores_empty_root() {
+        let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        let empty_root = tree.root();
+
+        tree.update(5, 10);
+        tree.update(5, 0);
+
+        assert_eq!(tree.root(), empty_root);
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100), (42, 50), (7, 3)], DEFAULT_DEPTH);
+        tree.update(7, 0); // Removed items must not affect the reloaded root
+        tree.update_leaf_hash(100, Fr::from(987654321u64));
+
+        let path = std::env::temp_dir().join(format!("smt_roundtrip_{}.bin", std::process::id()));
+        tree.save_to_file(&path).unwrap();
+        let loaded = SparseMerkleTree::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.depth(), tree.depth());
+        assert_eq!(loaded.root(), tree.root());
+        assert_eq!(loaded.get(42), 50);
+        assert_eq!(loaded.len(), 2);
+    }
+
+    #[test]
+    fn test_migrate_to_smaller_depth() {
+        let items = [(0, 10), (1, 20), (2, 30), (5, 40)];
+        let tree = SparseMerkleTree::from_items(&items, 4);
+
+        // 16 -> 8 slots: every item_id still fits
+        let migrated = tree.migrate(3).unwrap();
+        assert_eq!(migrated.depth(), 3);
+        assert_eq!(migrated.len(), 4);
+        assert_eq!(migrated.get(5), 40);
+        assert_eq!(migrated.root(), SparseMerkleTree::from_items(&items, 3).root());
+    }
+
+    #[test]
+    fn test_migrate_out_of_range_fails() {
+        let tree = SparseMerkleTree::from_items(&[(0, 10), (1, 20), (2, 30), (5, 40)], 4);
+
+        // 16 -> 2 slots: items 2 and 5 would be lost
+        assert_eq!(
+            tree.migrate(1).err(),
+            Some(MigrationError::ItemOutOfRange { item_id: 2, capacity: 2 })
+        );
+    }
+
     #[test]
     fn test_order_independence() {
         // Different order, same final state
@@ -318,4 +564,25 @@ mod tree_tests {
 
         assert_eq!(tree1.root(), tree2.root());
     }
+
+    #[test]
+    fn test_incremental_root_matches_recomputed() {
+        use ark_std::rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(0x5E7);
+        let mut tree = SparseMerkleTree::new(DEFAULT_DEPTH);
+        assert_eq!(tree.recompute_root(), tree.root());
+
+        // A small id range so updates hit shared paths and removals happen
+        for _ in 0..100 {
+            let item_id = rng.gen_range(0..64);
+            let root = if rng.gen_bool(0.1) {
+                tree.update_leaf_hash(item_id, Fr::from(rng.gen::<u64>()))
+            } else {
+                tree.update(item_id, rng.gen_range(0..4))
+            };
+
+            assert_eq!(root, tree.recompute_root());
+        }
+    }
 }
@@ -1,33 +1,139 @@
 //! SMT-based commitment scheme for inventories.
 //!
 //! The commitment scheme uses Poseidon hash:
-//! commitment = Poseidon(inventory_root, current_volume, blinding)
+//! commitment = Poseidon(COMMITMENT_DOMAIN, inventory_root, current_volume, blinding)
 //!
 //! Where:
 //! - inventory_root: Root of the Sparse Merkle Tree containing all items
 //! - current_volume: Total volume of all items in the inventory
 //! - blinding: Random value for hiding the commitment
+//!
+//! A separate, non-hiding content hash is also provided:
+//! content_hash = Poseidon(CONTENT_HASH_DOMAIN, inventory_root, current_volume)
 
 use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
 use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_serialize::CanonicalDeserialize;
+use std::fmt;
+
+use crate::poseidon::{
+    poseidon_config_cached, poseidon_hash_many, poseidon_hash_many_var, poseidon_hash_many_var_with,
+    poseidon_hash_many_with,
+};
+use crate::range_check::MAX_RANGE_VALUE;
+use crate::smt::SparseMerkleTree;
 
-use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+/// Domain tag for inventory commitments ("INVCOMMT" in ASCII).
+///
+/// Separates commitments from every other Poseidon hash over field elements,
+/// such as the public input hashes, which could otherwise absorb the same inputs.
+pub const COMMITMENT_DOMAIN: u64 = 0x494e56434f4d4d54;
+
+/// Version of the commitment scheme.
+///
+/// Version 1 was the untagged Poseidon(inventory_root, current_volume, blinding);
+/// version 2 prepends [`COMMITMENT_DOMAIN`]. Commitments of different versions
+/// never open to each other, so stored ones must be recomputed on upgrade.
+pub const COMMITMENT_VERSION: u32 = 2;
 
 /// Create an SMT-based inventory commitment using Poseidon.
 ///
-/// commitment = Poseidon(inventory_root, current_volume, blinding)
+/// commitment = Poseidon(COMMITMENT_DOMAIN, inventory_root, current_volume, blinding)
 pub fn create_smt_commitment(
     inventory_root: Fr,
     current_volume: u64,
     blinding: Fr,
+) -> Fr {
+    create_smt_commitment_with(
+        &poseidon_config_cached(),
+        inventory_root,
+        current_volume,
+        blinding,
+    )
+}
+
+/// Create an SMT-based inventory commitment with a specific Poseidon configuration.
+pub fn create_smt_commitment_with(
+    config: &PoseidonConfig<Fr>,
+    inventory_root: Fr,
+    current_volume: u64,
+    blinding: Fr,
 ) -> Fr {
     let inputs = vec![
+        Fr::from(COMMITMENT_DOMAIN),
         inventory_root,
         Fr::from(current_volume),
         blinding,
     ];
-    poseidon_hash_many(&inputs)
+    poseidon_hash_many_with(config, &inputs)
+}
+
+/// An inventory opening that cannot be committed to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommitmentError {
+    /// Blinding is not the 32-byte canonical encoding of a field element.
+    NonCanonicalBlinding,
+    /// An item quantity exceeds `MAX_RANGE_VALUE`.
+    QuantityOutOfRange { item_id: u64, quantity: u64 },
+    /// The volume exceeds `MAX_RANGE_VALUE`.
+    VolumeOutOfRange { volume: u64 },
+}
+
+impl fmt::Display for CommitmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommitmentError::NonCanonicalBlinding => {
+                write!(f, "blinding is not a canonical 32-byte field element")
+            }
+            CommitmentError::QuantityOutOfRange { item_id, quantity } => write!(
+                f,
+                "quantity {} of item {} exceeds the supported maximum {}",
+                quantity, item_id, MAX_RANGE_VALUE
+            ),
+            CommitmentError::VolumeOutOfRange { volume } => write!(
+                f,
+                "volume {} exceeds the supported maximum {}",
+                volume, MAX_RANGE_VALUE
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CommitmentError {}
+
+/// Commit to an inventory from untrusted input, validating it first.
+///
+/// `blinding` must be the canonical little-endian encoding of a field element;
+/// values at or above the modulus are rejected rather than reduced, since two
+/// encodings of the same blinding could otherwise be passed off as different.
+/// Quantities and the volume must fit the 32-bit range the circuits check, so
+/// the commitment can later be opened in a proof.
+pub fn try_create_smt_commitment(
+    tree: &SparseMerkleTree,
+    current_volume: u64,
+    blinding: &[u8],
+) -> Result<Fr, CommitmentError> {
+    if blinding.len() != 32 {
+        return Err(CommitmentError::NonCanonicalBlinding);
+    }
+    let blinding =
+        Fr::deserialize_compressed(blinding).map_err(|_| CommitmentError::NonCanonicalBlinding)?;
+
+    if let Some((item_id, quantity)) = tree
+        .items()
+        .filter(|&(_, quantity)| quantity > MAX_RANGE_VALUE)
+        .min()
+    {
+        return Err(CommitmentError::QuantityOutOfRange { item_id, quantity });
+    }
+    if current_volume > MAX_RANGE_VALUE {
+        return Err(CommitmentError::VolumeOutOfRange { volume: current_volume });
+    }
+
+    Ok(create_smt_commitment(tree.root(), current_volume, blinding))
 }
 
 /// Compute SMT commitment in-circuit using Poseidon.
@@ -36,12 +142,65 @@ pub fn create_smt_commitment_var(
     inventory_root: &FpVar<Fr>,
     current_volume: &FpVar<Fr>,
     blinding: &FpVar<Fr>,
+) -> Result<FpVar<Fr>, SynthesisError> {
+    create_smt_commitment_var_with(
+        cs,
+        &poseidon_config_cached(),
+        inventory_root,
+        current_volume,
+        blinding,
+    )
+}
+
+/// Compute SMT commitment in-circuit with a specific Poseidon configuration.
+pub fn create_smt_commitment_var_with(
+    cs: ConstraintSystemRef<Fr>,
+    config: &PoseidonConfig<Fr>,
+    inventory_root: &FpVar<Fr>,
+    current_volume: &FpVar<Fr>,
+    blinding: &FpVar<Fr>,
 ) -> Result<FpVar<Fr>, SynthesisError> {
     let inputs = vec![
+        FpVar::Constant(Fr::from(COMMITMENT_DOMAIN)),
         inventory_root.clone(),
         current_volume.clone(),
         blinding.clone(),
     ];
+    poseidon_hash_many_var_with(cs, config, &inputs)
+}
+
+/// Domain tag for inventory content hashes ("CONTENTS" in ASCII).
+///
+/// Separates content hashes from commitments and other 3-input Poseidon hashes.
+pub const CONTENT_HASH_DOMAIN: u64 = 0x434f4e54454e5453;
+
+/// Compute a fingerprint of an inventory's contents, independent of blinding.
+///
+/// content_hash = Poseidon(CONTENT_HASH_DOMAIN, inventory_root, current_volume)
+///
+/// This is NOT hiding: anyone who can guess the contents can recompute it.
+/// It is intended for deduplication of identical inventories, never as a
+/// substitute for `create_smt_commitment`.
+pub fn inventory_content_hash(inventory_root: Fr, current_volume: u64) -> Fr {
+    let inputs = vec![
+        Fr::from(CONTENT_HASH_DOMAIN),
+        inventory_root,
+        Fr::from(current_volume),
+    ];
+    poseidon_hash_many(&inputs)
+}
+
+/// Compute the inventory content hash in-circuit using Poseidon.
+pub fn inventory_content_hash_var(
+    cs: ConstraintSystemRef<Fr>,
+    inventory_root: &FpVar<Fr>,
+    current_volume: &FpVar<Fr>,
+) -> Result<FpVar<Fr>, SynthesisError> {
+    let inputs = vec![
+        FpVar::Constant(Fr::from(CONTENT_HASH_DOMAIN)),
+        inventory_root.clone(),
+        current_volume.clone(),
+    ];
     poseidon_hash_many_var(cs, &inputs)
 }
 
@@ -86,6 +245,11 @@ impl InventoryState {
         )
     }
 
+    /// Compute the non-hiding content hash for this state (ignores blinding).
+    pub fn content_hash(&self) -> Fr {
+        inventory_content_hash(self.inventory_root, self.current_volume)
+    }
+
     /// Update state after a deposit.
     ///
     /// Returns the new state and the volume delta.
@@ -152,6 +316,18 @@ impl InventoryStateVar {
         }
     }
 
+    /// Allocate an inventory state as witness variables.
+    pub fn new_witness(
+        cs: ConstraintSystemRef<Fr>,
+        state: &InventoryState,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            inventory_root: FpVar::new_witness(cs.clone(), || Ok(state.inventory_root))?,
+            current_volume: FpVar::new_witness(cs.clone(), || Ok(Fr::from(state.current_volume)))?,
+            blinding: FpVar::new_witness(cs, || Ok(state.blinding))?,
+        })
+    }
+
     /// Compute the commitment in-circuit.
     pub fn commitment(
         &self,
@@ -166,11 +342,30 @@ impl InventoryStateVar {
     }
 }
 
+/// Open several commitments at once: each state must commit to its expected value.
+///
+/// Equivalent to calling [`InventoryStateVar::commitment`] and `enforce_equal`
+/// for each pair, at the same constraint cost (one Poseidon hash per opening);
+/// keeping the checks in one place leaves room to batch them later.
+pub fn verify_commitments(
+    cs: ConstraintSystemRef<Fr>,
+    openings: &[(&InventoryStateVar, &FpVar<Fr>)],
+) -> Result<(), SynthesisError> {
+    let mut computed = Vec::with_capacity(openings.len());
+    let mut expected = Vec::with_capacity(openings.len());
+    for (state, commitment) in openings {
+        computed.push(state.commitment(cs.clone())?);
+        expected.push((*commitment).clone());
+    }
+
+    computed[..].enforce_equal(&expected[..])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
-    use ark_r1cs_std::prelude::*;
+    use crate::smt::DEFAULT_DEPTH;
+    use ark_serialize::CanonicalSerialize;
     use ark_relations::r1cs::ConstraintSystem;
 
     #[test]
@@ -216,6 +411,68 @@ mod tests {
         assert_ne!(commitment1, commitment2);
     }
 
+    #[test]
+    fn test_commitment_is_domain_separated() {
+        let root = Fr::from(12345u64);
+        let blinding = Fr::from(99999u64);
+
+        let volume = Fr::from(100u64);
+
+        let commitment = create_smt_commitment(root, 100, blinding);
+        let tagged = poseidon_hash_many(&[Fr::from(COMMITMENT_DOMAIN), root, volume, blinding]);
+
+        assert_eq!(commitment, tagged);
+        assert_ne!(commitment, poseidon_hash_many(&[root, volume, blinding]));
+    }
+
+    #[test]
+    fn test_commitment_hides_contents() {
+        use ark_ff::{PrimeField, UniformRand};
+        use ark_std::rand::{rngs::StdRng, Rng, SeedableRng};
+
+        // Not a proof of hiding, but a broken commitment that leaks structure
+        // (e.g. ignoring the blinding for some inputs) shows up as bias here.
+        const SAMPLES: usize = 1000;
+        const LOW_BITS: usize = 8;
+        const TOLERANCE: f64 = 0.1;
+
+        let mut rng = StdRng::seed_from_u64(0x1D1_u64);
+        let mut ones = [0usize; LOW_BITS];
+        let mut matches_content = [0usize; LOW_BITS];
+
+        for _ in 0..SAMPLES {
+            let items: Vec<(u64, u64)> = (0..rng.gen_range(0..4))
+                .map(|_| (rng.gen_range(0..1u64 << DEFAULT_DEPTH), rng.gen_range(1..1000)))
+                .collect();
+            let volume = rng.gen_range(0..10_000u64);
+            let root = SparseMerkleTree::from_items(&items, DEFAULT_DEPTH).root();
+
+            let commitment = create_smt_commitment(root, volume, Fr::rand(&mut rng));
+            let low_limb = commitment.into_bigint().0[0];
+
+            for bit in 0..LOW_BITS {
+                let commitment_bit = (low_limb >> bit) & 1;
+                ones[bit] += commitment_bit as usize;
+                // Compare against the same bit of the volume (a content property)
+                if commitment_bit == (volume >> bit) & 1 {
+                    matches_content[bit] += 1;
+                }
+            }
+        }
+
+        for bit in 0..LOW_BITS {
+            let ones_rate = ones[bit] as f64 / SAMPLES as f64;
+            let match_rate = matches_content[bit] as f64 / SAMPLES as f64;
+            assert!((ones_rate - 0.5).abs() < TOLERANCE, "bit {} biased: {}", bit, ones_rate);
+            assert!(
+                (match_rate - 0.5).abs() < TOLERANCE,
+                "bit {} correlates with volume: {}",
+                bit,
+                match_rate
+            );
+        }
+    }
+
     #[test]
     fn test_in_circuit_matches_native() {
         let root = Fr::from(12345u64);
@@ -248,6 +505,202 @@ mod tests {
         println!("SMT commitment constraints: {}", cs.num_constraints());
     }
 
+    /// Six states, one old/new pair per fee transfer party.
+    fn transfer_states() -> Vec<InventoryState> {
+        (0..6u64)
+            .map(|i| InventoryState::new(Fr::from(1000 + i), 10 * i, Fr::from(7 + i)))
+            .collect()
+    }
+
+    /// Open `states` against `commitments`, batched or one at a time.
+    fn open_commitments(
+        states: &[InventoryState],
+        commitments: &[Fr],
+        batched: bool,
+    ) -> ConstraintSystemRef<Fr> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let state_vars: Vec<InventoryStateVar> = states
+            .iter()
+            .map(|state| InventoryStateVar::new_witness(cs.clone(), state).unwrap())
+            .collect();
+        let expected_vars: Vec<FpVar<Fr>> = commitments
+            .iter()
+            .map(|&c| FpVar::new_input(cs.clone(), || Ok(c)).unwrap())
+            .collect();
+
+        if batched {
+            let openings: Vec<_> = state_vars.iter().zip(&expected_vars).collect();
+            verify_commitments(cs.clone(), &openings).unwrap();
+        } else {
+            for (state, expected) in state_vars.iter().zip(&expected_vars) {
+                state.commitment(cs.clone()).unwrap().enforce_equal(expected).unwrap();
+            }
+        }
+        cs
+    }
+
+    #[test]
+    fn test_verify_commitments_matches_individual_checks() {
+        let states = transfer_states();
+        let commitments: Vec<Fr> = states.iter().map(InventoryState::commitment).collect();
+
+        let batched = open_commitments(&states, &commitments, true);
+        let individual = open_commitments(&states, &commitments, false);
+
+        assert!(batched.is_satisfied().unwrap());
+        assert!(individual.is_satisfied().unwrap());
+        assert_eq!(batched.num_constraints(), individual.num_constraints());
+
+        // A single wrong commitment fails both ways
+        let mut wrong = commitments.clone();
+        wrong[3] += Fr::from(1u64);
+        assert!(!open_commitments(&states, &wrong, true).is_satisfied().unwrap());
+        assert!(!open_commitments(&states, &wrong, false).is_satisfied().unwrap());
+    }
+
+    /// Constraints for a commitment and for a 33-element absorb at `rate`,
+    /// checking each against its native counterpart.
+    fn constraints_at_rate(rate: usize) -> (usize, usize) {
+        let config = crate::poseidon::poseidon_config_with_rate(rate).unwrap();
+
+        let (root, volume, blinding) = (Fr::from(12345u64), 100u64, Fr::from(99999u64));
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let root_var = FpVar::new_witness(cs.clone(), || Ok(root)).unwrap();
+        let volume_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(volume))).unwrap();
+        let blinding_var = FpVar::new_witness(cs.clone(), || Ok(blinding)).unwrap();
+        let commitment = create_smt_commitment_var_with(
+            cs.clone(),
+            &config,
+            &root_var,
+            &volume_var,
+            &blinding_var,
+        )
+        .unwrap();
+        assert_eq!(
+            commitment.value().unwrap(),
+            create_smt_commitment_with(&config, root, volume, blinding)
+        );
+        let commitment_constraints = cs.num_constraints();
+
+        let inputs: Vec<Fr> = (0..33u64).map(Fr::from).collect();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let input_vars: Vec<FpVar<Fr>> = inputs
+            .iter()
+            .map(|&x| FpVar::new_witness(cs.clone(), || Ok(x)).unwrap())
+            .collect();
+        let hash = poseidon_hash_many_var_with(cs.clone(), &config, &input_vars).unwrap();
+        assert_eq!(hash.value().unwrap(), poseidon_hash_many_with(&config, &inputs));
+        let absorb_constraints = cs.num_constraints();
+
+        (commitment_constraints, absorb_constraints)
+    }
+
+    #[test]
+    fn test_commitment_constraints_rate_2_vs_4() {
+        let (commitment_2, absorb_2) = constraints_at_rate(2);
+        let (commitment_4, absorb_4) = constraints_at_rate(4);
+        println!("Commitment constraints: rate 2 = {}, rate 4 = {}", commitment_2, commitment_4);
+        println!("33-element absorb constraints: rate 2 = {}, rate 4 = {}", absorb_2, absorb_4);
+
+        // Fewer, wider permutations win once they replace enough narrow ones
+        assert!(commitment_4 < commitment_2);
+        assert!(absorb_4 < absorb_2);
+
+        // Rate 2 is the crate's default configuration
+        let (root, blinding) = (Fr::from(12345u64), Fr::from(99999u64));
+        assert_eq!(
+            create_smt_commitment_with(&crate::poseidon::poseidon_config(), root, 100, blinding),
+            create_smt_commitment(root, 100, blinding)
+        );
+    }
+
+    #[test]
+    fn test_try_create_commitment_valid() {
+        let tree = SparseMerkleTree::from_items(&[(1, 100), (42, 5)], DEFAULT_DEPTH);
+        let blinding = Fr::from(99999u64);
+        let mut bytes = Vec::new();
+        blinding.serialize_compressed(&mut bytes).unwrap();
+
+        assert_eq!(
+            try_create_smt_commitment(&tree, 150, &bytes),
+            Ok(create_smt_commitment(tree.root(), 150, blinding))
+        );
+    }
+
+    #[test]
+    fn test_try_create_commitment_rejects_out_of_range() {
+        let tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+
+        // 2^256 - 1 is above the modulus; from_le_bytes_mod_order would reduce it
+        let blinding = [0xff; 32];
+        assert_eq!(
+            try_create_smt_commitment(&tree, 100, &blinding),
+            Err(CommitmentError::NonCanonicalBlinding)
+        );
+        assert_eq!(
+            try_create_smt_commitment(&tree, 100, &[1; 31]),
+            Err(CommitmentError::NonCanonicalBlinding)
+        );
+
+        let valid_blinding = [0; 32];
+        assert_eq!(
+            try_create_smt_commitment(&tree, MAX_RANGE_VALUE + 1, &valid_blinding),
+            Err(CommitmentError::VolumeOutOfRange { volume: MAX_RANGE_VALUE + 1 })
+        );
+
+        let big = SparseMerkleTree::from_items(&[(7, MAX_RANGE_VALUE + 1)], DEFAULT_DEPTH);
+        assert_eq!(
+            try_create_smt_commitment(&big, 0, &valid_blinding),
+            Err(CommitmentError::QuantityOutOfRange { item_id: 7, quantity: MAX_RANGE_VALUE + 1 })
+        );
+    }
+
+    #[test]
+    fn test_content_hash_ignores_blinding() {
+        let tree = SparseMerkleTree::from_items(&[(1, 100), (42, 5)], DEFAULT_DEPTH);
+
+        let state1 = InventoryState::new(tree.root(), 150, Fr::from(1u64));
+        let state2 = InventoryState::new(tree.root(), 150, Fr::from(2u64));
+
+        assert_ne!(state1.commitment(), state2.commitment());
+        assert_eq!(state1.content_hash(), state2.content_hash());
+    }
+
+    #[test]
+    fn test_content_hash_differs_for_different_contents() {
+        let tree1 = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let tree2 = SparseMerkleTree::from_items(&[(1, 101)], DEFAULT_DEPTH);
+
+        assert_ne!(
+            inventory_content_hash(tree1.root(), 100),
+            inventory_content_hash(tree2.root(), 100)
+        );
+        assert_ne!(
+            inventory_content_hash(tree1.root(), 100),
+            inventory_content_hash(tree1.root(), 101)
+        );
+    }
+
+    #[test]
+    fn test_content_hash_in_circuit_matches_native() {
+        let root = Fr::from(12345u64);
+        let volume = 100u64;
+        let native_hash = inventory_content_hash(root, volume);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+
+        let root_var = FpVar::new_witness(cs.clone(), || Ok(root)).unwrap();
+        let volume_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(volume))).unwrap();
+
+        let circuit_hash =
+            inventory_content_hash_var(cs.clone(), &root_var, &volume_var).unwrap();
+
+        let expected_var = FpVar::new_input(cs.clone(), || Ok(native_hash)).unwrap();
+        circuit_hash.enforce_equal(&expected_var).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+    }
+
     #[test]
     fn test_inventory_state_workflow() {
         // Create empty inventory
@@ -0,0 +1,346 @@
+//! StackLimit Circuit for SMT-based inventory.
+//!
+//! Proves that an inventory holds no more of an item than that item's max stack.
+//!
+//! Limits come from a committed table: a Sparse Merkle Tree whose leaves are
+//! H(item_id, max_stack), built with `SparseMerkleTree::from_items`. Items
+//! without an entry (empty leaf) fall back to a public `default_cap`. Because
+//! empty leaves are canonical, a table entry of 0 also means "use the default".
+//!
+//! An SMT cannot be iterated in-circuit, so the check is per item. Enforcing it
+//! for the item touched by every state transition keeps all leaves within their
+//! limits inductively.
+//!
+//! Public input: Poseidon(commitment, limits_root, default_cap, item_id)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::enforce_geq;
+use crate::smt::{
+    compute_default_leaf_hash, compute_root_from_path, hash_leaf, verify_membership, MerkleProof,
+    MerkleProofVar,
+};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+
+/// Compute the public input hash for StackLimit proof.
+pub fn compute_stack_limit_hash(
+    commitment: Fr,
+    limits_root: Fr,
+    default_cap: u64,
+    item_id: u64,
+) -> Fr {
+    let inputs = vec![
+        commitment,
+        limits_root,
+        Fr::from(default_cap),
+        Fr::from(item_id),
+    ];
+    poseidon_hash_many(&inputs)
+}
+
+/// Enforce `quantity <= max_stack(item_id)` against a committed limits table.
+///
+/// `max_stack` is the table value for `item_id`, or 0 when the item has no entry,
+/// in which case `default_cap` applies. The proof is bound to `item_id`'s position
+/// so an entry cannot be hidden behind another empty slot.
+pub fn enforce_stack_limit(
+    cs: ConstraintSystemRef<Fr>,
+    limits_root: &FpVar<Fr>,
+    item_id: &FpVar<Fr>,
+    quantity: &FpVar<Fr>,
+    max_stack: &FpVar<Fr>,
+    limit_proof: &MerkleProofVar,
+    default_cap: &FpVar<Fr>,
+) -> Result<(), SynthesisError> {
+    limit_proof.enforce_index(item_id)?;
+
+    // Table leaf is H(item_id, max_stack), or the empty leaf when there is no entry
+    let has_entry = max_stack.is_neq(&FpVar::zero())?;
+    let entry_leaf = hash_leaf(cs.clone(), item_id, max_stack)?;
+    let empty_leaf = FpVar::constant(compute_default_leaf_hash());
+    let leaf = has_entry.select(&entry_leaf, &empty_leaf)?;
+
+    let computed_root = compute_root_from_path(cs.clone(), &leaf, limit_proof)?;
+    computed_root.enforce_equal(limits_root)?;
+
+    // quantity <= cap
+    let cap = has_entry.select(max_stack, default_cap)?;
+    enforce_geq(cs, &cap, quantity)
+}
+
+/// StackLimit Circuit.
+#[derive(Clone)]
+pub struct StackLimitCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Commitment components (witnesses)
+    /// Inventory SMT root
+    pub inventory_root: Option<Fr>,
+    /// Current volume
+    pub current_volume: Option<u64>,
+    /// Blinding factor
+    pub blinding: Option<Fr>,
+
+    // Item details (witnesses)
+    /// Item ID being checked
+    pub item_id: Option<u64>,
+    /// Quantity held
+    pub quantity: Option<u64>,
+    /// Proof for item in the inventory SMT
+    pub inventory_proof: Option<MerkleProof<Fr>>,
+
+    // Limits table (witnesses)
+    /// Root of the limits table
+    pub limits_root: Option<Fr>,
+    /// Table entry for item_id (0 when absent)
+    pub max_stack: Option<u64>,
+    /// Proof for item_id in the limits table
+    pub limit_proof: Option<MerkleProof<Fr>>,
+    /// Cap for items without a table entry
+    pub default_cap: Option<u64>,
+}
+
+impl StackLimitCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        use crate::smt::DEFAULT_DEPTH;
+
+        let dummy_proof = MerkleProof::new(
+            vec![Fr::from(0u64); DEFAULT_DEPTH],
+            vec![false; DEFAULT_DEPTH],
+        );
+
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            inventory_root: Some(Fr::from(0u64)),
+            current_volume: Some(0),
+            blinding: Some(Fr::from(0u64)),
+            item_id: Some(0),
+            quantity: Some(0),
+            inventory_proof: Some(dummy_proof.clone()),
+            limits_root: Some(Fr::from(0u64)),
+            max_stack: Some(0),
+            limit_proof: Some(dummy_proof),
+            default_cap: Some(0),
+        }
+    }
+
+    /// Create a new circuit with witnesses.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        inventory_root: Fr,
+        current_volume: u64,
+        blinding: Fr,
+        item_id: u64,
+        quantity: u64,
+        inventory_proof: MerkleProof<Fr>,
+        limits_root: Fr,
+        max_stack: u64,
+        limit_proof: MerkleProof<Fr>,
+        default_cap: u64,
+    ) -> Self {
+        let commitment = create_smt_commitment(inventory_root, current_volume, blinding);
+        let public_hash = compute_stack_limit_hash(commitment, limits_root, default_cap, item_id);
+
+        Self {
+            public_hash: Some(public_hash),
+            inventory_root: Some(inventory_root),
+            current_volume: Some(current_volume),
+            blinding: Some(blinding),
+            item_id: Some(item_id),
+            quantity: Some(quantity),
+            inventory_proof: Some(inventory_proof),
+            limits_root: Some(limits_root),
+            max_stack: Some(max_stack),
+            limit_proof: Some(limit_proof),
+            default_cap: Some(default_cap),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for StackLimitCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate commitment witnesses ===
+        let root_var = FpVar::new_witness(cs.clone(), || {
+            self.inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let volume_var = FpVar::new_witness(cs.clone(), || {
+            self.current_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate item witnesses ===
+        let item_id_var = FpVar::new_witness(cs.clone(), || {
+            self.item_id
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let quantity_var = FpVar::new_witness(cs.clone(), || {
+            self.quantity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let inventory_proof_var = MerkleProofVar::new_witness(
+            cs.clone(),
+            self.inventory_proof.as_ref().unwrap(),
+        )?;
+
+        // === Allocate limits table witnesses ===
+        let limits_root_var = FpVar::new_witness(cs.clone(), || {
+            self.limits_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let max_stack_var = FpVar::new_witness(cs.clone(), || {
+            self.max_stack
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let limit_proof_var = MerkleProofVar::new_witness(
+            cs.clone(),
+            self.limit_proof.as_ref().unwrap(),
+        )?;
+        let default_cap_var = FpVar::new_witness(cs.clone(), || {
+            self.default_cap
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Constraint 1: Verify item membership in inventory SMT ===
+        verify_membership(
+            cs.clone(),
+            &root_var,
+            &item_id_var,
+            &quantity_var,
+            &inventory_proof_var,
+        )?;
+
+        // === Constraint 2: quantity <= max_stack(item_id) ===
+        enforce_stack_limit(
+            cs.clone(),
+            &limits_root_var,
+            &item_id_var,
+            &quantity_var,
+            &max_stack_var,
+            &limit_proof_var,
+            &default_cap_var,
+        )?;
+
+        // === Constraint 3: Compute commitment using Poseidon ===
+        let commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &root_var,
+            &volume_var,
+            &blinding_var,
+        )?;
+
+        // === Constraint 4: Compute and verify public hash ===
+        let inputs = vec![
+            commitment_var,
+            limits_root_var,
+            default_cap_var,
+            item_id_var,
+        ];
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+    use ark_relations::r1cs::ConstraintSystem;
+
+    const DEFAULT_CAP: u64 = 99;
+
+    /// Swords (1) stack to 1, potions (2) to 64; everything else uses DEFAULT_CAP.
+    fn limits() -> SparseMerkleTree {
+        SparseMerkleTree::from_items(&[(1, 1), (2, 64)], DEFAULT_DEPTH)
+    }
+
+    fn stack_limit_circuit(
+        inventory: &SparseMerkleTree,
+        limits: &SparseMerkleTree,
+        item_id: u64,
+        max_stack: u64,
+        limit_proof_for: u64,
+    ) -> StackLimitCircuit {
+        StackLimitCircuit::new(
+            inventory.root(),
+            0,
+            Fr::from(12345u64),
+            item_id,
+            inventory.get(item_id),
+            inventory.get_proof(item_id),
+            limits.root(),
+            max_stack,
+            limits.get_proof(limit_proof_for),
+            DEFAULT_CAP,
+        )
+    }
+
+    fn is_satisfied(circuit: StackLimitCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_stack_limits_respected() {
+        let inventory = SparseMerkleTree::from_items(&[(1, 1), (2, 64), (3, 50)], DEFAULT_DEPTH);
+        let limits = limits();
+
+        for item_id in [1, 2, 3] {
+            let max_stack = limits.get(item_id);
+            let circuit = stack_limit_circuit(&inventory, &limits, item_id, max_stack, item_id);
+            assert!(is_satisfied(circuit), "item {} should be within its limit", item_id);
+        }
+    }
+
+    #[test]
+    fn test_stack_limit_exceeded_fails() {
+        let inventory = SparseMerkleTree::from_items(&[(2, 65)], DEFAULT_DEPTH);
+        let limits = limits();
+
+        assert!(!is_satisfied(stack_limit_circuit(&inventory, &limits, 2, 64, 2)));
+    }
+
+    #[test]
+    fn test_stack_limit_default_cap() {
+        let limits = limits();
+
+        let at_cap = SparseMerkleTree::from_items(&[(3, DEFAULT_CAP)], DEFAULT_DEPTH);
+        assert!(is_satisfied(stack_limit_circuit(&at_cap, &limits, 3, 0, 3)));
+
+        let over_cap = SparseMerkleTree::from_items(&[(3, DEFAULT_CAP + 1)], DEFAULT_DEPTH);
+        assert!(!is_satisfied(stack_limit_circuit(&over_cap, &limits, 3, 0, 3)));
+    }
+
+    #[test]
+    fn test_stack_limit_cannot_hide_table_entry() {
+        // 5 swords would pass the default cap, but swords have an entry of 1.
+        // Claiming "no entry" with the proof of an empty slot must fail.
+        let inventory = SparseMerkleTree::from_items(&[(1, 5)], DEFAULT_DEPTH);
+        let limits = limits();
+
+        assert!(!is_satisfied(stack_limit_circuit(&inventory, &limits, 1, 0, 3)));
+        assert!(!is_satisfied(stack_limit_circuit(&inventory, &limits, 1, 0, 1)));
+    }
+}
@@ -8,6 +8,8 @@
 //! - nonce: Replay protection (verified on-chain against inventory.nonce)
 //! - inventory_id: Cross-inventory protection (verified on-chain)
 //! - registry_root: Volume registry commitment (verified against VolumeRegistry)
+//! - min_total_reserve: Only when set via `with_min_total_reserve` (see below)
+//! - epoch: Only when set via `with_epoch` (see below)
 //!
 //! Witnesses:
 //! - Old inventory state (root, volume, blinding)
@@ -16,14 +18,47 @@
 //! - Merkle proof for the item
 //! - Registry proof for item volume lookup
 //! - Operation parameters (amount, op_type, max_capacity)
+//!
+//! Only the target leaf can change: the new root is recomputed from the old
+//! Merkle path with just that leaf replaced, so every other item is bound to
+//! its old value without a per-item loop or summed-delta constraint. The path
+//! is bound to item_id's position, so a deposit of a new item (old quantity 0)
+//! cannot be written into some other empty slot.
+//!
+//! ## Minimum reserve
+//!
+//! Collateralized inventories can require a floor on what remains after an
+//! operation. Item quantities live in SMT leaves and cannot be summed in-circuit,
+//! so the floor applies to the committed total volume, which already tracks the
+//! sum of `quantity * item_volume` over all items. Setting a reserve adds a fifth
+//! public input, so reserve-enabled proofs need their own keys (set up from
+//! `StateTransitionCircuit::empty().with_min_total_reserve(0)`).
+//!
+//! ## Epoch
+//!
+//! Verifiers that want to reject stale proofs can tag them with an epoch. The
+//! circuit only exposes the epoch as a public input (after `min_total_reserve`
+//! when both are set) and checks it fits in 64 bits, which ties it to the proof;
+//! deciding which epochs are fresh is up to the verifier. As with the reserve,
+//! epoch-tagged proofs need their own keys (set up from
+//! `StateTransitionCircuit::empty().with_epoch(0)`).
+//!
+//! ## Supported range
+//!
+//! Quantities, volumes and capacities are range checked to 32 bits
+//! (`range_check::MAX_RANGE_VALUE`). `amount` may be any u64: the arithmetic
+//! happens in the field and does not wrap at 2^64, so an amount that overflows
+//! or underflows the quantity yields a value outside the 32-bit range and the
+//! circuit is unsatisfiable.
 
 use ark_bn254::Fr;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::prelude::*;
+use ark_relations::ns;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 
-use crate::range_check::{enforce_geq, enforce_u32_range};
-use crate::signal::{compute_signal_hash, OpType};
+use crate::range_check::{enforce_geq, enforce_range, enforce_u32_range};
+use crate::signal::{OpType, SignalInputs};
 use crate::smt::{verify_and_update, MerkleProof, MerkleProofVar};
 use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
 
@@ -81,6 +116,14 @@ pub struct StateTransitionCircuit {
     // Capacity
     /// Maximum allowed capacity
     pub max_capacity: Option<u64>,
+
+    // Reserve
+    /// Minimum total volume that must remain after the operation (public input when set)
+    pub min_total_reserve: Option<u64>,
+
+    // Freshness
+    /// Epoch the proof is tagged with (public input when set)
+    pub epoch: Option<u64>,
 }
 
 impl StateTransitionCircuit {
@@ -114,6 +157,8 @@ impl StateTransitionCircuit {
             item_volume: Some(0),
             registry_root: Some(Fr::from(0u64)),
             max_capacity: Some(0),
+            min_total_reserve: None,
+            epoch: None,
         }
     }
 
@@ -138,22 +183,10 @@ impl StateTransitionCircuit {
         nonce: u64,
         inventory_id: Fr,
     ) -> Self {
-        // Compute commitments using Anemoi
-        let old_commitment = create_smt_commitment(
-            old_inventory_root,
-            old_volume,
-            old_blinding,
-        );
-        let new_commitment = create_smt_commitment(
-            new_inventory_root,
-            new_volume,
-            new_blinding,
-        );
-
-        // Compute signal hash (includes nonce and inventory_id for replay/cross-inventory protection)
-        let signal_hash = compute_signal_hash(
-            old_commitment,
-            new_commitment,
+        // Signal hash includes nonce and inventory_id for replay/cross-inventory protection
+        let signal = SignalInputs {
+            old_commitment: create_smt_commitment(old_inventory_root, old_volume, old_blinding),
+            new_commitment: create_smt_commitment(new_inventory_root, new_volume, new_blinding),
             registry_root,
             max_capacity,
             item_id,
@@ -161,29 +194,83 @@ impl StateTransitionCircuit {
             op_type,
             nonce,
             inventory_id,
-        );
+        };
 
+        Self::from_signal(
+            &signal,
+            old_inventory_root,
+            old_volume,
+            old_blinding,
+            new_inventory_root,
+            new_volume,
+            new_blinding,
+            old_quantity,
+            new_quantity,
+            inventory_proof,
+            item_volume,
+        )
+    }
+
+    /// Create a circuit for the statement in `signal`.
+    ///
+    /// Like [`StateTransitionCircuit::new`], but the commitments are taken from
+    /// `signal` instead of being recomputed from the witnesses, for callers that
+    /// already hold them. The witnesses must still open to those commitments,
+    /// or the constraints are not satisfied.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_signal(
+        signal: &SignalInputs,
+        old_inventory_root: Fr,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_inventory_root: Fr,
+        new_volume: u64,
+        new_blinding: Fr,
+        old_quantity: u64,
+        new_quantity: u64,
+        inventory_proof: MerkleProof<Fr>,
+        item_volume: u64,
+    ) -> Self {
         Self {
-            signal_hash: Some(signal_hash),
-            nonce: Some(nonce),
-            inventory_id: Some(inventory_id),
+            signal_hash: Some(signal.compute_hash()),
+            nonce: Some(signal.nonce),
+            inventory_id: Some(signal.inventory_id),
             old_inventory_root: Some(old_inventory_root),
             old_volume: Some(old_volume),
             old_blinding: Some(old_blinding),
             new_inventory_root: Some(new_inventory_root),
             new_volume: Some(new_volume),
             new_blinding: Some(new_blinding),
-            item_id: Some(item_id),
+            item_id: Some(signal.item_id),
             old_quantity: Some(old_quantity),
             new_quantity: Some(new_quantity),
-            amount: Some(amount),
-            op_type: Some(op_type),
+            amount: Some(signal.amount),
+            op_type: Some(signal.op_type),
             inventory_proof: Some(inventory_proof),
             item_volume: Some(item_volume),
-            registry_root: Some(registry_root),
-            max_capacity: Some(max_capacity),
+            registry_root: Some(signal.registry_root),
+            max_capacity: Some(signal.max_capacity),
+            min_total_reserve: None,
+            epoch: None,
         }
     }
+
+    /// Require the new total volume to be at least `min_total_reserve`.
+    ///
+    /// The reserve becomes an extra public input after `registry_root`.
+    pub fn with_min_total_reserve(mut self, min_total_reserve: u64) -> Self {
+        self.min_total_reserve = Some(min_total_reserve);
+        self
+    }
+
+    /// Tag the proof with `epoch`.
+    ///
+    /// The epoch becomes an extra public input after `registry_root` (and after
+    /// `min_total_reserve` when that is set).
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
 }
 
 impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
@@ -265,6 +352,18 @@ impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
             self.registry_root.ok_or(SynthesisError::AssignmentMissing)
         })?;
 
+        // === Allocate optional reserve public input ===
+        let min_reserve_var = self
+            .min_total_reserve
+            .map(|reserve| FpVar::new_input(cs.clone(), || Ok(Fr::from(reserve))))
+            .transpose()?;
+
+        // === Allocate optional epoch public input ===
+        let epoch_var = self
+            .epoch
+            .map(|epoch| FpVar::new_input(cs.clone(), || Ok(Fr::from(epoch))))
+            .transpose()?;
+
         // === Allocate registry witnesses ===
         let item_volume_var = FpVar::new_witness(cs.clone(), || {
             self.item_volume
@@ -279,6 +378,7 @@ impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
 
         // === Constraint 1: Verify and update inventory SMT ===
         // This verifies the old state and computes the new root
+        let section = ns!(cs, "inventory_update");
         let computed_new_root = verify_and_update(
             cs.clone(),
             &old_root_var,
@@ -290,10 +390,12 @@ impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
 
         // Enforce computed new root matches claimed new root
         computed_new_root.enforce_equal(&new_root_var)?;
+        drop(section);
 
         // === Constraint 2: Verify quantity change matches operation ===
         // For deposit: new_qty = old_qty + amount
         // For withdraw: new_qty = old_qty - amount
+        let section = ns!(cs, "quantity");
         let zero = FpVar::zero();
         let one = FpVar::one();
         let is_deposit = op_type_var.is_eq(&zero)?;
@@ -309,8 +411,10 @@ impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
         // Prevents underflow attacks where withdraw > current quantity
         // If qty_minus_amount wrapped around (negative), it won't fit in 32 bits
         enforce_u32_range(cs.clone(), &new_qty_var)?;
+        drop(section);
 
         // === Constraint 4: Verify volume change ===
+        let section = ns!(cs, "volume");
         // volume_delta = item_volume * amount
         let volume_delta = &item_volume_var * &amount_var;
 
@@ -331,7 +435,14 @@ impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
         // enforce_geq checks that (max_capacity - new_volume) fits in 32 bits
         enforce_geq(cs.clone(), &max_capacity_var, &new_volume_var)?;
 
+        // Optional reserve floor: new_volume >= min_total_reserve
+        if let Some(min_reserve_var) = &min_reserve_var {
+            enforce_geq(cs.clone(), &new_volume_var, min_reserve_var)?;
+        }
+        drop(section);
+
         // === Constraint 7: Compute commitments using Anemoi ===
+        let section = ns!(cs, "commitment");
         let old_commitment_var = create_smt_commitment_var(
             cs.clone(),
             &old_root_var,
@@ -346,7 +457,10 @@ impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
             &new_blinding_var,
         )?;
 
+        drop(section);
+
         // === Constraint 8: Compute and verify signal hash ===
+        let section = ns!(cs, "signal_hash");
         // Signal hash now includes nonce and inventory_id for replay/cross-inventory protection
         let computed_signal = crate::signal::compute_signal_hash_var(
             cs.clone(),
@@ -362,11 +476,23 @@ impl ConstraintSynthesizer<Fr> for StateTransitionCircuit {
         )?;
 
         computed_signal.enforce_equal(&signal_hash_var)?;
+        drop(section);
 
         // === Constraint 9: Ensure op_type is valid (0 or 1) ===
+        let section = ns!(cs, "op_type");
         let is_withdraw = op_type_var.is_eq(&one)?;
         let is_valid_op = is_deposit.or(&is_withdraw)?;
         is_valid_op.enforce_equal(&Boolean::TRUE)?;
+        drop(section);
+
+        // === Constraint 10: Epoch fits in 64 bits ===
+        // The proof binds the epoch like any public input; this only keeps it
+        // within the u64 range verifiers compare epochs in
+        if let Some(epoch_var) = &epoch_var {
+            let section = ns!(cs, "epoch");
+            enforce_range(cs.clone(), epoch_var, 64)?;
+            drop(section);
+        }
 
         Ok(())
     }
@@ -431,6 +557,57 @@ mod tests {
         println!("StateTransition (deposit) constraints: {}", cs.num_constraints());
     }
 
+    #[test]
+    fn test_from_signal_uses_given_commitments() {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+        tree.update(1, 150);
+        let new_root = tree.root();
+
+        let (old_blinding, new_blinding) = (Fr::from(12345u64), Fr::from(67890u64));
+        let mut signal = SignalInputs {
+            old_commitment: create_smt_commitment(old_root, 1000, old_blinding),
+            new_commitment: create_smt_commitment(new_root, 1500, new_blinding),
+            registry_root: Fr::from(99999u64),
+            max_capacity: 10000,
+            item_id: 1,
+            amount: 50,
+            op_type: OpType::Deposit,
+            nonce: 0,
+            inventory_id: Fr::from(12345678u64),
+        };
+        let circuit = |signal: &SignalInputs| {
+            StateTransitionCircuit::from_signal(
+                signal,
+                old_root,
+                1000,
+                old_blinding,
+                new_root,
+                1500,
+                new_blinding,
+                100,
+                150,
+                proof.clone(),
+                10,
+            )
+        };
+
+        let valid = circuit(&signal);
+        assert_eq!(valid.signal_hash, Some(signal.compute_hash()));
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        valid.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+
+        // The given commitment is used as is, so one the witnesses do not open to fails
+        signal.old_commitment += Fr::from(1u64);
+        let stale = circuit(&signal);
+        assert_eq!(stale.signal_hash, Some(signal.compute_hash()));
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        stale.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
     #[test]
     fn test_state_transition_withdraw() {
         // Create initial inventory with 1 item
@@ -630,6 +807,211 @@ mod tests {
         assert!(!cs.is_satisfied().unwrap());
     }
 
+    #[test]
+    fn test_unrelated_item_inflation_rejected() {
+        // Deposit 50 of item 1 while secretly inflating item 2
+        let mut tree = SparseMerkleTree::from_items(
+            &[(1, 100), (2, 10)],
+            DEFAULT_DEPTH,
+        );
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+
+        tree.update(1, 150);
+        tree.update(2, 1000); // Not part of this operation
+        let new_root = tree.root();
+
+        let item_volume = 10u64;
+        let old_volume = 110 * item_volume;
+        let new_volume = 160 * item_volume; // Volume only reflects the declared deposit
+
+        let circuit = StateTransitionCircuit::new(
+            old_root,
+            old_volume,
+            Fr::from(12345u64),
+            new_root,
+            new_volume,
+            Fr::from(67890u64),
+            1,   // item_id
+            100, // old_quantity
+            150, // new_quantity
+            50,  // amount
+            OpType::Deposit,
+            proof,
+            item_volume,
+            Fr::from(99999u64),
+            100000,
+            0,
+            Fr::from(12345678u64),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        // The new root is recomputed from item 1's path, so any other leaf change
+        // makes it differ from the claimed new root
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_deposit_into_wrong_empty_slot_rejected() {
+        // Deposit new item 5 by opening the empty slot 9 instead of its own
+        let tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let proof = tree.get_proof(9);
+        let new_root = proof.compute_root(5, 20);
+
+        let item_volume = 10u64;
+        let circuit = StateTransitionCircuit::new(
+            tree.root(),
+            100 * item_volume,
+            Fr::from(12345u64),
+            new_root,
+            120 * item_volume,
+            Fr::from(67890u64),
+            5,  // item_id
+            0,  // old_quantity
+            20, // new_quantity
+            20, // amount
+            OpType::Deposit,
+            proof,
+            item_volume,
+            Fr::from(99999u64),
+            100000,
+            0,
+            Fr::from(12345678u64),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        // An empty leaf does not name its item, so the path must be item 5's own
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    /// Withdraw 30 of item 1 (volume 10 each) from 100, leaving volume 700.
+    fn withdraw_with_reserve(min_total_reserve: u64) -> StateTransitionCircuit {
+        let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+
+        tree.update(1, 70);
+        let new_root = tree.root();
+
+        StateTransitionCircuit::new(
+            old_root,
+            1000,
+            Fr::from(12345u64),
+            new_root,
+            700,
+            Fr::from(67890u64),
+            1,
+            100,
+            70,
+            30,
+            OpType::Withdraw,
+            proof,
+            10,
+            Fr::from(99999u64),
+            10000,
+            0,
+            Fr::from(12345678u64),
+        )
+        .with_min_total_reserve(min_total_reserve)
+    }
+
+    #[test]
+    fn test_withdraw_leaves_min_reserve() {
+        let circuit = withdraw_with_reserve(700);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        // signal_hash, nonce, inventory_id, registry_root, min_total_reserve (+ constant one)
+        assert_eq!(cs.num_instance_variables(), 6);
+    }
+
+    #[test]
+    fn test_withdraw_below_min_reserve_fails() {
+        let circuit = withdraw_with_reserve(701);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_epoch_is_last_public_input() {
+        let circuit = withdraw_with_reserve(700).with_epoch(42);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        // signal_hash, nonce, inventory_id, registry_root, min_total_reserve, epoch
+        let instance = cs.borrow().unwrap().instance_assignment.clone();
+        assert_eq!(instance.len(), 7);
+        assert_eq!(instance[6], Fr::from(42u64));
+    }
+
+    /// Move `amount` of item 1 (volume 0, so only the quantity matters) from
+    /// `old_qty`, claiming the u64 result a wrapping implementation would produce.
+    fn transition_near_limit(old_qty: u64, amount: u64, op_type: OpType) -> bool {
+        let new_qty = match op_type {
+            OpType::Deposit => old_qty.wrapping_add(amount),
+            OpType::Withdraw => old_qty.wrapping_sub(amount),
+        };
+
+        let mut tree = SparseMerkleTree::from_items(&[(1, old_qty)], DEFAULT_DEPTH);
+        let old_root = tree.root();
+        let proof = tree.get_proof(1);
+        tree.update(1, new_qty);
+
+        let circuit = StateTransitionCircuit::new(
+            old_root,
+            0,
+            Fr::from(12345u64),
+            tree.root(),
+            0,
+            Fr::from(67890u64),
+            1,
+            old_qty,
+            new_qty,
+            amount,
+            op_type,
+            proof,
+            0,
+            Fr::from(99999u64),
+            10000,
+            0,
+            Fr::from(12345678u64),
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_u64_max_amount_rejected() {
+        // 100 + u64::MAX wraps to 99 in u64, but not in the field
+        assert!(!transition_near_limit(100, u64::MAX, OpType::Deposit));
+        // 100 - u64::MAX wraps to 101 in u64, but is negative in the field
+        assert!(!transition_near_limit(100, u64::MAX, OpType::Withdraw));
+    }
+
+    #[test]
+    fn test_quantity_range_boundary() {
+        let max = crate::range_check::MAX_RANGE_VALUE;
+
+        assert!(transition_near_limit(max - 1, 1, OpType::Deposit));
+        assert!(!transition_near_limit(max - 1, 2, OpType::Deposit));
+        assert!(transition_near_limit(max, max, OpType::Withdraw));
+        // Quantities above the range cannot be withdrawn from either
+        assert!(!transition_near_limit(u64::MAX, 1, OpType::Withdraw));
+    }
+
     #[test]
     fn test_underflow_attack_blocked() {
         // This test verifies that the range check prevents underflow attacks
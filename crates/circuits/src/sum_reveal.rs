@@ -0,0 +1,238 @@
+//! SumReveal Circuit for SMT-based inventory.
+//!
+//! Proves that an inventory holds exactly `revealed_sum` items in total across a
+//! publicly listed set of item_ids (e.g. for transparency reports), without
+//! revealing the per-item breakdown or anything about other items.
+//!
+//! Unlike `CategoryThresholdCircuit`, which proves `total >= min_total` over a
+//! category baked into the keys, this proves equality and takes the item_ids as
+//! public data: they are witnesses bound into the public hash, so one set of keys
+//! serves any list of the same length (fixed by [`SumRevealCircuit::empty`]).
+//! Items the inventory does not hold are proven absent and contribute 0; an id
+//! listed twice is counted twice.
+//!
+//! Public input: Poseidon(commitment, revealed_sum, item_id_0, item_id_1, ...)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::smt::{verify_quantity, MerkleProof, MerkleProofVar, SparseMerkleTree, DEFAULT_DEPTH};
+use crate::smt_commitment::{create_smt_commitment, create_smt_commitment_var};
+
+/// Compute the public input hash for SumReveal proof.
+pub fn compute_sum_reveal_hash(commitment: Fr, revealed_sum: u64, item_ids: &[u64]) -> Fr {
+    let mut inputs = vec![commitment, Fr::from(revealed_sum)];
+    inputs.extend(item_ids.iter().map(|&id| Fr::from(id)));
+    poseidon_hash_many(&inputs)
+}
+
+/// SumReveal Circuit.
+#[derive(Clone)]
+pub struct SumRevealCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Public data (bound via hash)
+    /// Item IDs whose quantities are summed
+    pub item_ids: Option<Vec<u64>>,
+    /// Claimed total quantity over `item_ids`
+    pub revealed_sum: Option<u64>,
+
+    // Commitment components (witnesses)
+    /// Inventory SMT root
+    pub inventory_root: Option<Fr>,
+    /// Current volume
+    pub current_volume: Option<u64>,
+    /// Blinding factor
+    pub blinding: Option<Fr>,
+
+    // Per-item witnesses
+    /// Quantity held of each listed item (0 if absent)
+    pub quantities: Option<Vec<u64>>,
+    /// Proof for each listed item in the inventory SMT
+    pub proofs: Option<Vec<MerkleProof<Fr>>>,
+}
+
+impl SumRevealCircuit {
+    /// Create an empty circuit for setup with room for `num_items` item_ids.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty(num_items: usize) -> Self {
+        let dummy_proof = MerkleProof::new(
+            vec![Fr::from(0u64); DEFAULT_DEPTH],
+            vec![false; DEFAULT_DEPTH],
+        );
+
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            item_ids: Some(vec![0; num_items]),
+            revealed_sum: Some(0),
+            inventory_root: Some(Fr::from(0u64)),
+            current_volume: Some(0),
+            blinding: Some(Fr::from(0u64)),
+            quantities: Some(vec![0; num_items]),
+            proofs: Some(vec![dummy_proof; num_items]),
+        }
+    }
+
+    /// Create a new circuit, reading each listed item's quantity and proof from `tree`.
+    pub fn new(
+        item_ids: Vec<u64>,
+        tree: &SparseMerkleTree,
+        current_volume: u64,
+        blinding: Fr,
+        revealed_sum: u64,
+    ) -> Self {
+        let inventory_root = tree.root();
+        let quantities = item_ids.iter().map(|&id| tree.get(id)).collect();
+        let proofs = item_ids.iter().map(|&id| tree.get_proof(id)).collect();
+
+        let commitment = create_smt_commitment(inventory_root, current_volume, blinding);
+        let public_hash = compute_sum_reveal_hash(commitment, revealed_sum, &item_ids);
+
+        Self {
+            public_hash: Some(public_hash),
+            item_ids: Some(item_ids),
+            revealed_sum: Some(revealed_sum),
+            inventory_root: Some(inventory_root),
+            current_volume: Some(current_volume),
+            blinding: Some(blinding),
+            quantities: Some(quantities),
+            proofs: Some(proofs),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for SumRevealCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate commitment witnesses ===
+        let root_var = FpVar::new_witness(cs.clone(), || {
+            self.inventory_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let volume_var = FpVar::new_witness(cs.clone(), || {
+            self.current_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding_var = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let revealed_sum_var = FpVar::new_witness(cs.clone(), || {
+            self.revealed_sum
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let item_ids = self.item_ids.as_ref().unwrap();
+        let quantities = self.quantities.as_ref().unwrap();
+        let proofs = self.proofs.as_ref().unwrap();
+        assert_eq!(quantities.len(), item_ids.len(), "one quantity per item");
+        assert_eq!(proofs.len(), item_ids.len(), "one proof per item");
+
+        // === Constraint 1: Look up each listed item and sum quantities ===
+        let mut total = FpVar::zero();
+        let mut item_id_vars = Vec::with_capacity(item_ids.len());
+        for ((&item_id, &quantity), proof) in item_ids.iter().zip(quantities).zip(proofs) {
+            let item_id_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(item_id)))?;
+            let quantity_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(quantity)))?;
+            let proof_var = MerkleProofVar::new_witness(cs.clone(), proof)?;
+
+            verify_quantity(cs.clone(), &root_var, &item_id_var, &quantity_var, &proof_var)?;
+            total += &quantity_var;
+            item_id_vars.push(item_id_var);
+        }
+
+        // === Constraint 2: total == revealed_sum ===
+        // Quantities are bound by their leaf hashes, so the sum cannot wrap
+        total.enforce_equal(&revealed_sum_var)?;
+
+        // === Constraint 3: Compute commitment using Poseidon ===
+        let commitment_var = create_smt_commitment_var(
+            cs.clone(),
+            &root_var,
+            &volume_var,
+            &blinding_var,
+        )?;
+
+        // === Constraint 4: Compute and verify public hash ===
+        let mut inputs = vec![commitment_var, revealed_sum_var];
+        inputs.extend(item_id_vars);
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Report on gold (1), gems (2) and relics (3); item 10 stays private.
+    fn reported_items() -> Vec<u64> {
+        vec![1, 2, 3]
+    }
+
+    fn inventory() -> SparseMerkleTree {
+        // 40 gold + 2 gems = 42; no relics
+        SparseMerkleTree::from_items(&[(1, 40), (2, 2), (10, 7)], DEFAULT_DEPTH)
+    }
+
+    fn is_satisfied(revealed_sum: u64) -> bool {
+        let circuit = SumRevealCircuit::new(
+            reported_items(),
+            &inventory(),
+            0,
+            Fr::from(12345u64),
+            revealed_sum,
+        );
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_sum_reveal_correct_sum() {
+        let circuit =
+            SumRevealCircuit::new(reported_items(), &inventory(), 0, Fr::from(12345u64), 42);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("SumReveal (3 items) constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_sum_reveal_understated_fails() {
+        assert!(!is_satisfied(41));
+    }
+
+    #[test]
+    fn test_sum_reveal_overstated_fails() {
+        assert!(!is_satisfied(43));
+    }
+
+    #[test]
+    fn test_sum_reveal_swapped_item_list_fails() {
+        // Summing the private item 10 instead of relics, under the published list
+        let tree = inventory();
+        let mut circuit = SumRevealCircuit::new(vec![1, 2, 10], &tree, 0, Fr::from(12345u64), 49);
+        let commitment = create_smt_commitment(tree.root(), 0, Fr::from(12345u64));
+        circuit.public_hash = Some(compute_sum_reveal_hash(commitment, 49, &reported_items()));
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
@@ -10,6 +10,7 @@ use crate::smt::{SparseMerkleTree, DEFAULT_DEPTH};
 use crate::state_transition::StateTransitionCircuit;
 use crate::item_exists_smt::ItemExistsSMTCircuit;
 use crate::capacity_smt::CapacitySMTCircuit;
+use crate::burn::{compute_burn_nullifier, BurnCircuit};
 
 /// Test full Groth16 proof generation and verification for StateTransitionCircuit (deposit)
 #[test]
@@ -249,3 +250,38 @@ fn test_invalid_proof_rejected() {
     let valid = Groth16::<Bn254>::verify(&vk, &wrong_public_inputs, &groth_proof).unwrap();
     assert!(!valid, "Invalid proof should be rejected");
 }
+
+/// Test full Groth16 proof for BurnCircuit
+#[test]
+fn test_burn_full_proof() {
+    let mut rng = thread_rng();
+
+    // Setup
+    let empty_circuit = BurnCircuit::empty();
+    let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(empty_circuit, &mut rng).unwrap();
+
+    // Burn 30 of item 1 (volume 10) from an inventory holding 100
+    let mut tree = SparseMerkleTree::from_items(&[(1, 100)], DEFAULT_DEPTH);
+    let circuit =
+        BurnCircuit::new(&mut tree, 1000, Fr::from(12345u64), Fr::from(67890u64), 1, 30, 10);
+    assert_eq!(tree.get(1), 70);
+
+    let public_hash = circuit.public_hash.unwrap();
+    let burn_nullifier = circuit.burn_nullifier.unwrap();
+
+    let old_commitment = circuit.update.as_ref().unwrap().old_state.commitment();
+    assert_eq!(burn_nullifier, compute_burn_nullifier(1, 30, old_commitment));
+
+    let groth_proof = Groth16::<Bn254>::prove(&pk, circuit, &mut rng).unwrap();
+
+    // Verify proof with both public inputs: public_hash, burn_nullifier
+    let public_inputs = vec![public_hash, burn_nullifier];
+    let valid = Groth16::<Bn254>::verify(&vk, &public_inputs, &groth_proof).unwrap();
+    assert!(valid, "Burn proof verification failed");
+
+    // The nullifier is bound: the same proof cannot be recorded under another one
+    let other_nullifier = compute_burn_nullifier(1, 31, old_commitment);
+    let public_inputs = vec![public_hash, other_nullifier];
+    let valid = Groth16::<Bn254>::verify(&vk, &public_inputs, &groth_proof).unwrap();
+    assert!(!valid, "Burn proof verified with a different nullifier");
+}
@@ -0,0 +1,218 @@
+//! Whitelist Circuit for SMT-based inventory.
+//!
+//! Proves that an inventory holds some item on a whitelist, without revealing
+//! which item or how many.
+//!
+//! The whitelist is itself a Sparse Merkle Tree mapping each allowed item_id to
+//! [`WHITELISTED`], so it can change without new circuits or keys: only its
+//! root is public. Build one with [`whitelist_tree`].
+//!
+//! Public input: Poseidon(commitment, whitelist_root)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::enforce_geq;
+use crate::smt::{verify_membership, MerkleProof, MerkleProofVar, SparseMerkleTree, DEFAULT_DEPTH};
+use crate::smt_commitment::{InventoryState, InventoryStateVar};
+
+/// Leaf value marking an item_id as allowed in a whitelist tree.
+pub const WHITELISTED: u64 = 1;
+
+/// Build a whitelist tree allowing `item_ids`.
+pub fn whitelist_tree(item_ids: &[u64]) -> SparseMerkleTree {
+    let items: Vec<(u64, u64)> = item_ids.iter().map(|&id| (id, WHITELISTED)).collect();
+    SparseMerkleTree::from_items(&items, DEFAULT_DEPTH)
+}
+
+/// Compute the public input hash for Whitelist proof.
+pub fn compute_whitelist_hash(commitment: Fr, whitelist_root: Fr) -> Fr {
+    poseidon_hash_many(&[commitment, whitelist_root])
+}
+
+/// Whitelist Circuit.
+#[derive(Clone)]
+pub struct WhitelistCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Public data (bound via hash)
+    /// Root of the whitelist tree (item_id -> WHITELISTED)
+    pub whitelist_root: Option<Fr>,
+
+    // Private witnesses
+    /// Inventory state the commitment opens to
+    pub state: Option<InventoryState>,
+    /// Held item that is on the whitelist
+    pub item_id: Option<u64>,
+    /// Quantity of item_id held
+    pub quantity: Option<u64>,
+    /// Proof for item_id in the inventory SMT
+    pub inventory_proof: Option<MerkleProof<Fr>>,
+    /// Proof for item_id in the whitelist SMT
+    pub whitelist_proof: Option<MerkleProof<Fr>>,
+}
+
+impl WhitelistCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        let dummy_proof = MerkleProof::new(
+            vec![Fr::from(0u64); DEFAULT_DEPTH],
+            vec![false; DEFAULT_DEPTH],
+        );
+
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            whitelist_root: Some(Fr::from(0u64)),
+            state: Some(InventoryState::new(Fr::from(0u64), 0, Fr::from(0u64))),
+            item_id: Some(0),
+            quantity: Some(0),
+            inventory_proof: Some(dummy_proof.clone()),
+            whitelist_proof: Some(dummy_proof),
+        }
+    }
+
+    /// Prove that `tree` holds `item_id`, which is on `whitelist`.
+    ///
+    /// The circuit is only satisfiable if the item is held and whitelisted.
+    pub fn new(
+        tree: &SparseMerkleTree,
+        current_volume: u64,
+        blinding: Fr,
+        item_id: u64,
+        whitelist: &SparseMerkleTree,
+    ) -> Self {
+        let state = InventoryState::new(tree.root(), current_volume, blinding);
+        let public_hash = compute_whitelist_hash(state.commitment(), whitelist.root());
+
+        Self {
+            public_hash: Some(public_hash),
+            whitelist_root: Some(whitelist.root()),
+            state: Some(state),
+            item_id: Some(item_id),
+            quantity: Some(tree.get(item_id)),
+            inventory_proof: Some(tree.get_proof(item_id)),
+            whitelist_proof: Some(whitelist.get_proof(item_id)),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for WhitelistCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate public data (bound via hash) ===
+        let whitelist_root_var = FpVar::new_witness(cs.clone(), || {
+            self.whitelist_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate private witnesses ===
+        let state = InventoryStateVar::new_witness(cs.clone(), self.state.as_ref().unwrap())?;
+        let item_id_var = FpVar::new_witness(cs.clone(), || {
+            self.item_id
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let quantity_var = FpVar::new_witness(cs.clone(), || {
+            self.quantity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let inventory_proof_var =
+            MerkleProofVar::new_witness(cs.clone(), self.inventory_proof.as_ref().unwrap())?;
+        let whitelist_proof_var =
+            MerkleProofVar::new_witness(cs.clone(), self.whitelist_proof.as_ref().unwrap())?;
+
+        // === Constraint 1: The inventory holds at least one of item_id ===
+        inventory_proof_var.enforce_index(&item_id_var)?;
+        verify_membership(
+            cs.clone(),
+            &state.inventory_root,
+            &item_id_var,
+            &quantity_var,
+            &inventory_proof_var,
+        )?;
+        enforce_geq(cs.clone(), &quantity_var, &FpVar::one())?;
+
+        // === Constraint 2: item_id is on the whitelist ===
+        whitelist_proof_var.enforce_index(&item_id_var)?;
+        verify_membership(
+            cs.clone(),
+            &whitelist_root_var,
+            &item_id_var,
+            &FpVar::constant(Fr::from(WHITELISTED)),
+            &whitelist_proof_var,
+        )?;
+
+        // === Constraint 3: Compute and verify public hash ===
+        let commitment_var = state.commitment(cs.clone())?;
+        let computed_hash =
+            poseidon_hash_many_var(cs.clone(), &[commitment_var, whitelist_root_var])?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn inventory() -> SparseMerkleTree {
+        SparseMerkleTree::from_items(&[(1, 100), (2, 50)], DEFAULT_DEPTH)
+    }
+
+    fn is_satisfied(circuit: WhitelistCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_whitelisted_item() {
+        let whitelist = whitelist_tree(&[2, 7, 9]);
+        let circuit = WhitelistCircuit::new(&inventory(), 150, Fr::from(1u64), 2, &whitelist);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        println!("Whitelist constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_non_whitelisted_item_fails() {
+        // Item 1 is held but not on the whitelist
+        let whitelist = whitelist_tree(&[2, 7, 9]);
+        let circuit = WhitelistCircuit::new(&inventory(), 150, Fr::from(1u64), 1, &whitelist);
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_whitelisted_but_not_held_fails() {
+        let whitelist = whitelist_tree(&[2, 7, 9]);
+        let circuit = WhitelistCircuit::new(&inventory(), 150, Fr::from(1u64), 7, &whitelist);
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_updated_whitelist() {
+        // Adding item 1 to the whitelist changes the root; no new circuit needed
+        let mut whitelist = whitelist_tree(&[2, 7, 9]);
+        whitelist.update(1, WHITELISTED);
+        let circuit = WhitelistCircuit::new(&inventory(), 150, Fr::from(1u64), 1, &whitelist);
+
+        assert!(is_satisfied(circuit));
+    }
+}
@@ -0,0 +1,349 @@
+//! WithdrawHidden Circuit for SMT-based inventory.
+//!
+//! Proves a valid withdrawal like `StateTransitionCircuit`, but without revealing
+//! which item was withdrawn. Instead of a public item_id, the item is shown to be
+//! a member of a public allowed set.
+//!
+//! The allowed set is a Sparse Merkle Tree mapping each allowed item_id to its
+//! per-unit volume, so the same membership proof also fixes the volume the
+//! withdrawal removes. Volumes in the set must be non-zero (a zero would be the
+//! empty leaf).
+//!
+//! The amount stays public. The inventory update is the state transition's:
+//! the item's leaf is replaced along its own path, the new quantity must not
+//! underflow, and the committed volume drops by `amount * item_volume`.
+//!
+//! Public input: Poseidon(old_commitment, new_commitment, allowed_root, amount,
+//!                        nonce, inventory_id)
+
+use ark_bn254::Fr;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+use crate::poseidon::{poseidon_hash_many, poseidon_hash_many_var};
+use crate::range_check::enforce_u32_range;
+use crate::smt::{
+    verify_and_update, verify_membership, MerkleProof, MerkleProofVar, SparseMerkleTree,
+    DEFAULT_DEPTH,
+};
+use crate::smt_commitment::{InventoryState, InventoryStateVar};
+
+/// Compute the public input hash for WithdrawHidden proof.
+pub fn compute_withdraw_hidden_hash(
+    old_commitment: Fr,
+    new_commitment: Fr,
+    allowed_root: Fr,
+    amount: u64,
+    nonce: u64,
+    inventory_id: Fr,
+) -> Fr {
+    let inputs = vec![
+        old_commitment,
+        new_commitment,
+        allowed_root,
+        Fr::from(amount),
+        Fr::from(nonce),
+        inventory_id,
+    ];
+    poseidon_hash_many(&inputs)
+}
+
+/// WithdrawHidden Circuit.
+#[derive(Clone)]
+pub struct WithdrawHiddenCircuit {
+    /// Public input hash
+    pub public_hash: Option<Fr>,
+
+    // Public data (bound via hash)
+    /// Root of the allowed set (item_id -> item_volume)
+    pub allowed_root: Option<Fr>,
+    /// Quantity withdrawn
+    pub amount: Option<u64>,
+    /// Replay protection (verified on-chain against inventory.nonce)
+    pub nonce: Option<u64>,
+    /// Cross-inventory protection (verified on-chain)
+    pub inventory_id: Option<Fr>,
+
+    // State witnesses
+    /// Inventory state before the withdrawal
+    pub old_state: Option<InventoryState>,
+    /// Inventory state after the withdrawal
+    pub new_state: Option<InventoryState>,
+
+    // Hidden item witnesses
+    /// Item withdrawn
+    pub item_id: Option<u64>,
+    /// Volume per unit of the item, as listed in the allowed set
+    pub item_volume: Option<u64>,
+    /// Quantity held before the withdrawal
+    pub old_quantity: Option<u64>,
+    /// Proof for the item in the inventory SMT
+    pub inventory_proof: Option<MerkleProof<Fr>>,
+    /// Proof for the item in the allowed set
+    pub allowed_proof: Option<MerkleProof<Fr>>,
+}
+
+impl WithdrawHiddenCircuit {
+    /// Create an empty circuit for setup.
+    /// Uses dummy values that produce valid constraint structure.
+    pub fn empty() -> Self {
+        let zero_state = InventoryState::new(Fr::from(0u64), 0, Fr::from(0u64));
+        let dummy_proof = MerkleProof::new(
+            vec![Fr::from(0u64); DEFAULT_DEPTH],
+            vec![false; DEFAULT_DEPTH],
+        );
+
+        Self {
+            public_hash: Some(Fr::from(0u64)),
+            allowed_root: Some(Fr::from(0u64)),
+            amount: Some(0),
+            nonce: Some(0),
+            inventory_id: Some(Fr::from(0u64)),
+            old_state: Some(zero_state.clone()),
+            new_state: Some(zero_state),
+            item_id: Some(0),
+            item_volume: Some(0),
+            old_quantity: Some(0),
+            inventory_proof: Some(dummy_proof.clone()),
+            allowed_proof: Some(dummy_proof),
+        }
+    }
+
+    /// Withdraw `amount` of `item_id` from `tree`, recording the witnesses.
+    ///
+    /// `tree` is updated in place so it reflects the new state afterwards.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        tree: &mut SparseMerkleTree,
+        old_volume: u64,
+        old_blinding: Fr,
+        new_blinding: Fr,
+        item_id: u64,
+        amount: u64,
+        allowed_set: &SparseMerkleTree,
+        nonce: u64,
+        inventory_id: Fr,
+    ) -> Self {
+        let old_state = InventoryState::new(tree.root(), old_volume, old_blinding);
+
+        let item_volume = allowed_set.get(item_id);
+        let old_quantity = tree.get(item_id);
+        let new_quantity = old_quantity
+            .checked_sub(amount)
+            .expect("Withdrawal would cause quantity underflow");
+        let new_volume = amount
+            .checked_mul(item_volume)
+            .and_then(|volume| old_volume.checked_sub(volume))
+            .expect("Withdrawal would cause volume underflow");
+
+        let inventory_proof = tree.get_proof(item_id);
+        tree.update(item_id, new_quantity);
+        let new_state = InventoryState::new(tree.root(), new_volume, new_blinding);
+
+        let allowed_root = allowed_set.root();
+        let public_hash = compute_withdraw_hidden_hash(
+            old_state.commitment(),
+            new_state.commitment(),
+            allowed_root,
+            amount,
+            nonce,
+            inventory_id,
+        );
+
+        Self {
+            public_hash: Some(public_hash),
+            allowed_root: Some(allowed_root),
+            amount: Some(amount),
+            nonce: Some(nonce),
+            inventory_id: Some(inventory_id),
+            old_state: Some(old_state),
+            new_state: Some(new_state),
+            item_id: Some(item_id),
+            item_volume: Some(item_volume),
+            old_quantity: Some(old_quantity),
+            inventory_proof: Some(inventory_proof),
+            allowed_proof: Some(allowed_set.get_proof(item_id)),
+        }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for WithdrawHiddenCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        // === Allocate public input ===
+        let public_hash_var = FpVar::new_input(cs.clone(), || {
+            self.public_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate public data witnesses (bound via hash) ===
+        let allowed_root_var = FpVar::new_witness(cs.clone(), || {
+            self.allowed_root.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let amount_var = FpVar::new_witness(cs.clone(), || {
+            self.amount
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let nonce_var = FpVar::new_witness(cs.clone(), || {
+            self.nonce
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let inventory_id_var = FpVar::new_witness(cs.clone(), || {
+            self.inventory_id.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // === Allocate state witnesses ===
+        let old_state =
+            InventoryStateVar::new_witness(cs.clone(), self.old_state.as_ref().unwrap())?;
+        let new_state =
+            InventoryStateVar::new_witness(cs.clone(), self.new_state.as_ref().unwrap())?;
+
+        // === Allocate hidden item witnesses ===
+        let item_id_var = FpVar::new_witness(cs.clone(), || {
+            self.item_id
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let item_volume_var = FpVar::new_witness(cs.clone(), || {
+            self.item_volume
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let old_quantity_var = FpVar::new_witness(cs.clone(), || {
+            self.old_quantity
+                .map(Fr::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let inventory_proof_var =
+            MerkleProofVar::new_witness(cs.clone(), self.inventory_proof.as_ref().unwrap())?;
+        let allowed_proof_var =
+            MerkleProofVar::new_witness(cs.clone(), self.allowed_proof.as_ref().unwrap())?;
+
+        // === Constraint 1: Item is in the allowed set, at its listed volume ===
+        // A zero volume would match an empty leaf instead of a listed item
+        item_volume_var.is_zero()?.enforce_equal(&Boolean::FALSE)?;
+        verify_membership(
+            cs.clone(),
+            &allowed_root_var,
+            &item_id_var,
+            &item_volume_var,
+            &allowed_proof_var,
+        )?;
+
+        // === Constraint 2: Quantity decreases by amount without underflow ===
+        let new_quantity_var = &old_quantity_var - &amount_var;
+        enforce_u32_range(cs.clone(), &new_quantity_var)?;
+
+        // === Constraint 3: Only the item's own leaf changes ===
+        let computed_new_root = verify_and_update(
+            cs.clone(),
+            &old_state.inventory_root,
+            &item_id_var,
+            &old_quantity_var,
+            &new_quantity_var,
+            &inventory_proof_var,
+        )?;
+        computed_new_root.enforce_equal(&new_state.inventory_root)?;
+
+        // === Constraint 4: Volume decreases by amount * item_volume ===
+        let expected_volume = &old_state.current_volume - &(&amount_var * &item_volume_var);
+        expected_volume.enforce_equal(&new_state.current_volume)?;
+        enforce_u32_range(cs.clone(), &new_state.current_volume)?;
+
+        // === Constraint 5: Compute and verify public hash ===
+        let old_commitment = old_state.commitment(cs.clone())?;
+        let new_commitment = new_state.commitment(cs.clone())?;
+
+        let inputs = vec![
+            old_commitment,
+            new_commitment,
+            allowed_root_var,
+            amount_var,
+            nonce_var,
+            inventory_id_var,
+        ];
+        let computed_hash = poseidon_hash_many_var(cs.clone(), &inputs)?;
+
+        computed_hash.enforce_equal(&public_hash_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    /// Potions (1, volume 2) and arrows (2, volume 1) may be withdrawn; relics (3) may not.
+    fn allowed_set() -> SparseMerkleTree {
+        SparseMerkleTree::from_items(&[(1, 2), (2, 1)], DEFAULT_DEPTH)
+    }
+
+    fn inventory() -> SparseMerkleTree {
+        // 10 potions, 50 arrows, 1 relic (volume 30): 20 + 50 + 30
+        SparseMerkleTree::from_items(&[(1, 10), (2, 50), (3, 1)], DEFAULT_DEPTH)
+    }
+
+    fn withdraw(item_id: u64, amount: u64, allowed: &SparseMerkleTree) -> WithdrawHiddenCircuit {
+        WithdrawHiddenCircuit::new(
+            &mut inventory(),
+            100,
+            Fr::from(12345u64),
+            Fr::from(67890u64),
+            item_id,
+            amount,
+            allowed,
+            0,
+            Fr::from(12345678u64),
+        )
+    }
+
+    fn is_satisfied(circuit: WithdrawHiddenCircuit) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_hidden_withdraw() {
+        let circuit = withdraw(1, 4, &allowed_set());
+        assert_eq!(circuit.new_state.as_ref().unwrap().current_volume, 92);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(cs.num_instance_variables(), 2);
+        println!("WithdrawHidden constraints: {}", cs.num_constraints());
+    }
+
+    #[test]
+    fn test_item_not_in_allowed_set_fails() {
+        // Relics have no entry in the allowed set, so claim a volume of 30 for them
+        let mut circuit = withdraw(3, 1, &allowed_set());
+        circuit.item_volume = Some(30);
+        let new_state = circuit.new_state.as_mut().unwrap();
+        new_state.current_volume = 70;
+        circuit.public_hash = Some(compute_withdraw_hidden_hash(
+            circuit.old_state.as_ref().unwrap().commitment(),
+            new_state.commitment(),
+            circuit.allowed_root.unwrap(),
+            1,
+            0,
+            Fr::from(12345678u64),
+        ));
+
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_understated_volume_fails() {
+        // Arrows are listed at volume 1; claiming 0 would leave the volume unchanged
+        let mut circuit = withdraw(2, 5, &allowed_set());
+        circuit.item_volume = Some(0);
+
+        assert!(!is_satisfied(circuit));
+    }
+}
@@ -0,0 +1,225 @@
+//! Server configuration read from environment variables.
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Default maximum request body size (1 MiB)
+pub const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Default limit on a single proof generation, in seconds
+pub const DEFAULT_PROOF_TIMEOUT_SECS: u64 = 60;
+
+/// Default limit on the number of requests in one `/api/prove/batch` call
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 16;
+
+/// Default epoch length, in seconds
+pub const DEFAULT_EPOCH_SECS: u64 = 3600;
+
+/// Default number of past epochs a proof may be tagged with and still be fresh
+pub const DEFAULT_EPOCH_WINDOW: u64 = 1;
+
+/// Runtime configuration for the proof server
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Maximum accepted request body size in bytes (`MAX_BODY_BYTES`)
+    pub max_body_bytes: usize,
+    /// Maximum time to wait for a proof before returning 504 (`PROOF_TIMEOUT_SECS`)
+    pub proof_timeout_secs: u64,
+    /// Maximum number of requests in one batch prove call (`MAX_BATCH_SIZE`)
+    pub max_batch_size: usize,
+    /// Length of an epoch in seconds (`EPOCH_SECS`)
+    pub epoch_secs: u64,
+    /// How many epochs old a proof may be before it is stale (`EPOCH_WINDOW`)
+    pub epoch_window: u64,
+    /// Bearer token for `/admin` endpoints (`ADMIN_TOKEN`); unset disables them
+    pub admin_token: Option<String>,
+    /// Directory the registry and spent nullifiers are saved in (`STORAGE_DIR`);
+    /// unset keeps them in memory only
+    pub storage_dir: Option<PathBuf>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            proof_timeout_secs: DEFAULT_PROOF_TIMEOUT_SECS,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            epoch_secs: DEFAULT_EPOCH_SECS,
+            epoch_window: DEFAULT_EPOCH_WINDOW,
+            admin_token: None,
+            storage_dir: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Load configuration from the environment, falling back to defaults.
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Load configuration using the given variable lookup.
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let defaults = Self::default();
+
+        Self {
+            max_body_bytes: parse_var(&lookup, "MAX_BODY_BYTES").unwrap_or(defaults.max_body_bytes),
+            proof_timeout_secs: parse_var(&lookup, "PROOF_TIMEOUT_SECS")
+                .unwrap_or(defaults.proof_timeout_secs),
+            max_batch_size: parse_var(&lookup, "MAX_BATCH_SIZE")
+                .unwrap_or(defaults.max_batch_size),
+            epoch_secs: parse_var(&lookup, "EPOCH_SECS")
+                .filter(|&secs| secs > 0)
+                .unwrap_or(defaults.epoch_secs),
+            epoch_window: parse_var(&lookup, "EPOCH_WINDOW").unwrap_or(defaults.epoch_window),
+            admin_token: lookup("ADMIN_TOKEN").filter(|token| !token.trim().is_empty()),
+            storage_dir: lookup("STORAGE_DIR")
+                .filter(|dir| !dir.trim().is_empty())
+                .map(PathBuf::from),
+        }
+    }
+
+    /// Proof generation timeout as a `Duration`.
+    pub fn proof_timeout(&self) -> Duration {
+        Duration::from_secs(self.proof_timeout_secs)
+    }
+
+    /// Freshness policy for epoch-tagged proofs.
+    pub fn epoch_policy(&self) -> EpochPolicy {
+        EpochPolicy {
+            epoch_secs: self.epoch_secs,
+            window: self.epoch_window,
+        }
+    }
+}
+
+/// Which epochs a proof may be tagged with to count as fresh.
+///
+/// Epochs are counted in `epoch_secs` steps since the Unix epoch. A proof is
+/// fresh if its epoch is the current one or at most `window` epochs older.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochPolicy {
+    pub epoch_secs: u64,
+    pub window: u64,
+}
+
+impl EpochPolicy {
+    /// The epoch the system clock is currently in.
+    pub fn current_epoch(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        now / self.epoch_secs
+    }
+
+    /// Whether `epoch` is fresh at `current_epoch`. Future epochs are not.
+    pub fn is_fresh(&self, epoch: u64, current_epoch: u64) -> bool {
+        epoch <= current_epoch && current_epoch - epoch <= self.window
+    }
+}
+
+/// Parse a variable, warning and returning None if it is set but invalid.
+fn parse_var<T: std::str::FromStr>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    key: &str,
+) -> Option<T> {
+    let value = lookup(key)?;
+    match value.trim().parse() {
+        Ok(v) => Some(v),
+        Err(_) => {
+            println!("Ignoring invalid {}={:?}, using default", key, value);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_when_unset() {
+        let config = ServerConfig::from_lookup(|_| None);
+        assert_eq!(config.max_body_bytes, DEFAULT_MAX_BODY_BYTES);
+        assert_eq!(config.proof_timeout_secs, DEFAULT_PROOF_TIMEOUT_SECS);
+        assert_eq!(config.max_batch_size, DEFAULT_MAX_BATCH_SIZE);
+        assert_eq!(config.epoch_secs, DEFAULT_EPOCH_SECS);
+        assert_eq!(config.epoch_window, DEFAULT_EPOCH_WINDOW);
+        assert_eq!(config.admin_token, None);
+        assert_eq!(config.storage_dir, None);
+    }
+
+    #[test]
+    fn test_max_body_bytes_from_env() {
+        let config = ServerConfig::from_lookup(|key| {
+            (key == "MAX_BODY_BYTES").then(|| "4096".to_string())
+        });
+        assert_eq!(config.max_body_bytes, 4096);
+    }
+
+    #[test]
+    fn test_proof_timeout_from_env() {
+        let config = ServerConfig::from_lookup(|key| {
+            (key == "PROOF_TIMEOUT_SECS").then(|| "5".to_string())
+        });
+        assert_eq!(config.proof_timeout(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_max_batch_size_from_env() {
+        let config = ServerConfig::from_lookup(|key| {
+            (key == "MAX_BATCH_SIZE").then(|| "4".to_string())
+        });
+        assert_eq!(config.max_batch_size, 4);
+    }
+
+    #[test]
+    fn test_invalid_value_falls_back_to_default() {
+        let config = ServerConfig::from_lookup(|_| Some("lots".to_string()));
+        assert_eq!(config.max_body_bytes, DEFAULT_MAX_BODY_BYTES);
+        assert_eq!(config.proof_timeout_secs, DEFAULT_PROOF_TIMEOUT_SECS);
+    }
+
+    #[test]
+    fn test_zero_epoch_secs_falls_back_to_default() {
+        let config = ServerConfig::from_lookup(|key| {
+            (key == "EPOCH_SECS").then(|| "0".to_string())
+        });
+        assert_eq!(config.epoch_secs, DEFAULT_EPOCH_SECS);
+    }
+
+    #[test]
+    fn test_epoch_window() {
+        let config = ServerConfig::from_lookup(|key| {
+            (key == "EPOCH_WINDOW").then(|| "2".to_string())
+        });
+        let policy = config.epoch_policy();
+
+        assert!(policy.is_fresh(10, 10));
+        assert!(policy.is_fresh(8, 10));
+        assert!(!policy.is_fresh(7, 10));
+        assert!(!policy.is_fresh(11, 10));
+    }
+
+    #[test]
+    fn test_admin_token() {
+        let config = ServerConfig::from_lookup(|key| {
+            (key == "ADMIN_TOKEN").then(|| "s3cret".to_string())
+        });
+        assert_eq!(config.admin_token.as_deref(), Some("s3cret"));
+
+        let config = ServerConfig::from_lookup(|key| {
+            (key == "ADMIN_TOKEN").then(|| " ".to_string())
+        });
+        assert_eq!(config.admin_token, None);
+    }
+
+    #[test]
+    fn test_storage_dir() {
+        let config = ServerConfig::from_lookup(|key| {
+            (key == "STORAGE_DIR").then(|| "data".to_string())
+        });
+        assert_eq!(config.storage_dir, Some(PathBuf::from("data")));
+    }
+}
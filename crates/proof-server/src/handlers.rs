@@ -1,37 +1,90 @@
 //! HTTP request handlers for SMT-based proof generation.
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
-use ark_bn254::Fr;
-use ark_ff::PrimeField;
+use ark_bn254::{Bn254, Fr};
+use ark_ff::{PrimeField, Zero};
+use ark_groth16::Proof;
 use ark_serialize::CanonicalSerialize;
-use ark_std::rand::Rng;
+use ark_std::rand::{rngs::OsRng, Rng};
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
 use inventory_circuits::{
-    signal::OpType,
+    constraint_breakdown,
+    signal::{OpType, SignalInputs},
     smt::{SparseMerkleTree, DEFAULT_DEPTH},
-    smt_commitment::create_smt_commitment,
+    smt_commitment::try_create_smt_commitment,
+    CapacitySMTCircuit, CircuitMeta, ItemExistsSMTCircuit, StateTransitionCircuit,
+};
+use inventory_prover::{
+    prove,
+    setup::{CircuitKeys, TypedVerifyingKey},
+    verify, InventoryState, ItemExistsPublicInputs, ProofWithInputs, ProverError,
+    StateTransitionPublicInputs,
 };
-use inventory_prover::{prove, InventoryState};
 
+use crate::config::EpochPolicy;
+use crate::health::ServerHealth;
+use crate::metrics::Metrics;
+use crate::storage::{StorageBackend, StorageError};
 use crate::AppState;
 
 /// Health check response
 #[derive(Serialize)]
 pub struct HealthResponse {
+    /// "ok", "starting" or "degraded"
     pub status: &'static str,
+    /// Why the server is degraded (omitted otherwise)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+}
+
+/// Report server health; anything but `Healthy` is 503 so load balancers route away.
+pub async fn health(State(state): State<Arc<RwLock<AppState>>>) -> Response {
+    health_response(&state.read().await.health)
+}
+
+fn health_response(health: &ServerHealth) -> Response {
+    let (status, reasons) = match health {
+        ServerHealth::Healthy => ("ok", Vec::new()),
+        ServerHealth::Starting => ("starting", Vec::new()),
+        ServerHealth::Degraded { reasons } => ("degraded", reasons.clone()),
+    };
+    let code = if health.is_healthy() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (code, Json(HealthResponse { status, reasons })).into_response()
 }
 
-pub async fn health() -> Json<HealthResponse> {
-    Json(HealthResponse { status: "ok" })
+/// Export proof metrics and storage sizes in the Prometheus text format.
+///
+/// Storage sizes are read at scrape time; they are left out if the backend
+/// cannot be read.
+pub async fn metrics(State(state): State<Arc<RwLock<AppState>>>) -> impl IntoResponse {
+    let (metrics, storage) = {
+        let app_state = state.read().await;
+        (app_state.metrics.clone(), app_state.storage.clone())
+    };
+
+    let mut text = metrics.render();
+    if let (Ok(leaves), Ok(nullifiers)) =
+        (storage.leaf_count().await, storage.nullifier_count().await)
+    {
+        text.push_str(&crate::metrics::render_storage(leaves, nullifiers));
+    }
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], text)
 }
 
 /// Item in inventory for API requests
@@ -70,6 +123,7 @@ fn parse_fr(hex: &str) -> Result<Fr, String> {
 
 /// Parse hex string to Fr (big-endian, for Sui object IDs)
 /// Sui object IDs are big-endian, so we reverse bytes before interpreting as LE field element
+#[allow(dead_code)]
 fn parse_fr_be(hex: &str) -> Result<Fr, String> {
     let bytes = hex::decode(hex.trim_start_matches("0x"))
         .map_err(|e| format!("Invalid hex: {}", e))?;
@@ -93,6 +147,7 @@ fn serialize_fr(f: &Fr) -> String {
 }
 
 /// Serialize Fr to hex string (big-endian, for Sui object IDs)
+#[allow(dead_code)]
 fn serialize_fr_be(f: &Fr) -> String {
     let mut bytes = Vec::new();
     f.serialize_compressed(&mut bytes).unwrap();
@@ -107,12 +162,88 @@ pub struct ProofResponse {
     pub public_inputs: Vec<String>,
 }
 
+impl ProofResponse {
+    fn new(proof: &ProofWithInputs) -> Result<Self, String> {
+        Ok(Self {
+            proof: serialize_proof_hex(proof)?,
+            public_inputs: proof.public_inputs.iter().map(serialize_fr).collect(),
+        })
+    }
+}
+
+/// Hex-encode a proof for a response.
+fn serialize_proof_hex(proof: &ProofWithInputs) -> Result<String, String> {
+    let bytes = proof.serialize_proof().map_err(|e| e.to_string())?;
+    Ok(format!("0x{}", hex::encode(bytes)))
+}
+
+/// 200 with the proof, 400 if the request could not be proven.
+fn proof_response<T: Serialize>(result: Result<Result<T, String>, Response>) -> Response {
+    match result {
+        Err(response) => response,
+        Ok(Ok(proof)) => (StatusCode::OK, Json(proof)).into_response(),
+        Ok(Err(error)) => (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+    }
+}
+
 /// Error response
 #[derive(Serialize)]
 pub struct ErrorResponse {
     pub error: String,
 }
 
+/// Run a blocking proving call on the blocking thread pool, giving up after `timeout`.
+///
+/// Groth16 proving cannot be interrupted, so a timed-out task keeps running to
+/// completion in the background; only the request is released (504 Gateway Timeout).
+async fn run_prover<T, F>(timeout: Duration, prove: F) -> Result<T, Response>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match tokio::time::timeout(timeout, tokio::task::spawn_blocking(prove)).await {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Proof generation failed: {}", e),
+            }),
+        )
+            .into_response()),
+        Err(_) => Err((
+            StatusCode::GATEWAY_TIMEOUT,
+            Json(ErrorResponse {
+                error: format!("Proof generation timed out after {}s", timeout.as_secs_f64()),
+            }),
+        )
+            .into_response()),
+    }
+}
+
+/// [`run_prover`] for a `kind` proof, counting a timeout as a failed proof in `metrics`.
+async fn run_timed_prover<T, F>(
+    metrics: &Metrics,
+    kind: ProofKind,
+    timeout: Duration,
+    prove: F,
+) -> Result<T, Response>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let result = run_prover(timeout, prove).await;
+    if matches!(&result, Err(response) if response.status() == StatusCode::GATEWAY_TIMEOUT) {
+        metrics.record_timeout(kind.label());
+    }
+    result
+}
+
+/// Clone out the keys, timeout and metrics so the state lock is not held while proving.
+async fn prover_context(state: &RwLock<AppState>) -> (Arc<CircuitKeys>, Duration, Arc<Metrics>) {
+    let app_state = state.read().await;
+    (app_state.keys.clone(), app_state.proof_timeout, app_state.metrics.clone())
+}
+
 // ============ State Transition (Deposit/Withdraw) ============
 
 #[derive(Deserialize)]
@@ -139,8 +270,22 @@ pub struct StateTransitionRequest {
     pub nonce: u64,
     /// Inventory object ID as hex string (for cross-inventory protection)
     pub inventory_id: String,
-    /// Operation type: "deposit" or "withdraw"
+    /// Operation type: "deposit" or "withdraw" (implied in /api/prove/batch)
+    #[serde(default)]
     pub op_type: String,
+    /// Epoch to tag the proof with (adds a fifth public input; see /api/verify/state-transition)
+    #[serde(default)]
+    pub epoch: Option<u64>,
+}
+
+impl StateTransitionRequest {
+    /// Which circuit's keys prove this request
+    fn kind(&self) -> ProofKind {
+        match self.epoch {
+            Some(_) => ProofKind::StateTransitionEpoch,
+            None => ProofKind::StateTransition,
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -160,79 +305,502 @@ pub struct StateTransitionResponse {
 pub async fn prove_state_transition(
     State(state): State<Arc<RwLock<AppState>>>,
     Json(req): Json<StateTransitionRequest>,
-) -> impl IntoResponse {
-    let old_blinding = match parse_fr(&req.old_blinding) {
-        Ok(b) => b,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
+) -> Response {
+    let (keys, timeout, metrics) = prover_context(&state).await;
+    let queued_at = Instant::now();
+    let kind = req.kind();
+    let prover_metrics = metrics.clone();
+    let result = run_timed_prover(&metrics, kind, timeout, move || {
+        let op_type = match req.op_type.to_lowercase().as_str() {
+            "deposit" => OpType::Deposit,
+            "withdraw" => OpType::Withdraw,
+            _ => return Err("op_type must be 'deposit' or 'withdraw'".to_string()),
+        };
+        state_transition_proof(&keys, &prover_metrics, &req, op_type, queued_at)
+    })
+    .await;
+
+    proof_response(result)
+}
+
+/// Parse a state transition request and prove it.
+fn state_transition_proof(
+    keys: &CircuitKeys,
+    metrics: &Metrics,
+    req: &StateTransitionRequest,
+    op_type: OpType,
+    queued_at: Instant,
+) -> Result<StateTransitionResponse, String> {
+    let old_blinding = parse_fr(&req.old_blinding)?;
+    let new_blinding = parse_fr(&req.new_blinding)?;
+    let registry_root = parse_fr(&req.registry_root)?;
+    // Parse inventory_id - interpreted as LE field element (with modular reduction if needed)
+    let inventory_id = parse_fr(&req.inventory_id)?;
+
+    let inventory_state = parse_inventory_state(&req.inventory, req.current_volume, old_blinding);
+
+    let result = metrics
+        .time_proof(req.kind().label(), queued_at, || match req.epoch {
+            None => prove::prove_state_transition(
+                &keys.state_transition.proving_key,
+                &inventory_state,
+                new_blinding,
+                req.item_id,
+                req.amount,
+                req.item_volume,
+                registry_root,
+                req.max_capacity,
+                req.nonce,
+                inventory_id,
+                op_type,
+            ),
+            Some(epoch) => prove::prove_state_transition_at_epoch(
+                &keys.state_transition_epoch.proving_key,
+                &inventory_state,
+                new_blinding,
+                req.item_id,
+                req.amount,
+                req.item_volume,
+                registry_root,
+                req.max_capacity,
+                req.nonce,
+                inventory_id,
+                op_type,
+                epoch,
+            ),
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(StateTransitionResponse {
+        proof: serialize_proof_hex(&result.proof)?,
+        public_inputs: result.proof.public_inputs.iter().map(serialize_fr).collect(),
+        new_commitment: serialize_fr(&result.new_commitment),
+        new_volume: result.new_state.current_volume,
+        nonce: result.nonce,
+        // Return serialized field element bytes - this matches what the circuit used
+        // after modular reduction (for object IDs exceeding BN254 field order)
+        inventory_id: serialize_fr(&inventory_id),
+        registry_root: serialize_fr(&result.registry_root),
+    })
+}
+
+// ============ Verify State Transition ============
+
+#[derive(Deserialize)]
+pub struct VerifyStateTransitionRequest {
+    /// Proof as returned by /api/prove/state-transition with an epoch
+    pub proof: String,
+    /// Public inputs as returned with the proof; the last one is the epoch
+    pub public_inputs: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct VerifyResponse {
+    pub valid: bool,
+    /// Epoch the proof is tagged with
+    pub epoch: u64,
+}
+
+/// Verify an epoch-tagged state transition proof.
+///
+/// Proofs whose epoch is outside the server's freshness window (including
+/// future epochs) are rejected with 409 Conflict before the proof is checked.
+pub async fn verify_state_transition(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Json(req): Json<VerifyStateTransitionRequest>,
+) -> Response {
+    let (keys, policy, metrics) = {
+        let app_state = state.read().await;
+        (app_state.keys.clone(), app_state.epoch_policy, app_state.metrics.clone())
     };
 
-    let new_blinding = match parse_fr(&req.new_blinding) {
-        Ok(b) => b,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
+    verify_at_epoch(
+        &keys.state_transition_epoch.verifying_key,
+        &req,
+        &policy,
+        policy.current_epoch(),
+        &metrics,
+    )
+}
+
+fn verify_at_epoch(
+    vk: &TypedVerifyingKey<StateTransitionCircuit>,
+    req: &VerifyStateTransitionRequest,
+    policy: &EpochPolicy,
+    current_epoch: u64,
+    metrics: &Metrics,
+) -> Response {
+    let bad_request =
+        |error: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response();
+
+    let ProofWithInputs { proof, public_inputs } =
+        match parse_proof_with_inputs(&req.proof, &req.public_inputs) {
+            Ok(proof) => proof,
+            Err(e) => return bad_request(e),
+        };
+
+    let inputs = match StateTransitionPublicInputs::from_vec(&public_inputs) {
+        Ok(inputs) => inputs,
+        Err(e) => return bad_request(e.to_string()),
+    };
+    let Some(epoch) = inputs.epoch else {
+        return bad_request("Proof is not tagged with an epoch".to_string());
     };
 
-    let registry_root = match parse_fr(&req.registry_root) {
-        Ok(r) => r,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
+    if let Err(error) = check_fresh(policy, epoch, current_epoch) {
+        return (StatusCode::CONFLICT, Json(ErrorResponse { error })).into_response();
+    }
+
+    match verify::verify_state_transition(vk, &proof, &inputs) {
+        Ok(valid) => {
+            metrics.record_verification(ProofKind::StateTransitionEpoch.label(), valid);
+            (StatusCode::OK, Json(VerifyResponse { valid, epoch })).into_response()
+        }
+        Err(e) => bad_request(e.to_string()),
+    }
+}
+
+/// Parse a hex-encoded proof and its hex-encoded public inputs.
+fn parse_proof_with_inputs(
+    proof: &str,
+    public_inputs: &[String],
+) -> Result<ProofWithInputs, String> {
+    let proof = parse_proof(proof)?;
+    let public_inputs = public_inputs.iter().map(|s| parse_fr(s)).collect::<Result<_, _>>()?;
+
+    Ok(ProofWithInputs { proof, public_inputs })
+}
+
+/// Parse a hex-encoded proof as written by `serialize_proof`.
+fn parse_proof(proof: &str) -> Result<Proof<Bn254>, String> {
+    let bytes = hex::decode(proof.trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid hex: {}", e))?;
+    inventory_prover::deserialize_proof(&bytes).map_err(|e| e.to_string())
+}
+
+/// The epoch an epoch-tagged state transition proof was made for (its last public input).
+fn proof_epoch(public_inputs: &[Fr]) -> Result<u64, String> {
+    // The circuit range checks the epoch to 64 bits, so a larger value cannot verify
+    match public_inputs.last().map(|epoch| epoch.into_bigint().0) {
+        Some([epoch, 0, 0, 0]) => Ok(epoch),
+        _ => Err("Last public input must be a u64 epoch".to_string()),
+    }
+}
+
+fn check_fresh(policy: &EpochPolicy, epoch: u64, current_epoch: u64) -> Result<(), String> {
+    if policy.is_fresh(epoch, current_epoch) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Stale epoch: proof is for epoch {}, current epoch is {} (window {})",
+            epoch, current_epoch, policy.window
+        ))
+    }
+}
+
+// ============ Verify by Statement ============
+
+#[derive(Deserialize)]
+pub struct VerifyItemExistsRequest {
+    /// Proof as returned by /api/prove/item-exists
+    pub proof: String,
+    /// Inventory commitment the proof is about
+    pub commitment: String,
+    pub item_id: u64,
+    pub min_quantity: u64,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyTransitionRequest {
+    /// Proof as returned by /api/prove/state-transition without an epoch
+    pub proof: String,
+    pub old_commitment: String,
+    pub new_commitment: String,
+    pub registry_root: String,
+    pub max_capacity: u64,
+    pub item_id: u64,
+    pub amount: u64,
+    pub nonce: u64,
+    pub inventory_id: String,
+}
+
+#[derive(Serialize)]
+pub struct ValidityResponse {
+    pub valid: bool,
+}
+
+/// Verify an ItemExists proof against the statement it should prove.
+///
+/// Malformed proofs and field elements are 400 Bad Request; a well-formed
+/// proof of a different statement is 200 with `valid: false`.
+pub async fn verify_item_exists(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Json(req): Json<VerifyItemExistsRequest>,
+) -> Response {
+    let parsed = parse_proof(&req.proof).and_then(|proof| {
+        let commitment = parse_fr(&req.commitment)?;
+        Ok((proof, commitment))
+    });
+    let (proof, commitment) = match parsed {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response()
+        }
+    };
+    let inputs = ItemExistsPublicInputs {
+        commitment,
+        item_id: req.item_id,
+        min_quantity: req.min_quantity,
     };
 
-    // Parse inventory_id - interpreted as LE field element (with modular reduction if needed)
-    let inventory_id = match parse_fr(&req.inventory_id) {
-        Ok(id) => id,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
+    let (keys, metrics) = {
+        let app_state = state.read().await;
+        (app_state.keys.clone(), app_state.metrics.clone())
+    };
+    let result = verify::verify_item_exists(&keys.item_exists.verifying_key, &proof, &inputs);
+    validity_response(result, ProofKind::ItemExists, &metrics)
+}
+
+/// Verify a deposit proof made without an epoch.
+pub async fn verify_deposit(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Json(req): Json<VerifyTransitionRequest>,
+) -> Response {
+    verify_transition(&state, &req, OpType::Deposit).await
+}
+
+/// Verify a withdrawal proof made without an epoch.
+pub async fn verify_withdraw(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Json(req): Json<VerifyTransitionRequest>,
+) -> Response {
+    verify_transition(&state, &req, OpType::Withdraw).await
+}
+
+/// Rebuild the signal of a state transition and verify the proof against it.
+///
+/// The operation type is part of the signal, so a deposit proof does not
+/// verify as a withdrawal.
+async fn verify_transition(
+    state: &RwLock<AppState>,
+    req: &VerifyTransitionRequest,
+    op_type: OpType,
+) -> Response {
+    let parsed = parse_proof(&req.proof).and_then(|proof| {
+        let signal = SignalInputs {
+            old_commitment: parse_fr(&req.old_commitment)?,
+            new_commitment: parse_fr(&req.new_commitment)?,
+            registry_root: parse_fr(&req.registry_root)?,
+            max_capacity: req.max_capacity,
+            item_id: req.item_id,
+            amount: req.amount,
+            op_type,
+            nonce: req.nonce,
+            inventory_id: parse_fr(&req.inventory_id)?,
+        };
+        Ok((proof, signal))
+    });
+    let (proof, signal) = match parsed {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response()
+        }
     };
+    let inputs = StateTransitionPublicInputs::from_signal(&signal, None);
 
-    let op_type = match req.op_type.to_lowercase().as_str() {
-        "deposit" => OpType::Deposit,
-        "withdraw" => OpType::Withdraw,
-        _ => return (StatusCode::BAD_REQUEST, Json(ErrorResponse {
-            error: "op_type must be 'deposit' or 'withdraw'".to_string()
-        })).into_response(),
+    let (keys, metrics) = {
+        let app_state = state.read().await;
+        (app_state.keys.clone(), app_state.metrics.clone())
     };
+    let vk = &keys.state_transition.verifying_key;
+    let result = verify::verify_state_transition(vk, &proof, &inputs);
+    validity_response(result, ProofKind::StateTransition, &metrics)
+}
 
-    let inventory_state = parse_inventory_state(&req.inventory, req.current_volume, old_blinding);
+fn validity_response(
+    result: Result<bool, ProverError>,
+    kind: ProofKind,
+    metrics: &Metrics,
+) -> Response {
+    match result {
+        Ok(valid) => {
+            metrics.record_verification(kind.label(), valid);
+            (StatusCode::OK, Json(ValidityResponse { valid })).into_response()
+        }
+        Err(e) => {
+            (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e.to_string() })).into_response()
+        }
+    }
+}
 
-    let app_state = state.read().await;
+// ============ Bulk Verify ============
 
-    match prove::prove_state_transition(
-        &app_state.keys.state_transition.proving_key,
-        &inventory_state,
-        new_blinding,
-        req.item_id,
-        req.amount,
-        req.item_volume,
-        registry_root,
-        req.max_capacity,
-        req.nonce,
-        inventory_id,
-        op_type,
-    ) {
-        Ok(result) => {
-            let proof_bytes = result.proof.serialize_proof().unwrap();
-            let response = StateTransitionResponse {
-                proof: format!("0x{}", hex::encode(proof_bytes)),
-                public_inputs: result.proof
-                    .public_inputs
-                    .iter()
-                    .map(serialize_fr)
-                    .collect(),
-                new_commitment: serialize_fr(&result.new_commitment),
-                new_volume: result.new_state.current_volume,
-                nonce: result.nonce,
-                // Return serialized field element bytes - this matches what the circuit used
-                // after modular reduction (for object IDs exceeding BN254 field order)
-                inventory_id: serialize_fr(&inventory_id),
-                registry_root: serialize_fr(&result.registry_root),
-            };
-            (StatusCode::OK, Json(response)).into_response()
+/// Which circuit's keys a proof was made with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofKind {
+    StateTransition,
+    /// Epoch-tagged state transition; also checked for freshness
+    StateTransitionEpoch,
+    ItemExists,
+    Capacity,
+}
+
+impl ProofKind {
+    /// Circuit label used in metrics
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::StateTransition => "state_transition",
+            Self::StateTransitionEpoch => "state_transition_epoch",
+            Self::ItemExists => "item_exists",
+            Self::Capacity => "capacity",
         }
-        Err(e) => (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: e.to_string(),
-            }),
-        )
-            .into_response(),
+    }
+}
+
+/// Newest envelope format this server reads
+pub const ENVELOPE_VERSION: u64 = 1;
+
+/// Version assumed for envelopes sent before versioning, which have no `version` field
+pub const LEGACY_ENVELOPE_VERSION: u64 = 1;
+
+fn legacy_envelope_version() -> u64 {
+    LEGACY_ENVELOPE_VERSION
+}
+
+/// A proof that says which circuit it is for
+#[derive(Deserialize)]
+pub struct ProofEnvelope {
+    /// Envelope format version
+    #[serde(default = "legacy_envelope_version")]
+    pub version: u64,
+    pub circuit: ProofKind,
+    pub proof: String,
+    pub public_inputs: Vec<String>,
+}
+
+impl ProofEnvelope {
+    /// Reject envelopes in a format this server does not know.
+    ///
+    /// [`ENVELOPE_VERSION`] is the only format so far. When the format
+    /// changes, older versions should be upgraded here instead of rejected.
+    fn check_version(&self) -> Result<(), String> {
+        match self.version {
+            ENVELOPE_VERSION => Ok(()),
+            version => Err(format!(
+                "Unsupported envelope version {} (newest supported is {})",
+                version, ENVELOPE_VERSION
+            )),
+        }
+    }
+}
+
+/// Outcome for one envelope of a bulk request
+#[derive(Debug, Serialize)]
+pub struct BulkVerifyResult {
+    /// Position of the envelope in the request
+    pub index: usize,
+    pub valid: bool,
+    /// Why the proof could not be checked (malformed, wrong input count, stale epoch)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Verify a mix of proofs in one request, returning one result per envelope.
+///
+/// Proofs of the same kind are batch verified together; only if a batch fails
+/// are its proofs verified one by one to find the invalid ones.
+pub async fn verify_bulk(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Json(envelopes): Json<Vec<ProofEnvelope>>,
+) -> Response {
+    let (keys, timeout, policy, metrics) = {
+        let app_state = state.read().await;
+        let policy = app_state.epoch_policy;
+        (app_state.keys.clone(), app_state.proof_timeout, policy, app_state.metrics.clone())
+    };
+
+    let verified = run_prover(timeout, move || {
+        verify_envelopes(&keys, &envelopes, &policy, policy.current_epoch(), &metrics)
+    })
+    .await;
+
+    match verified {
+        Ok(results) => (StatusCode::OK, Json(results)).into_response(),
+        Err(response) => response,
+    }
+}
+
+fn verify_envelopes(
+    keys: &CircuitKeys,
+    envelopes: &[ProofEnvelope],
+    policy: &EpochPolicy,
+    current_epoch: u64,
+    metrics: &Metrics,
+) -> Vec<BulkVerifyResult> {
+    let mut results: Vec<BulkVerifyResult> = (0..envelopes.len())
+        .map(|index| BulkVerifyResult { index, valid: false, error: None })
+        .collect();
+
+    // Group the well-formed proofs by kind, keeping their indices
+    let mut groups: HashMap<ProofKind, Vec<(usize, ProofWithInputs)>> = HashMap::new();
+    for (index, envelope) in envelopes.iter().enumerate() {
+        let parsed = envelope
+            .check_version()
+            .and_then(|()| parse_proof_with_inputs(&envelope.proof, &envelope.public_inputs))
+            .and_then(|proof| match envelope.circuit {
+                ProofKind::StateTransitionEpoch => {
+                    let epoch = proof_epoch(&proof.public_inputs)?;
+                    check_fresh(policy, epoch, current_epoch).map(|()| proof)
+                }
+                _ => Ok(proof),
+            });
+
+        match parsed {
+            Ok(proof) => groups.entry(envelope.circuit).or_default().push((index, proof)),
+            Err(e) => results[index].error = Some(e),
+        }
+    }
+
+    for (kind, group) in groups {
+        let (indices, proofs): (Vec<usize>, Vec<ProofWithInputs>) = group.into_iter().unzip();
+
+        if proofs.len() > 1 && matches!(batch_verify_kind(keys, kind, &proofs), Ok(true)) {
+            for index in indices {
+                results[index].valid = true;
+                metrics.record_verification(kind.label(), true);
+            }
+            continue;
+        }
+
+        for (index, proof) in indices.into_iter().zip(&proofs) {
+            match batch_verify_kind(keys, kind, std::slice::from_ref(proof)) {
+                Ok(valid) => {
+                    results[index].valid = valid;
+                    metrics.record_verification(kind.label(), valid);
+                }
+                Err(e) => results[index].error = Some(e.to_string()),
+            }
+        }
+    }
+
+    results
+}
+
+fn batch_verify_kind(
+    keys: &CircuitKeys,
+    kind: ProofKind,
+    proofs: &[ProofWithInputs],
+) -> Result<bool, ProverError> {
+    match kind {
+        ProofKind::StateTransition => {
+            verify::batch_verify(&keys.state_transition.verifying_key, proofs)
+        }
+        ProofKind::StateTransitionEpoch => {
+            verify::batch_verify(&keys.state_transition_epoch.verifying_key, proofs)
+        }
+        ProofKind::ItemExists => verify::batch_verify(&keys.item_exists.verifying_key, proofs),
+        ProofKind::Capacity => verify::batch_verify(&keys.capacity.verifying_key, proofs),
     }
 }
 
@@ -255,42 +823,40 @@ pub struct ItemExistsRequest {
 pub async fn prove_item_exists(
     State(state): State<Arc<RwLock<AppState>>>,
     Json(req): Json<ItemExistsRequest>,
-) -> impl IntoResponse {
-    let blinding = match parse_fr(&req.blinding) {
-        Ok(b) => b,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
-    };
+) -> Response {
+    let (keys, timeout, metrics) = prover_context(&state).await;
+    let queued_at = Instant::now();
+    let prover_metrics = metrics.clone();
+    let result = run_timed_prover(&metrics, ProofKind::ItemExists, timeout, move || {
+        item_exists_proof(&keys, &prover_metrics, &req, queued_at)
+    })
+    .await;
 
+    proof_response(result)
+}
+
+/// Parse an ItemExists request and prove it.
+fn item_exists_proof(
+    keys: &CircuitKeys,
+    metrics: &Metrics,
+    req: &ItemExistsRequest,
+    queued_at: Instant,
+) -> Result<ProofResponse, String> {
+    let blinding = parse_fr(&req.blinding)?;
     let inventory_state = parse_inventory_state(&req.inventory, req.current_volume, blinding);
 
-    let app_state = state.read().await;
+    let proof_with_inputs = metrics
+        .time_proof(ProofKind::ItemExists.label(), queued_at, || {
+            prove::prove_item_exists(
+                &keys.item_exists.proving_key,
+                &inventory_state,
+                req.item_id,
+                req.min_quantity,
+            )
+        })
+        .map_err(|e| e.to_string())?;
 
-    match prove::prove_item_exists(
-        &app_state.keys.item_exists.proving_key,
-        &inventory_state,
-        req.item_id,
-        req.min_quantity,
-    ) {
-        Ok(proof_with_inputs) => {
-            let proof_bytes = proof_with_inputs.serialize_proof().unwrap();
-            let response = ProofResponse {
-                proof: format!("0x{}", hex::encode(proof_bytes)),
-                public_inputs: proof_with_inputs
-                    .public_inputs
-                    .iter()
-                    .map(serialize_fr)
-                    .collect(),
-            };
-            (StatusCode::OK, Json(response)).into_response()
-        }
-        Err(e) => (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: e.to_string(),
-            }),
-        )
-            .into_response(),
-    }
+    ProofResponse::new(&proof_with_inputs)
 }
 
 // ============ Capacity ============
@@ -310,43 +876,443 @@ pub struct CapacityRequest {
 pub async fn prove_capacity(
     State(state): State<Arc<RwLock<AppState>>>,
     Json(req): Json<CapacityRequest>,
-) -> impl IntoResponse {
-    let blinding = match parse_fr(&req.blinding) {
-        Ok(b) => b,
+) -> Response {
+    let (keys, timeout, metrics) = prover_context(&state).await;
+    let queued_at = Instant::now();
+    let prover_metrics = metrics.clone();
+    let result = run_timed_prover(&metrics, ProofKind::Capacity, timeout, move || {
+        capacity_proof(&keys, &prover_metrics, &req, queued_at)
+    })
+    .await;
+
+    proof_response(result)
+}
+
+/// Parse a Capacity request and prove it.
+fn capacity_proof(
+    keys: &CircuitKeys,
+    metrics: &Metrics,
+    req: &CapacityRequest,
+    queued_at: Instant,
+) -> Result<ProofResponse, String> {
+    let blinding = parse_fr(&req.blinding)?;
+    let inventory_state = parse_inventory_state(&req.inventory, req.current_volume, blinding);
+
+    let proof_with_inputs = metrics
+        .time_proof(ProofKind::Capacity.label(), queued_at, || {
+            prove::prove_capacity(&keys.capacity.proving_key, &inventory_state, req.max_capacity)
+        })
+        .map_err(|e| e.to_string())?;
+
+    ProofResponse::new(&proof_with_inputs)
+}
+
+// ============ Batch Prove ============
+
+/// One request of a batch, tagged with its `type`
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum BatchProveRequest {
+    ItemExists(ItemExistsRequest),
+    Capacity(CapacityRequest),
+    /// `op_type` is implied and may be omitted
+    Deposit(StateTransitionRequest),
+    /// `op_type` is implied and may be omitted
+    Withdraw(StateTransitionRequest),
+}
+
+impl BatchProveRequest {
+    /// Which circuit's keys prove this request
+    fn kind(&self) -> ProofKind {
+        match self {
+            Self::ItemExists(_) => ProofKind::ItemExists,
+            Self::Capacity(_) => ProofKind::Capacity,
+            Self::Deposit(req) | Self::Withdraw(req) => req.kind(),
+        }
+    }
+}
+
+/// Outcome for one request of a batch: what its own endpoint would return
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum BatchProveResult {
+    Proof(ProofResponse),
+    StateTransition(StateTransitionResponse),
+    Error(ErrorResponse),
+}
+
+/// Prove several requests at once, returning one result per request in order.
+///
+/// The proofs are generated concurrently on the blocking thread pool. Each
+/// request succeeds or fails on its own, so the response is 200 whenever the
+/// body is a JSON array; requests that are malformed, fail to prove or are
+/// not done within the proof timeout get an `{"error"}` result.
+///
+/// Proving tasks cannot be cancelled once started, so batches longer than
+/// `max_batch_size` are rejected whole with 413 Payload Too Large.
+pub async fn prove_batch(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Json(requests): Json<Vec<serde_json::Value>>,
+) -> Response {
+    let max_batch_size = state.read().await.max_batch_size;
+    if requests.len() > max_batch_size {
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(ErrorResponse {
+                error: format!(
+                    "Batch of {} requests exceeds the limit of {}",
+                    requests.len(),
+                    max_batch_size
+                ),
+            }),
+        )
+            .into_response();
+    }
+
+    let (keys, timeout, metrics) = prover_context(&state).await;
+    let queued_at = Instant::now();
+    let deadline = tokio::time::Instant::from_std(queued_at) + timeout;
+
+    let tasks: Vec<_> = requests
+        .into_iter()
+        .map(|request| {
+            let request: BatchProveRequest = serde_json::from_value(request)
+                .map_err(|e| format!("Invalid request: {}", e))?;
+            let kind = request.kind();
+            let (keys, metrics) = (keys.clone(), metrics.clone());
+            let task = tokio::task::spawn_blocking(move || {
+                batch_proof(&keys, &metrics, request, queued_at)
+            });
+            Ok::<_, String>((kind, task))
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        let (kind, task) = match task {
+            Ok(task) => task,
+            Err(error) => {
+                results.push(BatchProveResult::Error(ErrorResponse { error }));
+                continue;
+            }
+        };
+        let result = match tokio::time::timeout_at(deadline, task).await {
+            Ok(Ok(Ok(result))) => result,
+            Ok(Ok(Err(error))) => BatchProveResult::Error(ErrorResponse { error }),
+            Ok(Err(e)) => BatchProveResult::Error(ErrorResponse {
+                error: format!("Proof generation failed: {}", e),
+            }),
+            Err(_) => {
+                metrics.record_timeout(kind.label());
+                BatchProveResult::Error(ErrorResponse {
+                    error: format!("Proof generation timed out after {}s", timeout.as_secs_f64()),
+                })
+            }
+        };
+        results.push(result);
+    }
+
+    (StatusCode::OK, Json(results)).into_response()
+}
+
+fn batch_proof(
+    keys: &CircuitKeys,
+    metrics: &Metrics,
+    request: BatchProveRequest,
+    queued_at: Instant,
+) -> Result<BatchProveResult, String> {
+    match request {
+        BatchProveRequest::ItemExists(req) => {
+            item_exists_proof(keys, metrics, &req, queued_at).map(BatchProveResult::Proof)
+        }
+        BatchProveRequest::Capacity(req) => {
+            capacity_proof(keys, metrics, &req, queued_at).map(BatchProveResult::Proof)
+        }
+        BatchProveRequest::Deposit(req) => {
+            state_transition_proof(keys, metrics, &req, OpType::Deposit, queued_at)
+                .map(BatchProveResult::StateTransition)
+        }
+        BatchProveRequest::Withdraw(req) => {
+            state_transition_proof(keys, metrics, &req, OpType::Withdraw, queued_at)
+                .map(BatchProveResult::StateTransition)
+        }
+    }
+}
+
+// ============ Registry and Nullifiers ============
+
+#[derive(Serialize)]
+pub struct RegistryRootResponse {
+    pub root: String,
+}
+
+#[derive(Deserialize)]
+pub struct InsertLeafRequest {
+    /// Leaf index in the registry tree
+    pub index: u64,
+    /// Leaf value (hex field element)
+    pub leaf: String,
+}
+
+#[derive(Deserialize)]
+pub struct NullifierRequest {
+    /// Nullifier (hex field element)
+    pub nullifier: String,
+}
+
+#[derive(Serialize)]
+pub struct NullifierResponse {
+    /// Whether the nullifier has been spent (after this request)
+    pub spent: bool,
+    /// Whether this request recorded it (false for checks and repeats)
+    pub recorded: bool,
+}
+
+async fn storage(state: &RwLock<AppState>) -> Arc<dyn StorageBackend> {
+    state.read().await.storage.clone()
+}
+
+fn storage_error(e: StorageError) -> Response {
+    let status = match e {
+        StorageError::IndexOutOfRange { .. } => StatusCode::BAD_REQUEST,
+        StorageError::Backend(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(ErrorResponse { error: e.to_string() })).into_response()
+}
+
+pub async fn registry_root(State(state): State<Arc<RwLock<AppState>>>) -> Response {
+    match storage(&state).await.get_root().await {
+        Ok(root) => Json(RegistryRootResponse { root: serialize_fr(&root) }).into_response(),
+        Err(e) => storage_error(e),
+    }
+}
+
+/// Set a registry leaf. Requires the admin bearer token, like `/admin/reload-keys`.
+pub async fn registry_insert(
+    State(state): State<Arc<RwLock<AppState>>>,
+    headers: HeaderMap,
+    Json(req): Json<InsertLeafRequest>,
+) -> Response {
+    if let Some(rejection) = require_admin(&state, &headers).await {
+        return rejection;
+    }
+
+    let leaf = match parse_fr(&req.leaf) {
+        Ok(leaf) => leaf,
         Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
     };
 
-    let inventory_state = parse_inventory_state(&req.inventory, req.current_volume, blinding);
+    match storage(&state).await.insert_leaf(req.index, leaf).await {
+        Ok(root) => Json(RegistryRootResponse { root: serialize_fr(&root) }).into_response(),
+        Err(e) => storage_error(e),
+    }
+}
 
-    let app_state = state.read().await;
+pub async fn nullifier_check(
+    State(state): State<Arc<RwLock<AppState>>>,
+    Json(req): Json<NullifierRequest>,
+) -> Response {
+    let nullifier = match parse_fr(&req.nullifier) {
+        Ok(n) => n,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
+    };
+
+    match storage(&state).await.has_nullifier(nullifier).await {
+        Ok(spent) => Json(NullifierResponse { spent, recorded: false }).into_response(),
+        Err(e) => storage_error(e),
+    }
+}
+
+/// Record a nullifier as spent. Repeating the request is harmless; `recorded`
+/// tells the first caller apart from the rest.
+///
+/// Requires the admin bearer token: anyone able to record nullifiers could
+/// block a legitimate burn or gift by spending its nullifier first.
+pub async fn nullifier_record(
+    State(state): State<Arc<RwLock<AppState>>>,
+    headers: HeaderMap,
+    Json(req): Json<NullifierRequest>,
+) -> Response {
+    if let Some(rejection) = require_admin(&state, &headers).await {
+        return rejection;
+    }
+
+    let nullifier = match parse_fr(&req.nullifier) {
+        Ok(n) => n,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
+    };
+
+    match storage(&state).await.record_nullifier(nullifier).await {
+        Ok(recorded) => Json(NullifierResponse { spent: true, recorded }).into_response(),
+        Err(e) => storage_error(e),
+    }
+}
+
+// ============ Circuit Breakdown ============
+
+#[derive(Clone, Serialize)]
+pub struct SectionResponse {
+    /// Outermost namespace that created the constraints
+    pub section: String,
+    pub constraints: usize,
+}
+
+#[derive(Clone, Serialize)]
+pub struct BreakdownResponse {
+    pub circuit: &'static str,
+    pub total_constraints: usize,
+    /// Per-section counts, in synthesis order; they sum to `total_constraints`
+    pub sections: Vec<SectionResponse>,
+}
+
+/// Constraint count of each section of a circuit, to show where proving cost goes.
+///
+/// Deposits and withdrawals both use `state_transition`. The breakdown only
+/// depends on the circuit, so it is computed once per kind and cached.
+pub async fn circuit_breakdown(Path(kind): Path<ProofKind>) -> Response {
+    static BREAKDOWNS: OnceLock<HashMap<ProofKind, BreakdownResponse>> = OnceLock::new();
+
+    if BREAKDOWNS.get().is_none() {
+        let error = match tokio::task::spawn_blocking(compute_breakdowns).await {
+            Ok(Ok(breakdowns)) => {
+                // A concurrent request may have filled it first; both are identical
+                let _ = BREAKDOWNS.set(breakdowns);
+                None
+            }
+            Ok(Err(e)) => Some(format!("Circuit synthesis failed: {}", e)),
+            Err(e) => Some(format!("Breakdown task failed: {}", e)),
+        };
+        if let Some(error) = error {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error }))
+                .into_response();
+        }
+    }
+
+    Json(BREAKDOWNS.get().unwrap()[&kind].clone()).into_response()
+}
+
+fn compute_breakdowns() -> Result<HashMap<ProofKind, BreakdownResponse>, String> {
+    let epoch = StateTransitionCircuit::empty().with_epoch(0);
+    Ok(HashMap::from([
+        breakdown(ProofKind::StateTransition, StateTransitionCircuit::empty())?,
+        breakdown(ProofKind::StateTransitionEpoch, epoch)?,
+        breakdown(ProofKind::ItemExists, ItemExistsSMTCircuit::empty())?,
+        breakdown(ProofKind::Capacity, CapacitySMTCircuit::empty())?,
+    ]))
+}
+
+fn breakdown<C: CircuitMeta>(
+    kind: ProofKind,
+    circuit: C,
+) -> Result<(ProofKind, BreakdownResponse), String> {
+    let total_constraints = circuit.num_constraints();
+    let sections = constraint_breakdown(circuit)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|count| SectionResponse { section: count.section, constraints: count.constraints })
+        .collect();
+
+    Ok((kind, BreakdownResponse { circuit: kind.label(), total_constraints, sections }))
+}
+
+// ============ Admin ============
+
+#[derive(Serialize)]
+pub struct ReloadKeysResponse {
+    /// Fingerprint of the keys now in use (see `CircuitKeys::fingerprint`)
+    pub fingerprint: String,
+}
+
+/// Reload circuit keys from the keys directory and swap them in.
+///
+/// Proofs already in progress finish with the keys they started with; requests
+/// after the swap use the new keys. Requires `Authorization: Bearer <token>`
+/// matching `ADMIN_TOKEN` (401 otherwise, 403 if no token is configured).
+///
+/// The new keys must pass the same self-test as at startup before they are
+/// swapped in, and the keys and `/health` are updated together. If the new
+/// keys cannot be loaded or fail the self-test, the old keys and health stay
+/// in place and the response is 500.
+pub async fn reload_keys(
+    State(state): State<Arc<RwLock<AppState>>>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(rejection) = require_admin(&state, &headers).await {
+        return rejection;
+    }
+    let keys_dir = state.read().await.keys_dir.clone();
+
+    let loaded = tokio::task::spawn_blocking(move || {
+        let keys = CircuitKeys::load_from_directory(&keys_dir).map_err(|e| e.to_string())?;
+        let fingerprint = keys.fingerprint().map_err(|e| e.to_string())?;
+        match crate::health::self_test(&keys) {
+            ServerHealth::Degraded { reasons } => Err(reasons.join("; ")),
+            health => Ok((keys, fingerprint, health)),
+        }
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
 
-    match prove::prove_capacity(
-        &app_state.keys.capacity.proving_key,
-        &inventory_state,
-        req.max_capacity,
-    ) {
-        Ok(proof_with_inputs) => {
-            let proof_bytes = proof_with_inputs.serialize_proof().unwrap();
-            let response = ProofResponse {
-                proof: format!("0x{}", hex::encode(proof_bytes)),
-                public_inputs: proof_with_inputs
-                    .public_inputs
-                    .iter()
-                    .map(serialize_fr)
-                    .collect(),
-            };
-            (StatusCode::OK, Json(response)).into_response()
+    match loaded {
+        Ok((keys, fingerprint, health)) => {
+            let mut state = state.write().await;
+            state.keys = Arc::new(keys);
+            state.health = health;
+            println!("Reloaded circuit keys, fingerprint {}", fingerprint);
+            (StatusCode::OK, Json(ReloadKeysResponse { fingerprint })).into_response()
         }
         Err(e) => (
-            StatusCode::BAD_REQUEST,
+            StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
-                error: e.to_string(),
+                error: format!("Failed to reload circuit keys: {}", e),
             }),
         )
             .into_response(),
     }
 }
 
+/// The error response for a request without the server's admin token, if any.
+async fn require_admin(state: &RwLock<AppState>, headers: &HeaderMap) -> Option<Response> {
+    let admin_token = state.read().await.admin_token.clone();
+    admin_rejection(admin_token.as_deref(), headers)
+}
+
+/// The error response for a request whose bearer token is not the admin token, if any.
+fn admin_rejection(expected: Option<&str>, headers: &HeaderMap) -> Option<Response> {
+    let Some(expected) = expected else {
+        return Some((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                error: "Admin endpoints are disabled; set ADMIN_TOKEN to enable them".into(),
+            }),
+        )
+            .into_response());
+    };
+
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match provided {
+        Some(token) if tokens_match(token.as_bytes(), expected.as_bytes()) => None,
+        _ => Some((
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            Json(ErrorResponse {
+                error: "Missing or invalid admin token".into(),
+            }),
+        )
+            .into_response()),
+    }
+}
+
+/// Compare tokens without exiting at the first differing byte, so response
+/// timing does not reveal how much of a guess was right.
+fn tokens_match(provided: &[u8], expected: &[u8]) -> bool {
+    provided.len() == expected.len()
+        && provided.iter().zip(expected).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
 // ============ Utilities ============
 
 #[derive(Deserialize)]
@@ -368,20 +1334,24 @@ pub struct CreateCommitmentResponse {
 pub async fn create_commitment(
     Json(req): Json<CreateCommitmentRequest>,
 ) -> impl IntoResponse {
-    let blinding = match parse_fr(&req.blinding) {
-        Ok(b) => b,
-        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })).into_response(),
+    // Unlike parse_fr, the blinding is not reduced: non-canonical encodings are rejected
+    let blinding = match hex::decode(req.blinding.trim_start_matches("0x")) {
+        Ok(bytes) => bytes,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse {
+            error: format!("Invalid hex: {}", e)
+        })).into_response(),
     };
 
     let pairs: Vec<(u64, u64)> = req.inventory.iter().map(|i| (i.item_id, i.quantity)).collect();
     let tree = SparseMerkleTree::from_items(&pairs, DEFAULT_DEPTH);
 
+    let commitment = match try_create_smt_commitment(&tree, req.current_volume, &blinding) {
+        Ok(c) => c,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse {
+            error: e.to_string()
+        })).into_response(),
+    };
     let inventory_root = tree.root();
-    let commitment = create_smt_commitment(
-        inventory_root,
-        req.current_volume,
-        blinding,
-    );
 
     (
         StatusCode::OK,
@@ -398,11 +1368,177 @@ pub struct GenerateBlindingResponse {
     pub blinding: String,
 }
 
+/// Generate a fresh blinding factor from the operating system's CSPRNG.
+///
+/// Zero is rejected: a zero blinding would make the commitment a plain hash
+/// of the inventory contents.
 pub async fn generate_blinding() -> Json<GenerateBlindingResponse> {
-    let mut rng = ark_std::rand::thread_rng();
-    let blinding: Fr = rng.gen();
+    let blinding = loop {
+        let candidate: Fr = OsRng.gen();
+        if !candidate.is_zero() {
+            break candidate;
+        }
+    };
 
     Json(GenerateBlindingResponse {
         blinding: serialize_fr(&blinding),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_serialize::CanonicalDeserialize;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use inventory_prover::setup::{
+        setup_capacity, setup_state_transition_with_epoch, CircuitKeyPair,
+    };
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn test_generate_blinding_distinct_and_nonzero() {
+        let mut seen = HashSet::new();
+
+        for _ in 0..100 {
+            let Json(response) = generate_blinding().await;
+
+            // Canonical 32-byte little-endian encoding of a valid field element
+            let bytes = hex::decode(response.blinding.trim_start_matches("0x")).unwrap();
+            assert_eq!(bytes.len(), 32);
+            let blinding = Fr::deserialize_compressed(&bytes[..]).unwrap();
+
+            assert!(!blinding.is_zero());
+            assert!(seen.insert(blinding), "duplicate blinding");
+        }
+    }
+
+    fn epoch_proof_request(
+        keys: &CircuitKeyPair<StateTransitionCircuit>,
+        epoch: u64,
+    ) -> VerifyStateTransitionRequest {
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.tree.update(1, 100);
+        state.current_volume = 1000;
+
+        let result = prove::prove_state_transition_at_epoch(
+            &keys.proving_key,
+            &state,
+            Fr::from(67890u64),
+            1,
+            5,
+            10,
+            Fr::from(99999u64),
+            10000,
+            0,
+            Fr::from(12345678u64),
+            OpType::Deposit,
+            epoch,
+        )
+        .unwrap();
+
+        VerifyStateTransitionRequest {
+            proof: format!("0x{}", hex::encode(result.proof.serialize_proof().unwrap())),
+            public_inputs: result.proof.public_inputs.iter().map(serialize_fr).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_stale_epoch() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition_with_epoch(&mut rng).unwrap();
+        let policy = EpochPolicy { epoch_secs: 3600, window: 1 };
+        let metrics = Metrics::default();
+        let req = epoch_proof_request(&keys, 100);
+
+        let current = verify_at_epoch(&keys.verifying_key, &req, &policy, 100, &metrics);
+        assert_eq!(current.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(current.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["valid"], true);
+        assert_eq!(body["epoch"], 100);
+
+        let within_window = verify_at_epoch(&keys.verifying_key, &req, &policy, 101, &metrics);
+        assert_eq!(within_window.status(), StatusCode::OK);
+
+        let stale = verify_at_epoch(&keys.verifying_key, &req, &policy, 102, &metrics);
+        assert_eq!(stale.status(), StatusCode::CONFLICT);
+
+        let future = verify_at_epoch(&keys.verifying_key, &req, &policy, 99, &metrics);
+        assert_eq!(future.status(), StatusCode::CONFLICT);
+
+        // Stale proofs are rejected before verification, so only two are counted
+        let counted = "inventory_proofs_verified_total{circuit=\"state_transition_epoch\",result";
+        assert!(metrics.render().contains(&format!("{}=\"valid\"}} 2\n", counted)));
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_health_ok_only_when_healthy() {
+        let healthy = health_response(&ServerHealth::Healthy);
+        assert_eq!(healthy.status(), StatusCode::OK);
+        assert_eq!(json_body(healthy).await, serde_json::json!({ "status": "ok" }));
+
+        let starting = health_response(&ServerHealth::Starting);
+        assert_eq!(starting.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json_body(starting).await["status"], "starting");
+    }
+
+    #[tokio::test]
+    async fn test_failed_self_test_reports_degraded() {
+        // Verifying key from a different setup than the proving key
+        let mut keys = setup_capacity(&mut StdRng::seed_from_u64(1)).unwrap();
+        keys.verifying_key = setup_capacity(&mut StdRng::seed_from_u64(2)).unwrap().verifying_key;
+
+        let check = crate::health::check_capacity(&keys);
+        let health = ServerHealth::from_checks([("capacity", check)]);
+        assert!(!health.is_healthy());
+
+        let response = health_response(&health);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = json_body(response).await;
+        assert_eq!(body["status"], "degraded");
+        let reason = body["reasons"][0].as_str().unwrap();
+        assert!(reason.starts_with("capacity self-test failed"), "{}", reason);
+    }
+
+    #[tokio::test]
+    async fn test_run_prover_returns_result() {
+        let result = run_prover(Duration::from_secs(5), || 42).await;
+        assert_eq!(result.ok(), Some(42));
+    }
+
+    #[tokio::test]
+    async fn test_run_prover_times_out() {
+        // Stand-in for a pathological proof that outlives the timeout
+        let slow_prover = || std::thread::sleep(Duration::from_millis(500));
+
+        let result = run_prover(Duration::from_millis(50), slow_prover).await;
+
+        let response = result.expect_err("slow prover should time out");
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_proof_counted_as_failed() {
+        let metrics = Metrics::default();
+        let slow_prover = || std::thread::sleep(Duration::from_millis(500));
+
+        let result =
+            run_timed_prover(&metrics, ProofKind::Capacity, Duration::from_millis(50), slow_prover)
+                .await;
+        assert!(result.is_err());
+        let timeouts = "inventory_proofs_failed_total{circuit=\"capacity\",reason=\"timeout\"}";
+        assert!(metrics.render().contains(&format!("{} 1\n", timeouts)));
+
+        // A proof done in time is not
+        run_timed_prover(&metrics, ProofKind::ItemExists, Duration::from_secs(5), || ())
+            .await
+            .unwrap();
+        assert!(!metrics.render().contains("circuit=\"item_exists\""));
+    }
+}
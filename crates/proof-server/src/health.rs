@@ -0,0 +1,160 @@
+//! Server health, as reported by `/health`.
+//!
+//! The server starts out `Starting`. Once the circuit keys are in place, a
+//! self-test proves and verifies a sample statement with each circuit's keys;
+//! any circuit that fails leaves the server `Degraded` instead of `Healthy`.
+
+use ark_bn254::Fr;
+
+use inventory_circuits::{
+    signal::OpType, CapacitySMTCircuit, ItemExistsSMTCircuit, StateTransitionCircuit,
+};
+use inventory_prover::{
+    prove, setup::CircuitKeyPair, verify, CapacityPublicInputs, CircuitKeys,
+    ItemExistsPublicInputs, InventoryState, ProofWithInputs, ProverError,
+    StateTransitionPublicInputs,
+};
+
+/// Overall server health
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerHealth {
+    /// Keys are loading or the self-test has not finished yet
+    Starting,
+    /// Every circuit passed its self-test
+    Healthy,
+    /// The server is up, but some circuits cannot be relied on
+    Degraded { reasons: Vec<String> },
+}
+
+impl ServerHealth {
+    /// Health after a self-test, given each circuit's name and result.
+    pub fn from_checks<'a>(
+        checks: impl IntoIterator<Item = (&'a str, Result<(), String>)>,
+    ) -> Self {
+        let reasons: Vec<String> = checks
+            .into_iter()
+            .filter_map(|(name, result)| {
+                result.err().map(|e| format!("{} self-test failed: {}", name, e))
+            })
+            .collect();
+
+        if reasons.is_empty() {
+            Self::Healthy
+        } else {
+            Self::Degraded { reasons }
+        }
+    }
+
+    /// Whether the server is fully operational
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, Self::Healthy)
+    }
+}
+
+/// Prove and verify a sample statement with every circuit's keys.
+pub fn self_test(keys: &CircuitKeys) -> ServerHealth {
+    ServerHealth::from_checks([
+        ("state_transition", check_state_transition(&keys.state_transition, None)),
+        (
+            "state_transition_epoch",
+            check_state_transition(&keys.state_transition_epoch, Some(0)),
+        ),
+        ("item_exists", check_item_exists(&keys.item_exists)),
+        ("capacity", check_capacity(&keys.capacity)),
+    ])
+}
+
+/// Inventory used by the self-test: 100 of item 1 at 10 volume each.
+fn sample_state() -> InventoryState {
+    let mut state = InventoryState::from_items(&[(1, 100)], Fr::from(12345u64));
+    state.current_volume = 1000;
+    state
+}
+
+/// Deposit 5 of item 1, optionally tagged with an epoch.
+pub fn check_state_transition(
+    keys: &CircuitKeyPair<StateTransitionCircuit>,
+    epoch: Option<u64>,
+) -> Result<(), String> {
+    let state = sample_state();
+    let new_blinding = Fr::from(67890u64);
+    let registry_root = Fr::from(99999u64);
+    let inventory_id = Fr::from(1u64);
+
+    let result = match epoch {
+        Some(epoch) => prove::prove_state_transition_at_epoch(
+            &keys.proving_key,
+            &state,
+            new_blinding,
+            1,
+            5,
+            10,
+            registry_root,
+            10000,
+            0,
+            inventory_id,
+            OpType::Deposit,
+            epoch,
+        ),
+        None => prove::prove_state_transition(
+            &keys.proving_key,
+            &state,
+            new_blinding,
+            1,
+            5,
+            10,
+            registry_root,
+            10000,
+            0,
+            inventory_id,
+            OpType::Deposit,
+        ),
+    }
+    .map(|result| result.proof);
+
+    check_proof(result, |proof| {
+        let inputs = StateTransitionPublicInputs::from_vec(&proof.public_inputs)?;
+        verify::verify_state_transition(&keys.verifying_key, &proof.proof, &inputs)
+    })
+}
+
+/// Prove holding at least 50 of item 1.
+pub fn check_item_exists(keys: &CircuitKeyPair<ItemExistsSMTCircuit>) -> Result<(), String> {
+    let state = sample_state();
+    let result = prove::prove_item_exists(&keys.proving_key, &state, 1, 50);
+    let inputs = ItemExistsPublicInputs {
+        commitment: state.commitment(),
+        item_id: 1,
+        min_quantity: 50,
+    };
+
+    check_proof(result, |proof| {
+        verify::verify_item_exists(&keys.verifying_key, &proof.proof, &inputs)
+    })
+}
+
+/// Prove staying within a capacity of 10000.
+pub fn check_capacity(keys: &CircuitKeyPair<CapacitySMTCircuit>) -> Result<(), String> {
+    let state = sample_state();
+    let result = prove::prove_capacity(&keys.proving_key, &state, 10000);
+    let inputs = CapacityPublicInputs {
+        commitment: state.commitment(),
+        max_capacity: 10000,
+    };
+
+    check_proof(result, |proof| {
+        verify::verify_capacity(&keys.verifying_key, &proof.proof, &inputs)
+    })
+}
+
+fn check_proof(
+    proof: Result<ProofWithInputs, ProverError>,
+    verify: impl FnOnce(&ProofWithInputs) -> Result<bool, ProverError>,
+) -> Result<(), String> {
+    let proof = proof.map_err(|e| e.to_string())?;
+    match verify(&proof) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err("sample proof did not verify".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
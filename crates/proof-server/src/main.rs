@@ -1,20 +1,48 @@
 //! HTTP API server for inventory proof generation.
 
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::Router;
 use tokio::sync::RwLock;
 use tower_http::cors::{Any, CorsLayer};
 
+mod config;
 mod handlers;
+mod health;
+mod metrics;
 mod routes;
+mod storage;
+
+use config::{EpochPolicy, ServerConfig};
+use health::ServerHealth;
+use metrics::Metrics;
+use storage::{FileStorage, InMemoryStorage, StorageBackend};
 
 use inventory_prover::setup::{setup_all_circuits, CircuitKeys};
 
 /// Application state shared across handlers
 pub struct AppState {
+    /// Swapped wholesale by `/admin/reload-keys`; in-flight proofs keep their own `Arc`
     pub keys: Arc<CircuitKeys>,
+    /// Directory keys are loaded from at startup and on reload
+    pub keys_dir: PathBuf,
+    /// Bearer token for `/admin` endpoints, if they are enabled
+    pub admin_token: Option<String>,
+    /// Maximum time a single proof may take before the request fails with 504
+    pub proof_timeout: Duration,
+    /// Most requests `/api/prove/batch` accepts at once (413 above that)
+    pub max_batch_size: usize,
+    /// Which epochs count as fresh when verifying epoch-tagged proofs
+    pub epoch_policy: EpochPolicy,
+    /// Reported by `/health`; set by the startup self-test
+    pub health: ServerHealth,
+    /// Proof counters and latencies, exported at `/metrics`
+    pub metrics: Arc<Metrics>,
+    /// Registry tree and spent nullifiers
+    pub storage: Arc<dyn StorageBackend>,
 }
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 2)]
@@ -26,25 +54,77 @@ async fn main() {
 
     println!("Starting inventory proof server...");
 
+    let config = ServerConfig::from_env();
+    println!("Max request body: {} bytes", config.max_body_bytes);
+    println!("Proof timeout: {}s", config.proof_timeout_secs);
+    println!("Max batch size: {}", config.max_batch_size);
+    println!(
+        "Epochs: {}s long, proofs fresh for {} past epoch(s)",
+        config.epoch_secs, config.epoch_window
+    );
+
     // Load or generate circuit keys
-    let keys_dir = std::path::Path::new("keys");
+    let keys_dir = PathBuf::from("keys");
     let keys = if keys_dir.exists() {
         println!("Loading existing circuit keys from {:?}", keys_dir);
-        CircuitKeys::load_from_directory(keys_dir).expect("Failed to load circuit keys")
+        CircuitKeys::load_from_directory(&keys_dir).unwrap_or_else(|e| {
+            eprintln!("Failed to load circuit keys: {}", e);
+            std::process::exit(1);
+        })
     } else {
         println!("Running trusted setup (this may take a while)...");
         let keys = setup_all_circuits().expect("Failed to setup circuits");
-        keys.save_to_directory(keys_dir)
+        keys.save_to_directory(&keys_dir)
             .expect("Failed to save circuit keys");
         println!("Circuit keys saved to {:?}", keys_dir);
         keys
     };
 
-    let state = Arc::new(RwLock::new(AppState { keys: Arc::new(keys) }));
+    for footprint in keys.memory_breakdown() {
+        println!(
+            "  {} keys: {:.1} MiB (proving {:.1} MiB)",
+            footprint.circuit,
+            mib(footprint.total()),
+            mib(footprint.proving_key)
+        );
+    }
+    println!("Circuit keys use ~{:.1} MiB", mib(keys.memory_footprint()));
+    match keys.fingerprint() {
+        Ok(fingerprint) => println!("Circuit keys fingerprint: {}", fingerprint),
+        Err(e) => println!("Could not fingerprint circuit keys: {}", e),
+    }
+    if config.admin_token.is_none() {
+        println!("ADMIN_TOKEN not set, /admin endpoints are disabled");
+    }
+
+    let storage: Arc<dyn StorageBackend> = match &config.storage_dir {
+        Some(dir) => {
+            println!("Loading registry and nullifiers from {:?}", dir);
+            Arc::new(FileStorage::open(dir).expect("Failed to open storage"))
+        }
+        None => {
+            println!("STORAGE_DIR not set, registry and nullifiers are lost on restart");
+            Arc::new(InMemoryStorage::default())
+        }
+    };
+
+    let state = Arc::new(RwLock::new(AppState {
+        keys: Arc::new(keys),
+        keys_dir,
+        admin_token: config.admin_token.clone(),
+        proof_timeout: config.proof_timeout(),
+        max_batch_size: config.max_batch_size,
+        epoch_policy: config.epoch_policy(),
+        health: ServerHealth::Starting,
+        metrics: Arc::new(Metrics::default()),
+        storage,
+    }));
+
+    // Self-test every circuit in the background; /health reports 503 until it passes
+    tokio::spawn(run_self_test(state.clone()));
 
     // Build router
-    let app = Router::new()
-        .merge(routes::api_routes())
+    let app = routes::with_limits(Router::new().merge(routes::api_routes()), &config)
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
@@ -60,3 +140,36 @@ async fn main() {
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
+
+/// Prove and verify a sample with each circuit, then record the result.
+///
+/// The result is dropped if the keys were reloaded in the meantime, since the
+/// reload has already recorded the health of the keys it swapped in.
+async fn run_self_test(state: Arc<RwLock<AppState>>) {
+    let keys = state.read().await.keys.clone();
+    let tested = keys.clone();
+    let health = tokio::task::spawn_blocking(move || health::self_test(&tested))
+        .await
+        .unwrap_or_else(|e| ServerHealth::Degraded {
+            reasons: vec![format!("self-test panicked: {}", e)],
+        });
+
+    match &health {
+        ServerHealth::Degraded { reasons } => {
+            println!("Circuit self-test failed, server is degraded:");
+            for reason in reasons {
+                println!("  {}", reason);
+            }
+        }
+        _ => println!("Circuit self-test passed"),
+    }
+
+    let mut state = state.write().await;
+    if Arc::ptr_eq(&state.keys, &keys) {
+        state.health = health;
+    }
+}
+
+fn mib(bytes: usize) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
@@ -0,0 +1,356 @@
+//! Proof metrics, exported at `/metrics` in the Prometheus text format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Upper bounds (in seconds) of the proving latency histogram buckets
+pub const LATENCY_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Counters shared by all handlers
+#[derive(Default)]
+pub struct Metrics {
+    /// Proofs currently being generated (including ones whose request timed out)
+    in_flight: AtomicU64,
+    /// Per-circuit counters, keyed by circuit label
+    circuits: Mutex<BTreeMap<&'static str, CircuitMetrics>>,
+}
+
+#[derive(Default)]
+struct CircuitMetrics {
+    generated: u64,
+    failed: u64,
+    timed_out: u64,
+    verified_valid: u64,
+    verified_invalid: u64,
+    latency: Histogram,
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// Observations per bucket (not cumulative), plus one for +Inf
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/// Decrements the in-flight gauge when dropped
+struct InFlight<'a>(&'a AtomicU64);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    /// Run a proving call, counting it as in flight and recording its latency if
+    /// it succeeds or a failure if it does not.
+    ///
+    /// Latency is measured from `queued_at`, the time the request was handed
+    /// to the blocking thread pool, so it includes the wait for a free thread.
+    pub fn time_proof<T, E>(
+        &self,
+        circuit: &'static str,
+        queued_at: Instant,
+        prove: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        let _in_flight = InFlight(&self.in_flight);
+
+        let result = prove();
+        match &result {
+            Ok(_) => self.record_proof(circuit, queued_at.elapsed()),
+            Err(_) => self.record_failure(circuit),
+        }
+        result
+    }
+
+    /// Record a successfully generated proof.
+    pub fn record_proof(&self, circuit: &'static str, elapsed: Duration) {
+        let mut circuits = self.circuits.lock().unwrap();
+        let stats = circuits.entry(circuit).or_default();
+        stats.generated += 1;
+        stats.latency.observe(elapsed.as_secs_f64());
+    }
+
+    /// Record a proving call that returned an error.
+    pub fn record_failure(&self, circuit: &'static str) {
+        self.circuits.lock().unwrap().entry(circuit).or_default().failed += 1;
+    }
+
+    /// Record a proof whose request gave up waiting for it (504).
+    ///
+    /// The proving call itself keeps running and is still recorded when it ends.
+    pub fn record_timeout(&self, circuit: &'static str) {
+        self.circuits.lock().unwrap().entry(circuit).or_default().timed_out += 1;
+    }
+
+    /// Record a completed verification.
+    pub fn record_verification(&self, circuit: &'static str, valid: bool) {
+        let mut circuits = self.circuits.lock().unwrap();
+        let stats = circuits.entry(circuit).or_default();
+        if valid {
+            stats.verified_valid += 1;
+        } else {
+            stats.verified_invalid += 1;
+        }
+    }
+
+    /// Render all metrics in the Prometheus text exposition format (version 0.0.4).
+    pub fn render(&self) -> String {
+        let circuits = self.circuits.lock().unwrap();
+        let mut out = String::new();
+
+        out.push_str("# HELP inventory_proofs_generated_total Proofs generated successfully.\n");
+        out.push_str("# TYPE inventory_proofs_generated_total counter\n");
+        for (circuit, stats) in circuits.iter() {
+            let _ = writeln!(
+                out,
+                "inventory_proofs_generated_total{{circuit=\"{}\"}} {}",
+                circuit, stats.generated
+            );
+        }
+
+        out.push_str("# HELP inventory_proofs_failed_total Proofs that failed or timed out.\n");
+        out.push_str("# TYPE inventory_proofs_failed_total counter\n");
+        for (circuit, stats) in circuits.iter() {
+            for (reason, count) in [("error", stats.failed), ("timeout", stats.timed_out)] {
+                let _ = writeln!(
+                    out,
+                    "inventory_proofs_failed_total{{circuit=\"{}\",reason=\"{}\"}} {}",
+                    circuit, reason, count
+                );
+            }
+        }
+
+        out.push_str("# HELP inventory_proofs_verified_total Proofs verified, by result.\n");
+        out.push_str("# TYPE inventory_proofs_verified_total counter\n");
+        for (circuit, stats) in circuits.iter() {
+            let results = [
+                ("valid", stats.verified_valid),
+                ("invalid", stats.verified_invalid),
+            ];
+            for (result, count) in results {
+                let _ = writeln!(
+                    out,
+                    "inventory_proofs_verified_total{{circuit=\"{}\",result=\"{}\"}} {}",
+                    circuit, result, count
+                );
+            }
+        }
+
+        out.push_str(
+            "# HELP inventory_proving_seconds Time to generate a proof, including queue wait.\n",
+        );
+        out.push_str("# TYPE inventory_proving_seconds histogram\n");
+        for (circuit, stats) in circuits.iter() {
+            let latency = &stats.latency;
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&latency.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "inventory_proving_seconds_bucket{{circuit=\"{}\",le=\"{}\"}} {}",
+                    circuit, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "inventory_proving_seconds_bucket{{circuit=\"{}\",le=\"+Inf\"}} {}",
+                circuit, latency.count
+            );
+            let _ = writeln!(
+                out,
+                "inventory_proving_seconds_sum{{circuit=\"{}\"}} {}",
+                circuit, latency.sum
+            );
+            let _ = writeln!(
+                out,
+                "inventory_proving_seconds_count{{circuit=\"{}\"}} {}",
+                circuit, latency.count
+            );
+        }
+
+        out.push_str("# HELP inventory_proofs_in_flight Proofs currently being generated.\n");
+        out.push_str("# TYPE inventory_proofs_in_flight gauge\n");
+        let _ = writeln!(
+            out,
+            "inventory_proofs_in_flight {}",
+            self.in_flight.load(Ordering::Relaxed)
+        );
+
+        out
+    }
+}
+
+/// Render the registry and nullifier store sizes as gauges, in the same
+/// format as [`Metrics::render`].
+pub fn render_storage(registry_leaves: u64, nullifiers: u64) -> String {
+    let mut out = String::new();
+
+    out.push_str("# HELP inventory_registry_leaves Leaves set in the registry tree.\n");
+    out.push_str("# TYPE inventory_registry_leaves gauge\n");
+    let _ = writeln!(out, "inventory_registry_leaves {}", registry_leaves);
+
+    out.push_str("# HELP inventory_nullifiers Spent nullifiers recorded.\n");
+    out.push_str("# TYPE inventory_nullifiers gauge\n");
+    let _ = writeln!(out, "inventory_nullifiers {}", nullifiers);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+
+    /// Check every line against the text exposition format and return the
+    /// declared type of each metric family.
+    fn parse_exposition(text: &str) -> HashMap<String, String> {
+        let mut types = HashMap::new();
+        let mut samples = HashSet::new();
+
+        for line in text.lines() {
+            if let Some(comment) = line.strip_prefix("# ") {
+                let mut parts = comment.splitn(3, ' ');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some("HELP"), Some(_), Some(_)) => {}
+                    (Some("TYPE"), Some(name), Some(kind)) => {
+                        assert!(
+                            ["counter", "gauge", "histogram"].contains(&kind),
+                            "unknown type: {}",
+                            line
+                        );
+                        assert!(types.insert(name.to_string(), kind.to_string()).is_none());
+                    }
+                    _ => panic!("malformed comment: {}", line),
+                }
+                continue;
+            }
+
+            let (series, value) = line.rsplit_once(' ').expect("sample without value");
+            assert!(value.parse::<f64>().is_ok(), "bad value: {}", line);
+
+            let name = match series.split_once('{') {
+                Some((name, labels)) => {
+                    let labels = labels.strip_suffix('}').expect("unterminated labels");
+                    for label in labels.split(',') {
+                        let (key, value) = label.split_once('=').expect("label without value");
+                        assert!(!key.is_empty(), "empty label name: {}", line);
+                        assert!(
+                            value.len() >= 2 && value.starts_with('"') && value.ends_with('"'),
+                            "unquoted label value: {}",
+                            line
+                        );
+                    }
+                    name
+                }
+                None => series,
+            };
+            assert!(
+                name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+                "bad metric name: {}",
+                line
+            );
+            assert!(
+                samples.insert(series.to_string()),
+                "duplicate series: {}",
+                line
+            );
+
+            // Every sample belongs to a declared family
+            let family = ["_bucket", "_sum", "_count"]
+                .iter()
+                .find_map(|suffix| name.strip_suffix(suffix))
+                .filter(|family| types.get(*family).map(String::as_str) == Some("histogram"))
+                .unwrap_or(name);
+            assert!(types.contains_key(family), "undeclared metric: {}", line);
+        }
+
+        types
+    }
+
+    #[test]
+    fn test_render_is_valid_exposition_format() {
+        let metrics = Metrics::default();
+        metrics.record_proof("item_exists", Duration::from_millis(30));
+        metrics.record_proof("item_exists", Duration::from_millis(700));
+        metrics
+            .time_proof("state_transition", Instant::now(), || Ok::<_, ()>(()))
+            .unwrap();
+        metrics
+            .time_proof("capacity", Instant::now(), || Err::<(), _>("invalid state"))
+            .unwrap_err();
+        metrics.record_verification("state_transition", true);
+        metrics.record_verification("state_transition", false);
+
+        let text = metrics.render();
+        let types = parse_exposition(&text);
+
+        assert_eq!(types["inventory_proofs_generated_total"], "counter");
+        assert_eq!(types["inventory_proofs_failed_total"], "counter");
+        assert_eq!(types["inventory_proofs_verified_total"], "counter");
+        assert_eq!(types["inventory_proving_seconds"], "histogram");
+        assert_eq!(types["inventory_proofs_in_flight"], "gauge");
+
+        assert!(text.contains("inventory_proofs_generated_total{circuit=\"item_exists\"} 2\n"));
+        assert!(text
+            .contains("inventory_proofs_generated_total{circuit=\"state_transition\"} 1\n"));
+        assert!(text.contains(
+            "inventory_proofs_verified_total{circuit=\"state_transition\",result=\"invalid\"} 1\n"
+        ));
+        assert!(text
+            .contains("inventory_proving_seconds_bucket{circuit=\"item_exists\",le=\"0.05\"} 1\n"));
+        assert!(
+            text.contains("inventory_proving_seconds_bucket{circuit=\"item_exists\",le=\"1\"} 2\n")
+        );
+        assert!(text.contains("inventory_proofs_in_flight 0\n"));
+        // Queue wait before the proving call counts toward latency
+        metrics
+            .time_proof("modulo", Instant::now() - Duration::from_secs(20), || Ok::<_, ()>(()))
+            .unwrap();
+        let text = metrics.render();
+        let bucket = "inventory_proving_seconds_bucket{circuit=\"modulo\"";
+        assert!(text.contains(&format!("{},le=\"10\"}} 0\n", bucket)));
+        assert!(text.contains(&format!("{},le=\"30\"}} 1\n", bucket)));
+        // A failed proof is counted as failed, not generated
+        assert!(text.contains("inventory_proofs_generated_total{circuit=\"capacity\"} 0\n"));
+        assert!(text.contains(
+            "inventory_proofs_failed_total{circuit=\"capacity\",reason=\"error\"} 1\n"
+        ));
+        assert!(text.contains("inventory_proving_seconds_count{circuit=\"capacity\"} 0\n"));
+
+        metrics.record_timeout("capacity");
+        let text = metrics.render();
+        parse_exposition(&text);
+        assert!(text.contains(
+            "inventory_proofs_failed_total{circuit=\"capacity\",reason=\"timeout\"} 1\n"
+        ));
+    }
+
+    #[test]
+    fn test_render_storage_is_valid_exposition_format() {
+        let text = format!("{}{}", Metrics::default().render(), render_storage(3, 7));
+        let types = parse_exposition(&text);
+
+        assert_eq!(types["inventory_registry_leaves"], "gauge");
+        assert_eq!(types["inventory_nullifiers"], "gauge");
+        assert!(text.contains("inventory_registry_leaves 3\n"));
+        assert!(text.contains("inventory_nullifiers 7\n"));
+    }
+}
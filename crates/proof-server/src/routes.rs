@@ -3,24 +3,603 @@
 use std::sync::Arc;
 
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{get, post},
     Router,
 };
 use tokio::sync::RwLock;
 
+use crate::config::ServerConfig;
 use crate::handlers;
 use crate::AppState;
 
 /// Create API routes
 pub fn api_routes() -> Router<Arc<RwLock<AppState>>> {
     Router::new()
-        // Health check
+        // Health check (reports the self-test result)
         .route("/health", get(handlers::health))
+        // Prometheus metrics
+        .route("/metrics", get(handlers::metrics))
         // SMT-based proof generation endpoints
         .route("/api/prove/state-transition", post(handlers::prove_state_transition))
         .route("/api/prove/item-exists", post(handlers::prove_item_exists))
         .route("/api/prove/capacity", post(handlers::prove_capacity))
+        .route("/api/prove/batch", post(handlers::prove_batch))
+        // Proof verification endpoints
+        .route("/api/verify/state-transition", post(handlers::verify_state_transition))
+        .route("/api/verify/item-exists", post(handlers::verify_item_exists))
+        .route("/api/verify/deposit", post(handlers::verify_deposit))
+        .route("/api/verify/withdraw", post(handlers::verify_withdraw))
+        .route("/api/verify/bulk", post(handlers::verify_bulk))
+        // Registry tree and spent nullifiers (see storage::StorageBackend);
+        // writes need the ADMIN_TOKEN bearer token
+        .route("/api/registry/root", get(handlers::registry_root))
+        .route("/api/registry/insert", post(handlers::registry_insert))
+        .route("/api/nullifier/check", post(handlers::nullifier_check))
+        .route("/api/nullifier/record", post(handlers::nullifier_record))
+        // Swap in keys from disk without a restart (bearer token from ADMIN_TOKEN)
+        .route("/admin/reload-keys", post(handlers::reload_keys))
+        .merge(utility_routes())
+}
+
+/// Routes that do not need circuit keys
+pub fn utility_routes<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new()
         // Utility endpoints
         .route("/api/commitment/create", post(handlers::create_commitment))
         .route("/api/blinding/generate", post(handlers::generate_blinding))
+        // Per-section constraint counts (static per circuit)
+        .route("/api/circuits/:kind/breakdown", get(handlers::circuit_breakdown))
+}
+
+/// Apply request limits from the server configuration.
+///
+/// Bodies larger than `max_body_bytes` are rejected with 413 Payload Too Large.
+pub fn with_limits<S: Clone + Send + Sync + 'static>(
+    router: Router<S>,
+    config: &ServerConfig,
+) -> Router<S> {
+    router.layer(DefaultBodyLimit::max(config.max_body_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use tower::ServiceExt;
+
+    use ark_bn254::Fr;
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use inventory_prover::{
+        prove, setup, setup_all_circuits, CircuitKeys, InventoryState, OpType, ProofWithInputs,
+    };
+
+    use crate::config::EpochPolicy;
+    use crate::health::ServerHealth;
+    use crate::metrics::Metrics;
+    use crate::storage::InMemoryStorage;
+
+    fn commitment_request(body: String) -> Request<Body> {
+        Request::post("/api/commitment/create")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    fn commitment_body(num_items: usize) -> String {
+        let items: Vec<String> = (0..num_items)
+            .map(|i| format!(r#"{{"item_id":{},"quantity":1}}"#, i))
+            .collect();
+        format!(
+            r#"{{"inventory":[{}],"current_volume":0,"blinding":"0x{}"}}"#,
+            items.join(","),
+            "00".repeat(32)
+        )
+    }
+
+    #[tokio::test]
+    async fn test_body_within_limit_accepted() {
+        let config = ServerConfig { max_body_bytes: 1024, ..Default::default() };
+        let app = with_limits(utility_routes::<()>(), &config);
+
+        let response = app.oneshot(commitment_request(commitment_body(1))).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_non_canonical_blinding_rejected() {
+        let app = with_limits(utility_routes::<()>(), &ServerConfig::default());
+
+        // 2^256 - 1 is not below the field modulus
+        let body = format!(
+            r#"{{"inventory":[],"current_volume":0,"blinding":"0x{}"}}"#,
+            "ff".repeat(32)
+        );
+        let response = app.oneshot(commitment_request(body)).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_rejected() {
+        let config = ServerConfig { max_body_bytes: 1024, ..Default::default() };
+        let app = with_limits(utility_routes::<()>(), &config);
+
+        let body = commitment_body(100);
+        assert!(body.len() > config.max_body_bytes);
+
+        let response = app.oneshot(commitment_request(body)).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_withdraw_breakdown_sums_to_total() {
+        let app = utility_routes::<()>();
+
+        let request = Request::get("/api/circuits/state_transition/breakdown")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let breakdown: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let sections = breakdown["sections"].as_array().unwrap();
+        let sum: u64 = sections.iter().map(|s| s["constraints"].as_u64().unwrap()).sum();
+
+        assert_eq!(breakdown["circuit"], "state_transition");
+        assert_eq!(sum, breakdown["total_constraints"].as_u64().unwrap());
+        assert!(sections.iter().any(|s| s["section"] == "state_transition::volume"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_circuit_breakdown_rejected() {
+        let request = Request::get("/api/circuits/teleport/breakdown").body(Body::empty()).unwrap();
+        let response = utility_routes::<()>().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    fn app_state(keys: CircuitKeys) -> Arc<RwLock<AppState>> {
+        Arc::new(RwLock::new(AppState {
+            keys: Arc::new(keys),
+            keys_dir: "keys".into(),
+            admin_token: None,
+            proof_timeout: std::time::Duration::from_secs(60),
+            max_batch_size: 4,
+            epoch_policy: EpochPolicy { epoch_secs: 3600, window: 1 },
+            health: ServerHealth::Healthy,
+            metrics: Arc::new(Metrics::default()),
+            storage: Arc::new(InMemoryStorage::default()),
+        }))
+    }
+
+    fn hex_fr(value: &Fr) -> String {
+        let mut bytes = Vec::new();
+        value.serialize_compressed(&mut bytes).unwrap();
+        format!("0x{}", hex::encode(bytes))
+    }
+
+    async fn post_json(
+        state: &Arc<RwLock<AppState>>,
+        uri: &str,
+        body: serde_json::Value,
+    ) -> (StatusCode, serde_json::Value) {
+        let request = Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = api_routes().with_state(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_verify_by_statement() {
+        let keys = setup_all_circuits().unwrap();
+
+        let mut inventory = InventoryState::new(Fr::from(12345u64));
+        inventory.tree.update(1, 100);
+        inventory.current_volume = 1000;
+
+        let item_exists =
+            prove::prove_item_exists(&keys.item_exists.proving_key, &inventory, 1, 50).unwrap();
+        let withdraw = prove::prove_state_transition(
+            &keys.state_transition.proving_key,
+            &inventory,
+            Fr::from(67890u64),
+            1,
+            30,
+            10,
+            Fr::from(99999u64),
+            10000,
+            0,
+            Fr::from(12345678u64),
+            OpType::Withdraw,
+        )
+        .unwrap();
+        let state = app_state(keys);
+
+        let proof_hex = |proof: &ProofWithInputs| {
+            format!("0x{}", hex::encode(proof.serialize_proof().unwrap()))
+        };
+        let item_exists_body = |proof: String, min_quantity: u64| {
+            serde_json::json!({
+                "proof": proof,
+                "commitment": hex_fr(&inventory.commitment()),
+                "item_id": 1,
+                "min_quantity": min_quantity,
+            })
+        };
+
+        let uri = "/api/verify/item-exists";
+        let valid = item_exists_body(proof_hex(&item_exists), 50);
+        assert_eq!(
+            post_json(&state, uri, valid).await,
+            (StatusCode::OK, serde_json::json!({ "valid": true }))
+        );
+        // Well formed, but claims more than was proven
+        let stronger = item_exists_body(proof_hex(&item_exists), 100);
+        assert_eq!(
+            post_json(&state, uri, stronger).await,
+            (StatusCode::OK, serde_json::json!({ "valid": false }))
+        );
+        let (status, body) = post_json(&state, uri, item_exists_body("0x00".into(), 50)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].is_string());
+
+        let signal = &withdraw.signal;
+        let transition = serde_json::json!({
+            "proof": proof_hex(&withdraw.proof),
+            "old_commitment": hex_fr(&signal.old_commitment),
+            "new_commitment": hex_fr(&signal.new_commitment),
+            "registry_root": hex_fr(&signal.registry_root),
+            "max_capacity": signal.max_capacity,
+            "item_id": signal.item_id,
+            "amount": signal.amount,
+            "nonce": signal.nonce,
+            "inventory_id": hex_fr(&signal.inventory_id),
+        });
+        assert_eq!(
+            post_json(&state, "/api/verify/withdraw", transition.clone()).await,
+            (StatusCode::OK, serde_json::json!({ "valid": true }))
+        );
+        // The operation type is part of the statement
+        assert_eq!(
+            post_json(&state, "/api/verify/deposit", transition).await,
+            (StatusCode::OK, serde_json::json!({ "valid": false }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prove_batch() {
+        let state = app_state(setup_all_circuits().unwrap());
+        let blinding = hex_fr(&Fr::from(12345u64));
+
+        let deposit = |item_id: u64, nonce: u64| {
+            serde_json::json!({
+                "type": "deposit",
+                "inventory": [{ "item_id": 1, "quantity": 100 }],
+                "current_volume": 1000,
+                "old_blinding": blinding,
+                "new_blinding": hex_fr(&Fr::from(67890u64)),
+                "item_id": item_id,
+                "amount": 5,
+                "item_volume": 10,
+                "registry_root": hex_fr(&Fr::from(99999u64)),
+                "max_capacity": 10000,
+                "nonce": nonce,
+                "inventory_id": hex_fr(&Fr::from(12345678u64)),
+            })
+        };
+        let body = serde_json::json!([
+            deposit(1, 0),
+            {
+                "type": "item-exists",
+                "inventory": [{ "item_id": 1, "quantity": 100 }],
+                "current_volume": 1000,
+                "blinding": blinding,
+                "item_id": 1,
+                "min_quantity": 50,
+            },
+            deposit(2, 1),
+            { "type": "transfer" },
+        ]);
+
+        let (status, results) = post_json(&state, "/api/prove/batch", body).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let results = results.as_array().unwrap();
+        assert_eq!(results.len(), 4);
+        for result in &results[..3] {
+            assert!(result["proof"].as_str().unwrap().starts_with("0x"), "{}", result);
+        }
+        // Results stay in request order
+        assert_eq!(results[0]["new_volume"], 1050);
+        assert_eq!(results[1]["public_inputs"].as_array().unwrap().len(), 1);
+        assert_eq!(results[2]["nonce"], 1);
+        assert!(results[3]["error"].as_str().unwrap().contains("transfer"));
+
+        // One more than max_batch_size is rejected before anything is proven
+        let body = serde_json::Value::Array(vec![deposit(1, 0); 5]);
+        let (status, body) = post_json(&state, "/api/prove/batch", body).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(body["error"].as_str().unwrap().contains("limit of 4"));
+    }
+
+    #[tokio::test]
+    async fn test_proof_recorded_in_metrics() {
+        let state = app_state(setup_all_circuits().unwrap());
+
+        let body = serde_json::json!({
+            "inventory": [{ "item_id": 1, "quantity": 100 }],
+            "current_volume": 1000,
+            "blinding": hex_fr(&Fr::from(12345u64)),
+            "item_id": 1,
+            "min_quantity": 50,
+        });
+        let (status, _) = post_json(&state, "/api/prove/item-exists", body).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let storage = state.read().await.storage.clone();
+        storage.insert_leaf(3, Fr::from(42u64)).await.unwrap();
+        for nullifier in [7u64, 8] {
+            storage.record_nullifier(Fr::from(nullifier)).await.unwrap();
+        }
+
+        let response = api_routes()
+            .with_state(state)
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("inventory_proofs_generated_total{circuit=\"item_exists\"} 1\n"));
+        assert!(text.contains("inventory_proving_seconds_count{circuit=\"item_exists\"} 1\n"));
+        assert!(text.contains("inventory_proofs_in_flight 0\n"));
+        assert!(text.contains("inventory_registry_leaves 1\n"));
+        assert!(text.contains("inventory_nullifiers 2\n"));
+    }
+
+    fn admin_request(uri: &str, token: Option<&str>, body: Body) -> Request<Body> {
+        let mut request = Request::post(uri).header(header::CONTENT_TYPE, "application/json");
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        request.body(body).unwrap()
+    }
+
+    fn reload_request(token: Option<&str>) -> Request<Body> {
+        admin_request("/admin/reload-keys", token, Body::empty())
+    }
+
+    #[tokio::test]
+    async fn test_storage_writes_require_admin_token() {
+        let state = app_state(setup_all_circuits().unwrap());
+        state.write().await.admin_token = Some("s3cret".into());
+        let app = api_routes().with_state(state.clone());
+
+        let nullifier = serde_json::json!({ "nullifier": hex_fr(&Fr::from(7u64)) });
+        let leaf = serde_json::json!({ "index": 3, "leaf": hex_fr(&Fr::from(42u64)) });
+        let writes = [("/api/registry/insert", leaf), ("/api/nullifier/record", nullifier.clone())];
+        let empty_root = state.read().await.storage.get_root().await.unwrap();
+
+        for (uri, body) in &writes {
+            for token in [None, Some("guess")] {
+                let request = admin_request(uri, token, Body::from(body.to_string()));
+                let response = app.clone().oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+            }
+        }
+        // Rejected writes changed nothing
+        assert_eq!(state.read().await.storage.get_root().await.unwrap(), empty_root);
+        let (_, check) = post_json(&state, "/api/nullifier/check", nullifier.clone()).await;
+        assert_eq!(check["spent"], false);
+
+        for (uri, body) in &writes {
+            let request = admin_request(uri, Some("s3cret"), Body::from(body.to_string()));
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+        }
+        assert_ne!(state.read().await.storage.get_root().await.unwrap(), empty_root);
+        let (_, check) = post_json(&state, "/api/nullifier/check", nullifier).await;
+        assert_eq!(check["spent"], true);
+    }
+
+    #[tokio::test]
+    async fn test_reload_keys() {
+        // Keys on disk from a different setup than the ones being served
+        let mut rng = StdRng::seed_from_u64(7);
+        let rotated = CircuitKeys {
+            state_transition: setup::setup_state_transition(&mut rng).unwrap(),
+            state_transition_epoch: setup::setup_state_transition_with_epoch(&mut rng).unwrap(),
+            item_exists: setup::setup_item_exists(&mut rng).unwrap(),
+            capacity: setup::setup_capacity(&mut rng).unwrap(),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        rotated.save_to_directory(dir.path()).unwrap();
+
+        let state = app_state(setup_all_circuits().unwrap());
+        let old_fingerprint = state.read().await.keys.fingerprint().unwrap();
+        let app = api_routes().with_state(state.clone());
+
+        // Disabled until a token is configured
+        let response = app.clone().oneshot(reload_request(Some("s3cret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        state.write().await.keys_dir = dir.path().to_path_buf();
+        state.write().await.admin_token = Some("s3cret".into());
+        for token in [None, Some("guess")] {
+            let response = app.clone().oneshot(reload_request(token)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        assert_eq!(state.read().await.keys.fingerprint().unwrap(), old_fingerprint);
+
+        // The reload records the new keys' self-test result
+        state.write().await.health = ServerHealth::Starting;
+        let response = app.oneshot(reload_request(Some("s3cret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.read().await.health, ServerHealth::Healthy);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let fingerprint = rotated.fingerprint().unwrap();
+        assert_eq!(body["fingerprint"], fingerprint);
+        assert_ne!(fingerprint, old_fingerprint);
+
+        // Proofs made after the reload verify
+        let blinding = Fr::from(12345u64);
+        let mut inventory = InventoryState::new(blinding);
+        inventory.tree.update(1, 100);
+        inventory.current_volume = 1000;
+
+        let request = serde_json::json!({
+            "inventory": [{ "item_id": 1, "quantity": 100 }],
+            "current_volume": 1000,
+            "blinding": hex_fr(&blinding),
+            "item_id": 1,
+            "min_quantity": 50,
+        });
+        let (status, proof) = post_json(&state, "/api/prove/item-exists", request).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let statement = serde_json::json!({
+            "proof": proof["proof"],
+            "commitment": hex_fr(&inventory.commitment()),
+            "item_id": 1,
+            "min_quantity": 50,
+        });
+        assert_eq!(
+            post_json(&state, "/api/verify/item-exists", statement).await,
+            (StatusCode::OK, serde_json::json!({ "valid": true }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reload_rejects_keys_failing_self_test() {
+        // A keys directory whose item-exists verifying key is from another setup
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut mismatched = setup_all_circuits().unwrap();
+        mismatched.item_exists.verifying_key =
+            setup::setup_item_exists(&mut rng).unwrap().verifying_key;
+        let dir = tempfile::tempdir().unwrap();
+        mismatched.save_to_directory(dir.path()).unwrap();
+
+        let state = app_state(setup_all_circuits().unwrap());
+        let old_fingerprint = state.read().await.keys.fingerprint().unwrap();
+        state.write().await.keys_dir = dir.path().to_path_buf();
+        state.write().await.admin_token = Some("s3cret".into());
+
+        let app = api_routes().with_state(state.clone());
+        let response = app.oneshot(reload_request(Some("s3cret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(body["error"].as_str().unwrap().contains("item_exists self-test failed"));
+
+        // The old keys and their health stay in place
+        assert_eq!(state.read().await.keys.fingerprint().unwrap(), old_fingerprint);
+        assert_eq!(state.read().await.health, ServerHealth::Healthy);
+    }
+
+    fn envelope(circuit: &str, proof: &ProofWithInputs) -> serde_json::Value {
+        let public_inputs: Vec<String> = proof.public_inputs.iter().map(hex_fr).collect();
+
+        serde_json::json!({
+            "circuit": circuit,
+            "proof": format!("0x{}", hex::encode(proof.serialize_proof().unwrap())),
+            "public_inputs": public_inputs,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_bulk_verify_reports_each_proof() {
+        let keys = setup_all_circuits().unwrap();
+
+        let mut inventory = InventoryState::new(Fr::from(12345u64));
+        inventory.tree.update(1, 100);
+        inventory.current_volume = 1000;
+
+        let item_exists =
+            prove::prove_item_exists(&keys.item_exists.proving_key, &inventory, 1, 50).unwrap();
+        let mut withdraw = prove::prove_state_transition(
+            &keys.state_transition.proving_key,
+            &inventory,
+            Fr::from(67890u64),
+            1,
+            30,
+            10,
+            Fr::from(99999u64),
+            10000,
+            0,
+            Fr::from(12345678u64),
+            OpType::Withdraw,
+        )
+        .unwrap()
+        .proof;
+        // Claim a different nonce than the proof was made for
+        withdraw.public_inputs[1] = Fr::from(1u64);
+
+        let state = app_state(keys);
+        let app = api_routes().with_state(state.clone());
+
+        // A valid proof in an envelope format from the future
+        let mut future = envelope("item_exists", &item_exists);
+        future["version"] = serde_json::json!(2);
+        // Even one that would not fit a byte only fails its own envelope
+        let mut far_future = envelope("item_exists", &item_exists);
+        far_future["version"] = serde_json::json!(256);
+
+        // Envelopes without a version are read as version 1
+        let body = serde_json::json!([
+            envelope("item_exists", &item_exists),
+            envelope("state_transition", &withdraw),
+            { "circuit": "capacity", "proof": "0x00", "public_inputs": [] },
+            future,
+            far_future,
+        ]);
+        let request = Request::post("/api/verify/bulk")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let results: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(results[0], serde_json::json!({ "index": 0, "valid": true }));
+        assert_eq!(results[1], serde_json::json!({ "index": 1, "valid": false }));
+        assert_eq!(results[2]["valid"], false);
+        assert!(results[2]["error"].is_string());
+        assert_eq!(results[3]["valid"], false);
+        assert!(results[3]["error"].as_str().unwrap().contains("Unsupported envelope version 2"));
+        assert_eq!(results[4]["valid"], false);
+        assert!(results[4]["error"].as_str().unwrap().contains("Unsupported envelope version 256"));
+
+        let response = api_routes()
+            .with_state(state)
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        let verified = "inventory_proofs_verified_total";
+        assert!(text.contains(&format!(
+            "{}{{circuit=\"item_exists\",result=\"valid\"}} 1\n",
+            verified
+        )));
+        assert!(text.contains(&format!(
+            "{}{{circuit=\"state_transition\",result=\"invalid\"}} 1\n",
+            verified
+        )));
+    }
 }
@@ -0,0 +1,336 @@
+//! Storage for server-side state that must outlive a request: the registry
+//! tree and the set of spent nullifiers.
+//!
+//! Handlers only see the [`StorageBackend`] trait object in `AppState`, so a
+//! backend shared between instances (Redis, Postgres, ...) can be plugged in
+//! by implementing the trait. [`FileStorage`] persists both to a directory so
+//! spent nullifiers survive a restart; [`InMemoryStorage`] keeps everything in
+//! process memory and is only meant for tests and throwaway servers.
+
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ark_bn254::Fr;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use async_trait::async_trait;
+use inventory_circuits::smt::{SparseMerkleTree, DEFAULT_DEPTH};
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+/// Errors reported by a storage backend.
+#[derive(Debug, Error)]
+pub enum StorageError {
+    /// Leaf index does not fit in the registry tree
+    #[error("leaf index {index} exceeds registry capacity {capacity}")]
+    IndexOutOfRange { index: u64, capacity: u64 },
+    /// The backend itself failed (connection lost, query error, ...)
+    #[error("storage backend error: {0}")]
+    Backend(String),
+}
+
+/// Registry and nullifier storage shared by all handlers.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Current root of the registry tree.
+    async fn get_root(&self) -> Result<Fr, StorageError>;
+
+    /// Number of leaves set in the registry tree.
+    async fn leaf_count(&self) -> Result<u64, StorageError>;
+
+    /// Number of nullifiers recorded.
+    async fn nullifier_count(&self) -> Result<u64, StorageError>;
+
+    /// Set the registry leaf at `index`, replacing any previous leaf.
+    /// Returns the new root.
+    async fn insert_leaf(&self, index: u64, leaf: Fr) -> Result<Fr, StorageError>;
+
+    /// Whether `nullifier` has been recorded.
+    async fn has_nullifier(&self, nullifier: Fr) -> Result<bool, StorageError>;
+
+    /// Record `nullifier` as spent.
+    ///
+    /// Recording is idempotent: returns `true` if the nullifier is new and
+    /// `false` if it was already recorded.
+    async fn record_nullifier(&self, nullifier: Fr) -> Result<bool, StorageError>;
+}
+
+/// A registry SMT and nullifier set in process memory.
+///
+/// Nothing is persisted, so all state is lost on restart and spent nullifiers
+/// would be accepted again. Use [`FileStorage`] for a real deployment.
+pub struct InMemoryStorage {
+    registry: RwLock<SparseMerkleTree>,
+    nullifiers: RwLock<HashSet<Fr>>,
+}
+
+impl InMemoryStorage {
+    /// Empty storage with a registry tree of the given depth.
+    pub fn new(depth: usize) -> Self {
+        Self {
+            registry: RwLock::new(SparseMerkleTree::new(depth)),
+            nullifiers: RwLock::new(HashSet::new()),
+        }
+    }
+}
+
+impl Default for InMemoryStorage {
+    fn default() -> Self {
+        Self::new(DEFAULT_DEPTH)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for InMemoryStorage {
+    async fn get_root(&self) -> Result<Fr, StorageError> {
+        Ok(self.registry.read().await.root())
+    }
+
+    async fn leaf_count(&self) -> Result<u64, StorageError> {
+        Ok(self.registry.read().await.raw_leaf_count() as u64)
+    }
+
+    async fn nullifier_count(&self) -> Result<u64, StorageError> {
+        Ok(self.nullifiers.read().await.len() as u64)
+    }
+
+    async fn insert_leaf(&self, index: u64, leaf: Fr) -> Result<Fr, StorageError> {
+        let mut registry = self.registry.write().await;
+        check_index(&registry, index)?;
+
+        Ok(registry.update_leaf_hash(index, leaf))
+    }
+
+    async fn has_nullifier(&self, nullifier: Fr) -> Result<bool, StorageError> {
+        Ok(self.nullifiers.read().await.contains(&nullifier))
+    }
+
+    async fn record_nullifier(&self, nullifier: Fr) -> Result<bool, StorageError> {
+        Ok(self.nullifiers.write().await.insert(nullifier))
+    }
+}
+
+/// Registry file inside a [`FileStorage`] directory
+const REGISTRY_FILE: &str = "registry.bin";
+
+/// Spent-nullifier file inside a [`FileStorage`] directory
+const NULLIFIERS_FILE: &str = "nullifiers.bin";
+
+/// Registry SMT and nullifier set persisted to a directory.
+///
+/// Every write is saved before it is acknowledged, so a restarted server
+/// still rejects nullifiers spent before the restart. Files are replaced
+/// through a rename, leaving the previous state intact if a save fails.
+pub struct FileStorage {
+    dir: PathBuf,
+    registry: RwLock<SparseMerkleTree>,
+    nullifiers: RwLock<HashSet<Fr>>,
+}
+
+impl FileStorage {
+    /// Open the storage in `dir`, creating it empty if nothing is saved there.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, StorageError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(backend_error)?;
+
+        let registry_path = dir.join(REGISTRY_FILE);
+        let registry = if registry_path.exists() {
+            SparseMerkleTree::load_from_file(&registry_path).map_err(backend_error)?
+        } else {
+            SparseMerkleTree::new(DEFAULT_DEPTH)
+        };
+
+        let nullifiers_path = dir.join(NULLIFIERS_FILE);
+        let nullifiers = if nullifiers_path.exists() {
+            let bytes = std::fs::read(&nullifiers_path).map_err(backend_error)?;
+            Vec::<Fr>::deserialize_compressed(&bytes[..])
+                .map_err(|e| StorageError::Backend(e.to_string()))?
+                .into_iter()
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        Ok(Self {
+            dir,
+            registry: RwLock::new(registry),
+            nullifiers: RwLock::new(nullifiers),
+        })
+    }
+
+    fn save_registry(&self, registry: &SparseMerkleTree) -> Result<(), StorageError> {
+        replace_file(&self.dir.join(REGISTRY_FILE), |tmp| registry.save_to_file(tmp))
+    }
+
+    fn save_nullifiers(&self, nullifiers: &HashSet<Fr>) -> Result<(), StorageError> {
+        let mut bytes = Vec::new();
+        nullifiers
+            .iter()
+            .copied()
+            .collect::<Vec<Fr>>()
+            .serialize_compressed(&mut bytes)
+            .map_err(|e| StorageError::Backend(e.to_string()))?;
+
+        replace_file(&self.dir.join(NULLIFIERS_FILE), |tmp| std::fs::write(tmp, &bytes))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for FileStorage {
+    async fn get_root(&self) -> Result<Fr, StorageError> {
+        Ok(self.registry.read().await.root())
+    }
+
+    async fn leaf_count(&self) -> Result<u64, StorageError> {
+        Ok(self.registry.read().await.raw_leaf_count() as u64)
+    }
+
+    async fn nullifier_count(&self) -> Result<u64, StorageError> {
+        Ok(self.nullifiers.read().await.len() as u64)
+    }
+
+    async fn insert_leaf(&self, index: u64, leaf: Fr) -> Result<Fr, StorageError> {
+        let mut registry = self.registry.write().await;
+        check_index(&registry, index)?;
+
+        // Only take the new leaf once it is on disk
+        let mut updated = registry.clone();
+        let root = updated.update_leaf_hash(index, leaf);
+        self.save_registry(&updated)?;
+        *registry = updated;
+        Ok(root)
+    }
+
+    async fn has_nullifier(&self, nullifier: Fr) -> Result<bool, StorageError> {
+        Ok(self.nullifiers.read().await.contains(&nullifier))
+    }
+
+    async fn record_nullifier(&self, nullifier: Fr) -> Result<bool, StorageError> {
+        let mut nullifiers = self.nullifiers.write().await;
+        if !nullifiers.insert(nullifier) {
+            return Ok(false);
+        }
+
+        if let Err(e) = self.save_nullifiers(&nullifiers) {
+            nullifiers.remove(&nullifier);
+            return Err(e);
+        }
+        Ok(true)
+    }
+}
+
+/// Reject an index outside the registry tree.
+fn check_index(registry: &SparseMerkleTree, index: u64) -> Result<(), StorageError> {
+    let capacity = 1u64 << registry.depth();
+    if index >= capacity {
+        return Err(StorageError::IndexOutOfRange { index, capacity });
+    }
+    Ok(())
+}
+
+/// Write `path` through a temporary file renamed over it.
+fn replace_file(
+    path: &Path,
+    write: impl FnOnce(&Path) -> io::Result<()>,
+) -> Result<(), StorageError> {
+    let tmp = path.with_extension("tmp");
+    write(&tmp).and_then(|()| std::fs::rename(&tmp, path)).map_err(backend_error)
+}
+
+fn backend_error(e: io::Error) -> StorageError {
+    StorageError::Backend(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use inventory_circuits::compute_gift_nullifier;
+
+    #[tokio::test]
+    async fn test_insert_and_lookup() {
+        let storage = InMemoryStorage::default();
+        let empty_root = storage.get_root().await.unwrap();
+
+        let root = storage.insert_leaf(3, Fr::from(42u64)).await.unwrap();
+        assert_ne!(root, empty_root);
+        assert_eq!(storage.get_root().await.unwrap(), root);
+
+        let mut expected = SparseMerkleTree::new(DEFAULT_DEPTH);
+        assert_eq!(root, expected.update_leaf_hash(3, Fr::from(42u64)));
+
+        // Replacing the leaf moves the root again
+        let replaced = storage.insert_leaf(3, Fr::from(43u64)).await.unwrap();
+        assert_ne!(replaced, root);
+        assert_eq!(replaced, expected.update_leaf_hash(3, Fr::from(43u64)));
+        assert_eq!(storage.leaf_count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_insert_out_of_range_rejected() {
+        let storage = InMemoryStorage::new(4);
+
+        let err = storage.insert_leaf(16, Fr::from(1u64)).await.unwrap_err();
+        assert!(matches!(err, StorageError::IndexOutOfRange { index: 16, capacity: 16 }));
+    }
+
+    #[tokio::test]
+    async fn test_record_nullifier_idempotent() {
+        let storage = InMemoryStorage::default();
+        let nullifier = Fr::from(7u64);
+
+        assert!(!storage.has_nullifier(nullifier).await.unwrap());
+        assert!(storage.record_nullifier(nullifier).await.unwrap());
+        assert!(storage.has_nullifier(nullifier).await.unwrap());
+
+        // Recording again is a no-op that reports the nullifier as already spent
+        assert!(!storage.record_nullifier(nullifier).await.unwrap());
+        assert!(storage.has_nullifier(nullifier).await.unwrap());
+        assert!(!storage.has_nullifier(Fr::from(8u64)).await.unwrap());
+        assert_eq!(storage.nullifier_count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_double_gift_rejected() {
+        // Two gifts from the same sender state carry the same nullifier
+        let sender_commitment = Fr::from(1234u64);
+        let sender_blinding = Fr::from(5678u64);
+        let storage = InMemoryStorage::default();
+
+        let first = compute_gift_nullifier(sender_commitment, sender_blinding);
+        let second = compute_gift_nullifier(sender_commitment, sender_blinding);
+
+        assert!(storage.record_nullifier(first).await.unwrap());
+        assert!(!storage.record_nullifier(second).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let nullifier = compute_gift_nullifier(Fr::from(1234u64), Fr::from(5678u64));
+
+        let storage = FileStorage::open(dir.path()).unwrap();
+        let empty_root = storage.get_root().await.unwrap();
+        assert_eq!(empty_root, SparseMerkleTree::new(DEFAULT_DEPTH).root());
+
+        let root = storage.insert_leaf(3, Fr::from(42u64)).await.unwrap();
+        assert!(storage.record_nullifier(nullifier).await.unwrap());
+        drop(storage);
+
+        // A restarted server sees the same registry and still rejects the nullifier
+        let storage = FileStorage::open(dir.path()).unwrap();
+        assert_eq!(storage.get_root().await.unwrap(), root);
+        assert!(storage.has_nullifier(nullifier).await.unwrap());
+        assert!(!storage.record_nullifier(nullifier).await.unwrap());
+        assert!(!storage.has_nullifier(Fr::from(8u64)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_out_of_range_not_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FileStorage::open(dir.path()).unwrap();
+
+        let err = storage.insert_leaf(1 << DEFAULT_DEPTH, Fr::from(1u64)).await.unwrap_err();
+        assert!(matches!(err, StorageError::IndexOutOfRange { .. }));
+        assert!(!dir.path().join(REGISTRY_FILE).exists());
+    }
+}
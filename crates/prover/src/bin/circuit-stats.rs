@@ -12,6 +12,7 @@ use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
 
 use inventory_circuits::{
     CapacitySMTCircuit,
+    CircuitMeta,
     ItemExistsSMTCircuit,
     StateTransitionCircuit,
     DEFAULT_DEPTH,
@@ -28,6 +29,23 @@ fn count_constraints<C: ConstraintSynthesizer<Fr>>(circuit: C, name: &str) -> us
     count
 }
 
+fn print_influence<C: CircuitMeta>(circuit: &C, name: &str) {
+    let influence = circuit.public_input_influence();
+    for input in 0..influence.len() {
+        let constraints = &influence[&input];
+        let first = constraints.iter().take(5).map(|c| c.to_string()).collect::<Vec<_>>();
+        let more = if constraints.len() > 5 { ", ..." } else { "" };
+        println!(
+            "{:25} input {}: {:>4} constraints [{}{}]",
+            name,
+            input,
+            constraints.len(),
+            first.join(", "),
+            more
+        );
+    }
+}
+
 fn main() {
     let args: Vec<String> = std::env::args().collect();
     let include_timing = args.iter().any(|a| a == "--time");
@@ -55,6 +73,14 @@ fn main() {
     let cap_circuit = CapacitySMTCircuit::empty();
     let cap_count = count_constraints(cap_circuit, "CapacityProof");
 
+    println!("\n─────────────────────────────────────────────────────────────");
+    println!("PUBLIC INPUT INFLUENCE:");
+    println!("─────────────────────────────────────────────────────────────\n");
+
+    print_influence(&StateTransitionCircuit::empty(), "StateTransition");
+    print_influence(&ItemExistsSMTCircuit::empty(), "ItemExists");
+    print_influence(&CapacitySMTCircuit::empty(), "CapacityProof");
+
     println!("\n─────────────────────────────────────────────────────────────");
     println!("SUMMARY:");
     println!("─────────────────────────────────────────────────────────────\n");
@@ -127,7 +153,7 @@ fn run_timing_benchmarks(keys_path: &Path) {
 
     // Constraint counts for efficiency calculation
     const CAP_CONSTRAINTS: u128 = 724;
-    const ITEM_CONSTRAINTS: u128 = 4124;
+    const ITEM_CONSTRAINTS: u128 = 4157;
     const STATE_CONSTRAINTS: u128 = 8255;
 
     println!("Circuit          Constraints    Avg Time    μs/constraint");
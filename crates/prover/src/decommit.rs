@@ -0,0 +1,72 @@
+//! Checked reconstruction of an inventory from its commitment opening.
+
+use ark_bn254::Fr;
+use thiserror::Error;
+
+use crate::prove::InventoryState;
+
+/// Errors when opening a commitment
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DecommitError {
+    #[error("Inventory does not match commitment")]
+    Mismatch,
+}
+
+/// Rebuild an inventory from its opening, returning it only if it matches `commitment`.
+///
+/// Use this instead of constructing an `InventoryState` directly whenever the
+/// items come from an untrusted source and a commitment is already known.
+pub fn decommit(
+    commitment: Fr,
+    items: &[(u64, u64)],
+    current_volume: u64,
+    blinding: Fr,
+) -> Result<InventoryState, DecommitError> {
+    let mut state = InventoryState::from_items(items, blinding);
+    state.current_volume = current_volume;
+
+    if state.commitment() != commitment {
+        return Err(DecommitError::Mismatch);
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn committed_state() -> InventoryState {
+        let mut state = InventoryState::from_items(&[(1, 100), (42, 5)], Fr::from(12345u64));
+        state.current_volume = 150;
+        state
+    }
+
+    #[test]
+    fn test_decommit_matching() {
+        let expected = committed_state();
+
+        let state = decommit(expected.commitment(), &[(1, 100), (42, 5)], 150, expected.blinding)
+            .unwrap();
+
+        assert_eq!(state.root(), expected.root());
+        assert_eq!(state.current_volume, 150);
+        assert_eq!(state.get_quantity(42), 5);
+    }
+
+    #[test]
+    fn test_decommit_wrong_items() {
+        let expected = committed_state();
+
+        let result = decommit(expected.commitment(), &[(1, 100), (42, 6)], 150, expected.blinding);
+        assert_eq!(result.err(), Some(DecommitError::Mismatch));
+    }
+
+    #[test]
+    fn test_decommit_wrong_blinding() {
+        let expected = committed_state();
+
+        let result = decommit(expected.commitment(), &[(1, 100), (42, 5)], 150, Fr::from(1u64));
+        assert_eq!(result.err(), Some(DecommitError::Mismatch));
+    }
+}
@@ -0,0 +1,33 @@
+//! Unified error type for the prover crate.
+
+use thiserror::Error;
+
+use crate::decommit::DecommitError;
+use crate::prove::ProveError;
+use crate::serialization::SerializationError;
+use crate::setup::SetupError;
+use crate::validate::ValidationError;
+use crate::verify::VerifyError;
+
+/// Any error returned by the prover's public API.
+///
+/// Each variant wraps the module-level error it came from, so callers can
+/// match on the stage that failed and still get the specific cause.
+#[derive(Error, Debug)]
+pub enum ProverError {
+    #[error(transparent)]
+    Setup(#[from] SetupError),
+    #[error(transparent)]
+    Prove(#[from] ProveError),
+    #[error(transparent)]
+    Verify(#[from] VerifyError),
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+    #[error(transparent)]
+    Decommit(#[from] DecommitError),
+    #[error(transparent)]
+    Serialization(#[from] SerializationError),
+}
+
+/// Result alias using [`ProverError`].
+pub type Result<T, E = ProverError> = std::result::Result<T, E>;
@@ -4,18 +4,45 @@
 //! - Trusted setup (generating proving and verifying keys)
 //! - Proof generation for SMT-based circuits
 //! - Local proof verification (for testing)
+//! - Byte encodings of proofs and verifying keys
+//! - Linking state transition proofs through shared commitments
+//! - Off-circuit validation of state transition requests
 
+pub mod decommit;
+pub mod error;
+pub mod linked;
 pub mod prove;
+pub mod public_inputs;
+pub mod serialization;
 pub mod setup;
+pub mod validate;
 pub mod verify;
 
+pub use decommit::{decommit, DecommitError};
+pub use error::{ProverError, Result};
+pub use linked::{verify_linked, LinkedProofs, ProofBundle};
 pub use inventory_circuits::signal::OpType;
 pub use prove::{
-    prove_capacity, prove_item_exists, prove_state_transition, InventoryState, ProofWithInputs,
+    prove_capacity, prove_item_exists, prove_state_transition, prove_state_transition_at_epoch,
+    prove_state_transition_from_commitment, InventoryState, ProofWithInputs, ProveError,
     StateTransitionResult,
 };
-pub use setup::{setup_all_circuits, CircuitKeys, CircuitKeyPair, SetupError};
-pub use verify::{verify_capacity, verify_item_exists, verify_state_transition};
+pub use public_inputs::{
+    CapacityPublicInputs, ItemExistsPublicInputs, StateTransitionPublicInputs,
+};
+pub use serialization::{
+    deserialize_proof, deserialize_verifying_key, serialize_proof, serialize_verifying_key,
+    SerializationError,
+};
+pub use setup::{
+    setup_all_circuits, CircuitKeyPair, CircuitKeys, KeyFootprint, SetupError, TypedProvingKey,
+    TypedVerifyingKey,
+};
+pub use validate::{validate_deposit_request, validate_withdraw_request, ValidationError};
+pub use verify::{
+    batch_verify, verify_capacity, verify_item_exists, verify_item_exists_batch,
+    verify_state_transition, VerifyError,
+};
 
 use ark_bn254::Fr;
 
@@ -0,0 +1,139 @@
+//! Linking state transition proofs without recursion.
+//!
+//! A state transition proof only exposes its signal hash, so on its own it does
+//! not say which commitments it moves between. A [`ProofBundle`] carries the
+//! signal inputs alongside the proof; checking that they hash to the proof's
+//! signal hash lets a verifier read the commitments, and [`verify_linked`] uses
+//! that to chain two transitions (e.g. a withdraw followed by a deposit).
+
+use inventory_circuits::{signal::SignalInputs, StateTransitionCircuit};
+
+use crate::error::ProverError;
+use crate::prove::{ProofWithInputs, StateTransitionResult};
+use crate::public_inputs::StateTransitionPublicInputs;
+use crate::setup::TypedVerifyingKey;
+use crate::verify::verify_state_transition;
+
+/// A state transition proof together with the signal inputs it commits to.
+#[derive(Clone)]
+pub struct ProofBundle {
+    pub proof: ProofWithInputs,
+    pub signal: SignalInputs,
+}
+
+impl From<&StateTransitionResult> for ProofBundle {
+    fn from(result: &StateTransitionResult) -> Self {
+        Self {
+            proof: result.proof.clone(),
+            signal: result.signal.clone(),
+        }
+    }
+}
+
+impl ProofBundle {
+    /// Verify the proof and that its public inputs match the signal inputs.
+    ///
+    /// Public inputs are (signal_hash, nonce, inventory_id, registry_root).
+    pub fn verify(
+        &self,
+        vk: &TypedVerifyingKey<StateTransitionCircuit>,
+    ) -> Result<bool, ProverError> {
+        let inputs = StateTransitionPublicInputs::from_vec(&self.proof.public_inputs)?;
+        if inputs != StateTransitionPublicInputs::from_signal(&self.signal, inputs.epoch) {
+            return Ok(false);
+        }
+
+        verify_state_transition(vk, &self.proof.proof, &inputs)
+    }
+}
+
+/// Two state transitions where the first's new commitment is the second's old one.
+#[derive(Clone)]
+pub struct LinkedProofs {
+    pub first: ProofBundle,
+    pub second: ProofBundle,
+}
+
+/// Verify both proofs and that they share the linking commitment.
+///
+/// Returns `Ok(false)` if either proof is invalid, a bundle's signal inputs do
+/// not match its proof, or the commitments do not chain.
+pub fn verify_linked(
+    vk: &TypedVerifyingKey<StateTransitionCircuit>,
+    linked: &LinkedProofs,
+) -> Result<bool, ProverError> {
+    if linked.first.signal.new_commitment != linked.second.signal.old_commitment {
+        return Ok(false);
+    }
+
+    Ok(linked.first.verify(vk)? && linked.second.verify(vk)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prove::{prove_state_transition, InventoryState};
+    use ark_bn254::Fr;
+    use crate::setup::{setup_state_transition, CircuitKeyPair};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use inventory_circuits::signal::OpType;
+
+    fn transition(
+        keys: &CircuitKeyPair<StateTransitionCircuit>,
+        state: &InventoryState,
+        amount: u64,
+        nonce: u64,
+        op_type: OpType,
+    ) -> StateTransitionResult {
+        prove_state_transition(
+            &keys.proving_key,
+            state,
+            Fr::from(1000 + nonce),
+            1,
+            amount,
+            10,
+            Fr::from(99999u64),
+            10000,
+            nonce,
+            Fr::from(12345678u64),
+            op_type,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verify_linked() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.tree.update(1, 100);
+        state.current_volume = 1000;
+
+        let withdraw = transition(&keys, &state, 30, 0, OpType::Withdraw);
+        let deposit = transition(&keys, &withdraw.new_state, 10, 1, OpType::Deposit);
+
+        let linked = LinkedProofs {
+            first: ProofBundle::from(&withdraw),
+            second: ProofBundle::from(&deposit),
+        };
+        assert!(verify_linked(&keys.verifying_key, &linked).unwrap());
+
+        // Deposit made from the original state does not continue the withdraw
+        let unlinked_deposit = transition(&keys, &state, 10, 1, OpType::Deposit);
+        let unlinked = LinkedProofs {
+            first: ProofBundle::from(&withdraw),
+            second: ProofBundle::from(&unlinked_deposit),
+        };
+        assert!(!verify_linked(&keys.verifying_key, &unlinked).unwrap());
+
+        // Claiming the link through edited signal inputs breaks the signal hash
+        let mut forged = ProofBundle::from(&unlinked_deposit);
+        forged.signal.old_commitment = withdraw.new_commitment;
+        let forged = LinkedProofs {
+            first: ProofBundle::from(&withdraw),
+            second: forged,
+        };
+        assert!(!verify_linked(&keys.verifying_key, &forged).unwrap());
+    }
+}
@@ -1,14 +1,18 @@
 //! Proof generation for SMT-based inventory circuits.
 
 use ark_bn254::{Bn254, Fr};
-use ark_groth16::{Groth16, Proof, ProvingKey};
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_groth16::{Groth16, Proof};
+use ark_serialize::CanonicalSerialize;
 use ark_snark::SNARK;
 use ark_std::rand::{rngs::StdRng, SeedableRng};
 use thiserror::Error;
 
+use crate::error::ProverError;
+use crate::setup::TypedProvingKey;
+
 use inventory_circuits::{
-    signal::OpType,
+    range_check::MAX_RANGE_VALUE,
+    signal::{OpType, SignalInputs},
     smt::{MerkleProof, SparseMerkleTree, DEFAULT_DEPTH},
     smt_commitment::create_smt_commitment,
     CapacitySMTCircuit, ItemExistsSMTCircuit, StateTransitionCircuit,
@@ -33,13 +37,9 @@ pub struct ProofWithInputs {
 }
 
 impl ProofWithInputs {
-    /// Serialize proof to bytes
+    /// Serialize proof to bytes (see [`crate::serialization::serialize_proof`])
     pub fn serialize_proof(&self) -> Result<Vec<u8>, ProveError> {
-        let mut bytes = Vec::new();
-        self.proof
-            .serialize_compressed(&mut bytes)
-            .map_err(|e| ProveError::Serialization(e.to_string()))?;
-        Ok(bytes)
+        Ok(crate::serialization::serialize_proof(&self.proof))
     }
 
     /// Serialize public inputs to bytes (each Fr is 32 bytes)
@@ -53,9 +53,10 @@ impl ProofWithInputs {
         Ok(bytes)
     }
 
-    /// Deserialize proof from bytes
+    /// Deserialize proof from bytes (see [`crate::serialization::deserialize_proof`])
     pub fn deserialize_proof(bytes: &[u8]) -> Result<Proof<Bn254>, ProveError> {
-        Proof::deserialize_compressed(bytes).map_err(|e| ProveError::Serialization(e.to_string()))
+        crate::serialization::deserialize_proof(bytes)
+            .map_err(|e| ProveError::Serialization(e.to_string()))
     }
 }
 
@@ -122,9 +123,7 @@ impl InventoryState {
         item_volume: u64,
         new_blinding: Fr,
     ) -> Result<(InventoryState, MerkleProof<Fr>), ProveError> {
-        let old_qty = self.get_quantity(item_id);
-        let new_qty = old_qty.checked_add(amount)
-            .ok_or_else(|| ProveError::InvalidState("Quantity overflow".into()))?;
+        let (new_qty, new_volume) = self.after_deposit(item_id, amount, item_volume)?;
 
         // Get proof before update
         let proof = self.get_proof(item_id);
@@ -133,11 +132,6 @@ impl InventoryState {
         let mut new_tree = self.tree.clone();
         new_tree.update(item_id, new_qty);
 
-        // Update volume
-        let volume_delta = amount * item_volume;
-        let new_volume = self.current_volume.checked_add(volume_delta)
-            .ok_or_else(|| ProveError::InvalidState("Volume overflow".into()))?;
-
         Ok((
             InventoryState {
                 tree: new_tree,
@@ -156,14 +150,7 @@ impl InventoryState {
         item_volume: u64,
         new_blinding: Fr,
     ) -> Result<(InventoryState, MerkleProof<Fr>), ProveError> {
-        let old_qty = self.get_quantity(item_id);
-        if old_qty < amount {
-            return Err(ProveError::InvalidState(format!(
-                "Insufficient quantity: have {}, need {}",
-                old_qty, amount
-            )));
-        }
-        let new_qty = old_qty - amount;
+        let (new_qty, new_volume) = self.after_withdraw(item_id, amount, item_volume)?;
 
         // Get proof before update
         let proof = self.get_proof(item_id);
@@ -172,10 +159,6 @@ impl InventoryState {
         let mut new_tree = self.tree.clone();
         new_tree.update(item_id, new_qty);
 
-        // Update volume
-        let volume_delta = amount * item_volume;
-        let new_volume = self.current_volume.saturating_sub(volume_delta);
-
         Ok((
             InventoryState {
                 tree: new_tree,
@@ -185,6 +168,46 @@ impl InventoryState {
             proof,
         ))
     }
+
+    /// New item quantity and total volume after a deposit.
+    fn after_deposit(
+        &self,
+        item_id: u64,
+        amount: u64,
+        item_volume: u64,
+    ) -> Result<(u64, u64), ProveError> {
+        let new_qty = self.get_quantity(item_id).checked_add(amount)
+            .ok_or_else(|| ProveError::InvalidState("Quantity overflow".into()))?;
+
+        let volume_delta = amount.checked_mul(item_volume)
+            .ok_or_else(|| ProveError::InvalidState("Volume overflow".into()))?;
+        let new_volume = self.current_volume.checked_add(volume_delta)
+            .ok_or_else(|| ProveError::InvalidState("Volume overflow".into()))?;
+
+        Ok((new_qty, new_volume))
+    }
+
+    /// New item quantity and total volume after a withdrawal.
+    fn after_withdraw(
+        &self,
+        item_id: u64,
+        amount: u64,
+        item_volume: u64,
+    ) -> Result<(u64, u64), ProveError> {
+        let old_qty = self.get_quantity(item_id);
+        if old_qty < amount {
+            return Err(ProveError::InvalidState(format!(
+                "Insufficient quantity: have {}, need {}",
+                old_qty, amount
+            )));
+        }
+
+        let new_volume = amount.checked_mul(item_volume)
+            .and_then(|volume_delta| self.current_volume.checked_sub(volume_delta))
+            .ok_or_else(|| ProveError::InvalidState("Volume underflow".into()))?;
+
+        Ok((old_qty - amount, new_volume))
+    }
 }
 
 /// Result of a state transition proof
@@ -198,6 +221,8 @@ pub struct StateTransitionResult {
     pub inventory_id: Fr,
     /// Registry root used in this proof (for on-chain verification)
     pub registry_root: Fr,
+    /// Inputs bound by the proof's signal hash
+    pub signal: SignalInputs,
 }
 
 /// Generate proof for StateTransitionCircuit (deposit or withdraw)
@@ -214,10 +239,127 @@ pub struct StateTransitionResult {
 /// * `nonce` - Current inventory nonce (must match on-chain, for replay protection)
 /// * `inventory_id` - Inventory object ID as field element (must match on-chain)
 /// * `op_type` - Deposit or Withdraw
+///
+/// The resulting quantity and volume must not exceed `MAX_RANGE_VALUE` (32 bits),
+/// the range the circuit checks; larger values return `ProveError::InvalidState`.
 #[allow(clippy::too_many_arguments)]
 pub fn prove_state_transition(
-    pk: &ProvingKey<Bn254>,
+    pk: &TypedProvingKey<StateTransitionCircuit>,
+    old_state: &InventoryState,
+    new_blinding: Fr,
+    item_id: u64,
+    amount: u64,
+    item_volume: u64,
+    registry_root: Fr,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    op_type: OpType,
+) -> Result<StateTransitionResult, ProverError> {
+    prove_transition(
+        pk,
+        old_state,
+        old_state.commitment(),
+        new_blinding,
+        item_id,
+        amount,
+        item_volume,
+        registry_root,
+        max_capacity,
+        nonce,
+        inventory_id,
+        op_type,
+        None,
+    )
+}
+
+/// Generate a StateTransitionCircuit proof tagged with `epoch`.
+///
+/// Takes the same arguments as [`prove_state_transition`], but needs keys set up
+/// with an epoch (see `setup_state_transition_with_epoch`). The epoch is
+/// appended as a fifth public input.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_state_transition_at_epoch(
+    pk: &TypedProvingKey<StateTransitionCircuit>,
+    old_state: &InventoryState,
+    new_blinding: Fr,
+    item_id: u64,
+    amount: u64,
+    item_volume: u64,
+    registry_root: Fr,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    op_type: OpType,
+    epoch: u64,
+) -> Result<StateTransitionResult, ProverError> {
+    prove_transition(
+        pk,
+        old_state,
+        old_state.commitment(),
+        new_blinding,
+        item_id,
+        amount,
+        item_volume,
+        registry_root,
+        max_capacity,
+        nonce,
+        inventory_id,
+        op_type,
+        Some(epoch),
+    )
+}
+
+/// Generate a StateTransitionCircuit proof for a state whose commitment the
+/// caller already holds, e.g. the one stored on-chain.
+///
+/// Takes the same arguments as [`prove_state_transition`] plus
+/// `old_commitment`, which is used as is instead of being recomputed from
+/// `old_state`. The state must open to it: debug builds assert this, and
+/// otherwise the proof does not verify.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_state_transition_from_commitment(
+    pk: &TypedProvingKey<StateTransitionCircuit>,
+    old_state: &InventoryState,
+    old_commitment: Fr,
+    new_blinding: Fr,
+    item_id: u64,
+    amount: u64,
+    item_volume: u64,
+    registry_root: Fr,
+    max_capacity: u64,
+    nonce: u64,
+    inventory_id: Fr,
+    op_type: OpType,
+) -> Result<StateTransitionResult, ProverError> {
+    debug_assert_eq!(
+        old_state.commitment(),
+        old_commitment,
+        "Inventory state does not open to the given commitment"
+    );
+
+    prove_transition(
+        pk,
+        old_state,
+        old_commitment,
+        new_blinding,
+        item_id,
+        amount,
+        item_volume,
+        registry_root,
+        max_capacity,
+        nonce,
+        inventory_id,
+        op_type,
+        None,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn prove_transition(
+    pk: &TypedProvingKey<StateTransitionCircuit>,
     old_state: &InventoryState,
+    old_commitment: Fr,
     new_blinding: Fr,
     item_id: u64,
     amount: u64,
@@ -227,7 +369,8 @@ pub fn prove_state_transition(
     nonce: u64,
     inventory_id: Fr,
     op_type: OpType,
-) -> Result<StateTransitionResult, ProveError> {
+    epoch: Option<u64>,
+) -> Result<StateTransitionResult, ProverError> {
     // Get old quantities and proof
     let old_quantity = old_state.get_quantity(item_id);
     let inventory_proof = old_state.get_proof(item_id);
@@ -237,7 +380,8 @@ pub fn prove_state_transition(
         OpType::Deposit => {
             let new_qty = old_quantity.checked_add(amount)
                 .ok_or_else(|| ProveError::InvalidState("Quantity overflow".into()))?;
-            let volume_delta = amount * item_volume;
+            let volume_delta = amount.checked_mul(item_volume)
+                .ok_or_else(|| ProveError::InvalidState("Volume overflow".into()))?;
             let new_vol = old_state.current_volume.checked_add(volume_delta)
                 .ok_or_else(|| ProveError::InvalidState("Volume overflow".into()))?;
             // max_capacity of 0 means unlimited
@@ -245,7 +389,7 @@ pub fn prove_state_transition(
                 return Err(ProveError::InvalidState(format!(
                     "Capacity exceeded: {} > {}",
                     new_vol, max_capacity
-                )));
+                )).into());
             }
             (new_qty, new_vol)
         }
@@ -254,15 +398,25 @@ pub fn prove_state_transition(
                 return Err(ProveError::InvalidState(format!(
                     "Insufficient quantity: have {}, need {}",
                     old_quantity, amount
-                )));
+                )).into());
             }
             let new_qty = old_quantity - amount;
-            let volume_delta = amount * item_volume;
-            let new_vol = old_state.current_volume.saturating_sub(volume_delta);
+            let new_vol = amount.checked_mul(item_volume)
+                .and_then(|volume_delta| old_state.current_volume.checked_sub(volume_delta))
+                .ok_or_else(|| ProveError::InvalidState("Volume underflow".into()))?;
             (new_qty, new_vol)
         }
     };
 
+    // The circuit range checks quantities and volumes to 32 bits; anything
+    // larger would only produce a proof that fails verification
+    if new_quantity > MAX_RANGE_VALUE || new_volume > MAX_RANGE_VALUE {
+        return Err(ProveError::InvalidState(format!(
+            "Quantity {} or volume {} exceeds the supported maximum {}",
+            new_quantity, new_volume, MAX_RANGE_VALUE
+        )).into());
+    }
+
     // Create new tree state
     let mut new_tree = old_state.tree.clone();
     new_tree.update(item_id, new_quantity);
@@ -274,27 +428,36 @@ pub fn prove_state_transition(
     };
 
     let new_commitment = new_state.commitment();
+    let signal = SignalInputs {
+        old_commitment,
+        new_commitment,
+        registry_root,
+        max_capacity,
+        item_id,
+        amount,
+        op_type,
+        nonce,
+        inventory_id,
+    };
 
-    // Create circuit with all security parameters
-    let circuit = StateTransitionCircuit::new(
+    // Create circuit with all security parameters; the commitments come from
+    // `signal` rather than being hashed again
+    let mut circuit = StateTransitionCircuit::from_signal(
+        &signal,
         old_state.tree.root(),
         old_state.current_volume,
         old_state.blinding,
         new_state.tree.root(),
         new_volume,
         new_blinding,
-        item_id,
         old_quantity,
         new_quantity,
-        amount,
-        op_type,
         inventory_proof,
         item_volume,
-        registry_root,
-        max_capacity,
-        nonce,
-        inventory_id,
     );
+    if let Some(epoch) = epoch {
+        circuit = circuit.with_epoch(epoch);
+    }
 
     let signal_hash = circuit.signal_hash.unwrap();
 
@@ -304,34 +467,38 @@ pub fn prove_state_transition(
         .map_err(|e| ProveError::ProofGeneration(e.to_string()))?;
 
     // Return all 4 public inputs for on-chain verification
-    // Order: signal_hash, nonce, inventory_id, registry_root
+    // Order: signal_hash, nonce, inventory_id, registry_root (, epoch)
+    let mut public_inputs = vec![signal_hash, Fr::from(nonce), inventory_id, registry_root];
+    public_inputs.extend(epoch.map(Fr::from));
+
     Ok(StateTransitionResult {
         proof: ProofWithInputs {
             proof,
-            public_inputs: vec![signal_hash, Fr::from(nonce), inventory_id, registry_root],
+            public_inputs,
         },
         new_state,
         new_commitment,
         nonce,
         inventory_id,
         registry_root,
+        signal,
     })
 }
 
 /// Generate proof for ItemExistsSMTCircuit
 pub fn prove_item_exists(
-    pk: &ProvingKey<Bn254>,
+    pk: &TypedProvingKey<ItemExistsSMTCircuit>,
     state: &InventoryState,
     item_id: u64,
     min_quantity: u64,
-) -> Result<ProofWithInputs, ProveError> {
+) -> Result<ProofWithInputs, ProverError> {
     // Get actual quantity and proof
     let actual_quantity = state.get_quantity(item_id);
     if actual_quantity < min_quantity {
         return Err(ProveError::InvalidState(format!(
             "Insufficient quantity: have {}, need >= {}",
             actual_quantity, min_quantity
-        )));
+        )).into());
     }
 
     let proof = state.get_proof(item_id);
@@ -362,16 +529,16 @@ pub fn prove_item_exists(
 
 /// Generate proof for CapacitySMTCircuit
 pub fn prove_capacity(
-    pk: &ProvingKey<Bn254>,
+    pk: &TypedProvingKey<CapacitySMTCircuit>,
     state: &InventoryState,
     max_capacity: u64,
-) -> Result<ProofWithInputs, ProveError> {
+) -> Result<ProofWithInputs, ProverError> {
     // Verify capacity compliance (max_capacity of 0 means unlimited)
     if max_capacity > 0 && state.current_volume > max_capacity {
         return Err(ProveError::InvalidState(format!(
             "Volume exceeds capacity: {} > {}",
             state.current_volume, max_capacity
-        )));
+        )).into());
     }
 
     // Create circuit
@@ -435,6 +602,102 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_prove_state_transition_insufficient_error_variant() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+
+        let blinding = Fr::from(12345u64);
+        let mut state = InventoryState::new(blinding);
+        state.tree.update(1, 10);
+        state.current_volume = 100;
+
+        let result = prove_state_transition(
+            &keys.proving_key,
+            &state,
+            Fr::from(67890u64),
+            1,  // item_id
+            50, // more than we have
+            10,
+            Fr::from(99999u64),
+            1000,
+            0,
+            Fr::from(12345678u64),
+            OpType::Withdraw,
+        );
+
+        assert!(matches!(
+            result,
+            Err(ProverError::Prove(ProveError::InvalidState(_)))
+        ));
+    }
+
+    #[test]
+    fn test_prove_state_transition_from_commitment() {
+        use crate::public_inputs::StateTransitionPublicInputs;
+        use crate::verify::verify_state_transition;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.tree.update(1, 10);
+        state.current_volume = 100;
+
+        let prove = |old_commitment| {
+            prove_state_transition_from_commitment(
+                &keys.proving_key,
+                &state,
+                old_commitment,
+                Fr::from(67890u64),
+                1,
+                5,
+                10,
+                Fr::from(99999u64),
+                1000,
+                0,
+                Fr::from(12345678u64),
+                OpType::Withdraw,
+            )
+        };
+
+        // The precomputed commitment is the one the proof's signal hash binds
+        let old_commitment = state.commitment();
+        let result = prove(old_commitment).unwrap();
+        assert_eq!(result.signal.old_commitment, old_commitment);
+        assert_eq!(result.proof.public_inputs[0], result.signal.compute_hash());
+        let inputs = StateTransitionPublicInputs::from_signal(&result.signal, None);
+        let valid = verify_state_transition(&keys.verifying_key, &result.proof.proof, &inputs);
+        assert!(valid.unwrap());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "does not open to the given commitment")]
+    fn test_prove_state_transition_from_mismatched_commitment() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.tree.update(1, 10);
+        state.current_volume = 100;
+
+        let _ = prove_state_transition_from_commitment(
+            &keys.proving_key,
+            &state,
+            state.commitment() + Fr::from(1u64),
+            Fr::from(67890u64),
+            1,
+            5,
+            10,
+            Fr::from(99999u64),
+            1000,
+            0,
+            Fr::from(12345678u64),
+            OpType::Withdraw,
+        );
+    }
+
     #[test]
     fn test_prove_capacity() {
         let mut rng = StdRng::seed_from_u64(42);
@@ -542,4 +805,41 @@ mod tests {
         assert_eq!(res.new_state.current_volume, 700); // 1000 - 30*10
         assert_eq!(res.new_state.get_quantity(1), 70); // 100 - 30
     }
+
+    #[test]
+    fn test_prove_state_transition_out_of_range_rejected() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.tree.update(1, 100);
+        state.current_volume = 100;
+
+        let transition = |amount: u64, item_volume: u64, op_type: OpType| {
+            prove_state_transition(
+                &keys.proving_key,
+                &state,
+                Fr::from(67890u64),
+                1,
+                amount,
+                item_volume,
+                Fr::from(99999u64),
+                MAX_RANGE_VALUE,
+                0,
+                Fr::from(12345678u64),
+                op_type,
+            )
+        };
+        let is_invalid_state =
+            |result| matches!(result, Err(ProverError::Prove(ProveError::InvalidState(_))));
+
+        // u64::MAX amounts overflow the quantity, the volume, or underflow
+        assert!(is_invalid_state(transition(u64::MAX, 1, OpType::Deposit)));
+        assert!(is_invalid_state(transition(u64::MAX, 1, OpType::Withdraw)));
+        assert!(is_invalid_state(transition(1 << 40, 1 << 40, OpType::Deposit)));
+
+        // Fits in u64 but not in the circuit's 32-bit range
+        assert!(is_invalid_state(transition(MAX_RANGE_VALUE, 0, OpType::Deposit)));
+        assert!(transition(MAX_RANGE_VALUE - 100, 0, OpType::Deposit).is_ok());
+    }
 }
@@ -0,0 +1,186 @@
+//! Typed public inputs for each circuit.
+//!
+//! Groth16 verification takes public inputs as a positional `&[Fr]`, where a
+//! wrong order just makes the proof fail to verify. These structs name each
+//! input and produce the canonical order with `to_vec`.
+//!
+//! ItemExists and Capacity expose a single Poseidon hash of their statement,
+//! so their structs hold the statement and `to_vec` hashes it. A hash cannot be
+//! parsed back, so only [`StateTransitionPublicInputs`] has a `from_vec`.
+
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+
+use inventory_circuits::{compute_capacity_hash, compute_item_exists_hash, signal::SignalInputs};
+
+use crate::verify::VerifyError;
+
+/// Public inputs of a StateTransition proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateTransitionPublicInputs {
+    /// Poseidon hash of the signal inputs
+    pub signal_hash: Fr,
+    /// On-chain nonce the transition was made for
+    pub nonce: u64,
+    /// Inventory object ID as a field element
+    pub inventory_id: Fr,
+    /// Volume registry root
+    pub registry_root: Fr,
+    /// Epoch, for keys set up with one
+    pub epoch: Option<u64>,
+}
+
+impl StateTransitionPublicInputs {
+    /// Number of public inputs without an epoch.
+    pub const LEN: usize = 4;
+
+    /// Public inputs of a transition with the given signal inputs.
+    pub fn from_signal(signal: &SignalInputs, epoch: Option<u64>) -> Self {
+        Self {
+            signal_hash: signal.compute_hash(),
+            nonce: signal.nonce,
+            inventory_id: signal.inventory_id,
+            registry_root: signal.registry_root,
+            epoch,
+        }
+    }
+
+    /// Inputs in circuit order: signal_hash, nonce, inventory_id, registry_root (, epoch).
+    pub fn to_vec(&self) -> Vec<Fr> {
+        let mut inputs = vec![
+            self.signal_hash,
+            Fr::from(self.nonce),
+            self.inventory_id,
+            self.registry_root,
+        ];
+        inputs.extend(self.epoch.map(Fr::from));
+        inputs
+    }
+
+    /// Parse inputs in circuit order. A fifth input is read as the epoch.
+    pub fn from_vec(inputs: &[Fr]) -> Result<Self, VerifyError> {
+        let (inputs, epoch) = match inputs.len() {
+            Self::LEN => (inputs, None),
+            len if len == Self::LEN + 1 => (&inputs[..Self::LEN], Some(to_u64(inputs[Self::LEN])?)),
+            actual => {
+                let expected = if actual > Self::LEN { Self::LEN + 1 } else { Self::LEN };
+                return Err(VerifyError::PublicInputCount { expected, actual });
+            }
+        };
+
+        Ok(Self {
+            signal_hash: inputs[0],
+            nonce: to_u64(inputs[1])?,
+            inventory_id: inputs[2],
+            registry_root: inputs[3],
+            epoch,
+        })
+    }
+}
+
+/// Public inputs of an ItemExists proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ItemExistsPublicInputs {
+    /// Inventory commitment
+    pub commitment: Fr,
+    /// Item ID held
+    pub item_id: u64,
+    /// Minimum quantity held
+    pub min_quantity: u64,
+}
+
+impl ItemExistsPublicInputs {
+    /// Inputs in circuit order: Poseidon(commitment, item_id, min_quantity).
+    pub fn to_vec(&self) -> Vec<Fr> {
+        vec![compute_item_exists_hash(self.commitment, self.item_id, self.min_quantity)]
+    }
+}
+
+/// Public inputs of a Capacity proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityPublicInputs {
+    /// Inventory commitment
+    pub commitment: Fr,
+    /// Capacity the volume stays within
+    pub max_capacity: u64,
+}
+
+impl CapacityPublicInputs {
+    /// Inputs in circuit order: Poseidon(commitment, max_capacity).
+    pub fn to_vec(&self) -> Vec<Fr> {
+        vec![compute_capacity_hash(self.commitment, self.max_capacity)]
+    }
+}
+
+fn to_u64(value: Fr) -> Result<u64, VerifyError> {
+    match value.into_bigint().0 {
+        [low, 0, 0, 0] => Ok(low),
+        _ => Err(VerifyError::InvalidInputs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_transition_inputs(epoch: Option<u64>) -> StateTransitionPublicInputs {
+        StateTransitionPublicInputs {
+            signal_hash: Fr::from(111u64),
+            nonce: 3,
+            inventory_id: Fr::from(12345678u64),
+            registry_root: Fr::from(99999u64),
+            epoch,
+        }
+    }
+
+    #[test]
+    fn test_state_transition_round_trip() {
+        for epoch in [None, Some(7)] {
+            let inputs = state_transition_inputs(epoch);
+            let vec = inputs.to_vec();
+
+            assert_eq!(vec.len(), StateTransitionPublicInputs::LEN + epoch.is_some() as usize);
+            assert_eq!(vec[1], Fr::from(3u64));
+            assert_eq!(StateTransitionPublicInputs::from_vec(&vec).unwrap(), inputs);
+        }
+    }
+
+    #[test]
+    fn test_state_transition_wrong_length_rejected() {
+        let vec = state_transition_inputs(Some(7)).to_vec();
+
+        for (len, expected) in [(1, 4), (3, 4), (6, 5)] {
+            let mut inputs = vec.clone();
+            inputs.resize(len, Fr::from(0u64));
+            let err = StateTransitionPublicInputs::from_vec(&inputs).unwrap_err();
+            match err {
+                VerifyError::PublicInputCount { expected: e, actual } => {
+                    assert_eq!((e, actual), (expected, len));
+                }
+                other => panic!("unexpected error: {}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_state_transition_non_u64_nonce_rejected() {
+        let mut vec = state_transition_inputs(None).to_vec();
+        vec[1] = -Fr::from(1u64);
+
+        assert!(matches!(
+            StateTransitionPublicInputs::from_vec(&vec),
+            Err(VerifyError::InvalidInputs)
+        ));
+    }
+
+    #[test]
+    fn test_hashed_inputs_match_circuit_hash() {
+        let commitment = Fr::from(42u64);
+
+        let item_exists = ItemExistsPublicInputs { commitment, item_id: 1, min_quantity: 50 };
+        assert_eq!(item_exists.to_vec(), vec![compute_item_exists_hash(commitment, 1, 50)]);
+
+        let capacity = CapacityPublicInputs { commitment, max_capacity: 1000 };
+        assert_eq!(capacity.to_vec(), vec![compute_capacity_hash(commitment, 1000)]);
+    }
+}
@@ -0,0 +1,104 @@
+//! Byte encodings of proofs and verifying keys, for storage and transport.
+//!
+//! Everything uses arkworks' compressed canonical encoding, the same as the
+//! key files written by `CircuitKeys::save_to_directory`. Decoding validates
+//! that every point is on the curve and in the right subgroup.
+
+use ark_bn254::Bn254;
+use ark_groth16::{Proof, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use thiserror::Error;
+
+/// Errors decoding a proof or verifying key
+#[derive(Error, Debug)]
+pub enum SerializationError {
+    #[error("Invalid proof encoding: {0}")]
+    InvalidProof(String),
+    #[error("Invalid verifying key encoding: {0}")]
+    InvalidVerifyingKey(String),
+}
+
+/// Encode a proof (128 bytes).
+pub fn serialize_proof(proof: &Proof<Bn254>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(proof.compressed_size());
+    proof
+        .serialize_compressed(&mut bytes)
+        .expect("writing to a Vec cannot fail");
+    bytes
+}
+
+/// Decode a proof written by [`serialize_proof`].
+pub fn deserialize_proof(bytes: &[u8]) -> Result<Proof<Bn254>, SerializationError> {
+    Proof::deserialize_compressed(bytes)
+        .map_err(|e| SerializationError::InvalidProof(e.to_string()))
+}
+
+/// Encode a verifying key.
+pub fn serialize_verifying_key(vk: &VerifyingKey<Bn254>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(vk.compressed_size());
+    vk.serialize_compressed(&mut bytes)
+        .expect("writing to a Vec cannot fail");
+    bytes
+}
+
+/// Decode a verifying key written by [`serialize_verifying_key`].
+pub fn deserialize_verifying_key(bytes: &[u8]) -> Result<VerifyingKey<Bn254>, SerializationError> {
+    VerifyingKey::deserialize_compressed(bytes)
+        .map_err(|e| SerializationError::InvalidVerifyingKey(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    use crate::prove::{prove_item_exists, InventoryState, ProofWithInputs};
+    use crate::public_inputs::ItemExistsPublicInputs;
+    use crate::setup::{setup_item_exists, TypedVerifyingKey};
+    use crate::verify::verify_item_exists;
+
+    #[test]
+    fn test_round_trip_verifies() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_item_exists(&mut rng).unwrap();
+
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.tree.update(42, 100);
+        state.current_volume = 500;
+        let result = prove_item_exists(&keys.proving_key, &state, 42, 50).unwrap();
+
+        let proof_bytes = serialize_proof(&result.proof);
+        let vk_bytes = serialize_verifying_key(&keys.verifying_key);
+        assert_eq!(proof_bytes.len(), 128);
+        // ProofWithInputs uses the same encoding in both directions
+        assert_eq!(result.serialize_proof().unwrap(), proof_bytes);
+        assert_eq!(ProofWithInputs::deserialize_proof(&proof_bytes).unwrap(), result.proof);
+
+        let proof = deserialize_proof(&proof_bytes).unwrap();
+        let vk = TypedVerifyingKey::new(deserialize_verifying_key(&vk_bytes).unwrap());
+
+        let inputs = ItemExistsPublicInputs {
+            commitment: state.commitment(),
+            item_id: 42,
+            min_quantity: 50,
+        };
+        assert!(verify_item_exists(&vk, &proof, &inputs).unwrap());
+    }
+
+    #[test]
+    fn test_truncated_bytes_rejected() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_item_exists(&mut rng).unwrap();
+        let vk_bytes = serialize_verifying_key(&keys.verifying_key);
+
+        assert!(matches!(
+            deserialize_proof(&[0u8; 64]),
+            Err(SerializationError::InvalidProof(_))
+        ));
+        assert!(matches!(
+            deserialize_verifying_key(&vk_bytes[..vk_bytes.len() - 1]),
+            Err(SerializationError::InvalidVerifyingKey(_))
+        ));
+    }
+}
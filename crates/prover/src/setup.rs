@@ -5,8 +5,13 @@ use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_snark::SNARK;
 use ark_std::rand::rngs::StdRng;
+use sha2::{Digest, Sha256};
+use std::marker::PhantomData;
+use std::ops::Deref;
 use thiserror::Error;
 
+use crate::error::ProverError;
+
 use inventory_circuits::{
     CapacitySMTCircuit, ItemExistsSMTCircuit, StateTransitionCircuit,
 };
@@ -20,18 +25,174 @@ pub enum SetupError {
     Serialization(String),
     #[error("Deserialization failed: {0}")]
     Deserialization(String),
+    #[error(
+        "Missing key file {0}: delete the keys directory and rerun setup to regenerate all keys"
+    )]
+    MissingKeyFile(std::path::PathBuf),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
 
+/// Read one key file, naming it if it does not exist.
+///
+/// Keys saved before a circuit was added lack its files, so a missing file
+/// usually means the keys directory predates this version.
+fn read_key_file(dir: &std::path::Path, name: &str) -> Result<Vec<u8>, SetupError> {
+    let path = dir.join(name);
+    std::fs::read(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => SetupError::MissingKeyFile(path),
+        _ => SetupError::Io(e),
+    })
+}
+
+/// A proving key tagged with the circuit it was generated for.
+///
+/// The marker makes passing a key for the wrong circuit a compile error:
+///
+/// ```
+/// use inventory_circuits::ItemExistsSMTCircuit;
+/// use inventory_prover::{prove_item_exists, InventoryState, TypedProvingKey};
+///
+/// fn prove(pk: &TypedProvingKey<ItemExistsSMTCircuit>, state: &InventoryState) {
+///     let _ = prove_item_exists(pk, state, 1, 1);
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use inventory_circuits::CapacitySMTCircuit;
+/// use inventory_prover::{prove_item_exists, InventoryState, TypedProvingKey};
+///
+/// fn prove(pk: &TypedProvingKey<CapacitySMTCircuit>, state: &InventoryState) {
+///     let _ = prove_item_exists(pk, state, 1, 1);
+/// }
+/// ```
+pub struct TypedProvingKey<C> {
+    key: ProvingKey<Bn254>,
+    _circuit: PhantomData<fn() -> C>,
+}
+
+impl<C> TypedProvingKey<C> {
+    /// Tag a proving key as belonging to circuit `C`.
+    ///
+    /// The caller is responsible for the key actually matching `C`.
+    pub fn new(key: ProvingKey<Bn254>) -> Self {
+        Self {
+            key,
+            _circuit: PhantomData,
+        }
+    }
+
+    /// Unwrap into the untyped proving key.
+    pub fn into_inner(self) -> ProvingKey<Bn254> {
+        self.key
+    }
+}
+
+impl<C> Clone for TypedProvingKey<C> {
+    fn clone(&self) -> Self {
+        Self::new(self.key.clone())
+    }
+}
+
+impl<C> Deref for TypedProvingKey<C> {
+    type Target = ProvingKey<Bn254>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.key
+    }
+}
+
+/// A verifying key tagged with the circuit it was generated for.
+pub struct TypedVerifyingKey<C> {
+    key: VerifyingKey<Bn254>,
+    _circuit: PhantomData<fn() -> C>,
+}
+
+impl<C> TypedVerifyingKey<C> {
+    /// Tag a verifying key as belonging to circuit `C`.
+    ///
+    /// The caller is responsible for the key actually matching `C`.
+    pub fn new(key: VerifyingKey<Bn254>) -> Self {
+        Self {
+            key,
+            _circuit: PhantomData,
+        }
+    }
+
+    /// Unwrap into the untyped verifying key.
+    pub fn into_inner(self) -> VerifyingKey<Bn254> {
+        self.key
+    }
+
+    /// Number of public inputs the key verifies against.
+    ///
+    /// Matches `CircuitMeta::num_public_inputs` of the circuit it was set up from.
+    pub fn num_public_inputs(&self) -> usize {
+        self.key.gamma_abc_g1.len() - 1
+    }
+}
+
+impl<C> Clone for TypedVerifyingKey<C> {
+    fn clone(&self) -> Self {
+        Self::new(self.key.clone())
+    }
+}
+
+impl<C> Deref for TypedVerifyingKey<C> {
+    type Target = VerifyingKey<Bn254>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.key
+    }
+}
+
+/// Estimated memory used by one circuit's keys, in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyFootprint {
+    /// Circuit name, as in the key file names
+    pub circuit: &'static str,
+    /// Proving key bytes
+    pub proving_key: usize,
+    /// Verifying key bytes
+    pub verifying_key: usize,
+}
+
+impl KeyFootprint {
+    /// Bytes used by both keys.
+    pub fn total(&self) -> usize {
+        self.proving_key + self.verifying_key
+    }
+}
+
 /// Keys for a single circuit
-#[derive(Clone)]
-pub struct CircuitKeyPair {
-    pub proving_key: ProvingKey<Bn254>,
-    pub verifying_key: VerifyingKey<Bn254>,
+pub struct CircuitKeyPair<C> {
+    pub proving_key: TypedProvingKey<C>,
+    pub verifying_key: TypedVerifyingKey<C>,
 }
 
-impl CircuitKeyPair {
+impl<C> Clone for CircuitKeyPair<C> {
+    fn clone(&self) -> Self {
+        Self {
+            proving_key: self.proving_key.clone(),
+            verifying_key: self.verifying_key.clone(),
+        }
+    }
+}
+
+impl<C> CircuitKeyPair<C> {
+    /// Estimate the memory used by both keys.
+    ///
+    /// Uses the uncompressed serialized size: keys are held as uncompressed
+    /// affine points, so this is close to their heap size (it leaves out
+    /// allocator and `Vec` overhead).
+    pub fn footprint(&self, circuit: &'static str) -> KeyFootprint {
+        KeyFootprint {
+            circuit,
+            proving_key: self.proving_key.uncompressed_size(),
+            verifying_key: self.verifying_key.uncompressed_size(),
+        }
+    }
+
     /// Serialize proving key to bytes
     pub fn serialize_pk(&self) -> Result<Vec<u8>, SetupError> {
         let mut bytes = Vec::new();
@@ -51,14 +212,16 @@ impl CircuitKeyPair {
     }
 
     /// Deserialize proving key from bytes
-    pub fn deserialize_pk(bytes: &[u8]) -> Result<ProvingKey<Bn254>, SetupError> {
+    pub fn deserialize_pk(bytes: &[u8]) -> Result<TypedProvingKey<C>, SetupError> {
         ProvingKey::deserialize_compressed(bytes)
+            .map(TypedProvingKey::new)
             .map_err(|e| SetupError::Deserialization(e.to_string()))
     }
 
     /// Deserialize verifying key from bytes
-    pub fn deserialize_vk(bytes: &[u8]) -> Result<VerifyingKey<Bn254>, SetupError> {
+    pub fn deserialize_vk(bytes: &[u8]) -> Result<TypedVerifyingKey<C>, SetupError> {
         VerifyingKey::deserialize_compressed(bytes)
+            .map(TypedVerifyingKey::new)
             .map_err(|e| SetupError::Deserialization(e.to_string()))
     }
 }
@@ -66,14 +229,44 @@ impl CircuitKeyPair {
 /// All circuit keys for SMT-based circuits
 pub struct CircuitKeys {
     /// StateTransition circuit (deposit/withdraw with capacity)
-    pub state_transition: CircuitKeyPair,
+    pub state_transition: CircuitKeyPair<StateTransitionCircuit>,
+    /// StateTransition circuit with an epoch public input (for freshness checks)
+    pub state_transition_epoch: CircuitKeyPair<StateTransitionCircuit>,
     /// ItemExists circuit (prove ownership of items)
-    pub item_exists: CircuitKeyPair,
+    pub item_exists: CircuitKeyPair<ItemExistsSMTCircuit>,
     /// Capacity circuit (prove volume compliance)
-    pub capacity: CircuitKeyPair,
+    pub capacity: CircuitKeyPair<CapacitySMTCircuit>,
 }
 
 impl CircuitKeys {
+    /// Estimated memory used by each circuit's keys.
+    pub fn memory_breakdown(&self) -> Vec<KeyFootprint> {
+        vec![
+            self.state_transition.footprint("state_transition"),
+            self.state_transition_epoch.footprint("state_transition_epoch"),
+            self.item_exists.footprint("item_exists"),
+            self.capacity.footprint("capacity"),
+        ]
+    }
+
+    /// Estimated memory used by all keys, in bytes (see [`CircuitKeyPair::footprint`]).
+    pub fn memory_footprint(&self) -> usize {
+        self.memory_breakdown().iter().map(KeyFootprint::total).sum()
+    }
+
+    /// SHA-256 over every circuit's verifying key, hex encoded.
+    ///
+    /// Changes whenever any verifying key does, so it identifies which trusted
+    /// setup the keys came from without comparing the keys themselves.
+    pub fn fingerprint(&self) -> Result<String, SetupError> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.state_transition.serialize_vk()?);
+        hasher.update(self.state_transition_epoch.serialize_vk()?);
+        hasher.update(self.item_exists.serialize_vk()?);
+        hasher.update(self.capacity.serialize_vk()?);
+        Ok(hex::encode(hasher.finalize()))
+    }
+
     /// Save all keys to a directory
     pub fn save_to_directory(&self, dir: &std::path::Path) -> Result<(), SetupError> {
         std::fs::create_dir_all(dir)?;
@@ -86,6 +279,14 @@ impl CircuitKeys {
             dir.join("state_transition.vk"),
             self.state_transition.serialize_vk()?,
         )?;
+        std::fs::write(
+            dir.join("state_transition_epoch.pk"),
+            self.state_transition_epoch.serialize_pk()?,
+        )?;
+        std::fs::write(
+            dir.join("state_transition_epoch.vk"),
+            self.state_transition_epoch.serialize_vk()?,
+        )?;
 
         std::fs::write(dir.join("item_exists.pk"), self.item_exists.serialize_pk()?)?;
         std::fs::write(dir.join("item_exists.vk"), self.item_exists.serialize_vk()?)?;
@@ -97,34 +298,45 @@ impl CircuitKeys {
     }
 
     /// Load all keys from a directory
+    ///
+    /// Fails with [`SetupError::MissingKeyFile`] if any circuit's key file is
+    /// absent, e.g. a directory saved before `state_transition_epoch` existed.
     pub fn load_from_directory(dir: &std::path::Path) -> Result<Self, SetupError> {
         let state_transition = CircuitKeyPair {
-            proving_key: CircuitKeyPair::deserialize_pk(&std::fs::read(
-                dir.join("state_transition.pk"),
+            proving_key: CircuitKeyPair::deserialize_pk(&read_key_file(
+                dir,
+                "state_transition.pk",
             )?)?,
-            verifying_key: CircuitKeyPair::deserialize_vk(&std::fs::read(
-                dir.join("state_transition.vk"),
+            verifying_key: CircuitKeyPair::deserialize_vk(&read_key_file(
+                dir,
+                "state_transition.vk",
             )?)?,
         };
 
-        let item_exists = CircuitKeyPair {
-            proving_key: CircuitKeyPair::deserialize_pk(&std::fs::read(
-                dir.join("item_exists.pk"),
+        let state_transition_epoch = CircuitKeyPair {
+            proving_key: CircuitKeyPair::deserialize_pk(&read_key_file(
+                dir,
+                "state_transition_epoch.pk",
             )?)?,
-            verifying_key: CircuitKeyPair::deserialize_vk(&std::fs::read(
-                dir.join("item_exists.vk"),
+            verifying_key: CircuitKeyPair::deserialize_vk(&read_key_file(
+                dir,
+                "state_transition_epoch.vk",
             )?)?,
         };
 
+        let item_exists = CircuitKeyPair {
+            proving_key: CircuitKeyPair::deserialize_pk(&read_key_file(dir, "item_exists.pk")?)?,
+            verifying_key: CircuitKeyPair::deserialize_vk(&read_key_file(dir, "item_exists.vk")?)?,
+        };
+
         let capacity = CircuitKeyPair {
-            proving_key: CircuitKeyPair::deserialize_pk(&std::fs::read(dir.join("capacity.pk"))?)?,
-            verifying_key: CircuitKeyPair::deserialize_vk(&std::fs::read(
-                dir.join("capacity.vk"),
-            )?)?,
+            proving_key: CircuitKeyPair::deserialize_pk(&read_key_file(dir, "capacity.pk")?)?,
+            verifying_key: CircuitKeyPair::deserialize_vk(&read_key_file(dir, "capacity.vk")?)?,
         };
 
         Ok(Self {
             state_transition,
+            state_transition_epoch,
             item_exists,
             capacity,
         })
@@ -132,13 +344,16 @@ impl CircuitKeys {
 }
 
 /// Run trusted setup for all SMT circuits
-pub fn setup_all_circuits() -> Result<CircuitKeys, SetupError> {
+pub fn setup_all_circuits() -> Result<CircuitKeys, ProverError> {
     // Use a fixed seed for reproducible setup (in production, use secure randomness)
     let mut rng = ark_std::rand::SeedableRng::seed_from_u64(42);
 
     println!("Setting up StateTransitionCircuit...");
     let state_transition = setup_state_transition(&mut rng)?;
 
+    println!("Setting up StateTransitionCircuit with epoch...");
+    let state_transition_epoch = setup_state_transition_with_epoch(&mut rng)?;
+
     println!("Setting up ItemExistsSMTCircuit...");
     let item_exists = setup_item_exists(&mut rng)?;
 
@@ -147,6 +362,7 @@ pub fn setup_all_circuits() -> Result<CircuitKeys, SetupError> {
 
     Ok(CircuitKeys {
         state_transition,
+        state_transition_epoch,
         item_exists,
         capacity,
     })
@@ -155,42 +371,56 @@ pub fn setup_all_circuits() -> Result<CircuitKeys, SetupError> {
 /// Setup StateTransitionCircuit
 pub fn setup_state_transition(
     rng: &mut StdRng,
-) -> Result<CircuitKeyPair, SetupError> {
+) -> Result<CircuitKeyPair<StateTransitionCircuit>, ProverError> {
     let circuit = StateTransitionCircuit::empty();
     let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(circuit, rng)
         .map_err(|e| SetupError::CircuitSetup(e.to_string()))?;
 
     Ok(CircuitKeyPair {
-        proving_key: pk,
-        verifying_key: vk,
+        proving_key: TypedProvingKey::new(pk),
+        verifying_key: TypedVerifyingKey::new(vk),
+    })
+}
+
+/// Setup StateTransitionCircuit with an epoch public input
+pub fn setup_state_transition_with_epoch(
+    rng: &mut StdRng,
+) -> Result<CircuitKeyPair<StateTransitionCircuit>, ProverError> {
+    let circuit = StateTransitionCircuit::empty().with_epoch(0);
+    let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(circuit, rng)
+        .map_err(|e| SetupError::CircuitSetup(e.to_string()))?;
+
+    Ok(CircuitKeyPair {
+        proving_key: TypedProvingKey::new(pk),
+        verifying_key: TypedVerifyingKey::new(vk),
     })
 }
 
 /// Setup ItemExistsSMTCircuit
 pub fn setup_item_exists(
     rng: &mut StdRng,
-) -> Result<CircuitKeyPair, SetupError> {
+) -> Result<CircuitKeyPair<ItemExistsSMTCircuit>, ProverError> {
     let circuit = ItemExistsSMTCircuit::empty();
     let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(circuit, rng)
         .map_err(|e| SetupError::CircuitSetup(e.to_string()))?;
 
     Ok(CircuitKeyPair {
-        proving_key: pk,
-        verifying_key: vk,
+        proving_key: TypedProvingKey::new(pk),
+        verifying_key: TypedVerifyingKey::new(vk),
     })
 }
 
 /// Setup CapacitySMTCircuit
 pub fn setup_capacity(
     rng: &mut StdRng,
-) -> Result<CircuitKeyPair, SetupError> {
+) -> Result<CircuitKeyPair<CapacitySMTCircuit>, ProverError> {
     let circuit = CapacitySMTCircuit::empty();
     let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(circuit, rng)
         .map_err(|e| SetupError::CircuitSetup(e.to_string()))?;
 
     Ok(CircuitKeyPair {
-        proving_key: pk,
-        verifying_key: vk,
+        proving_key: TypedProvingKey::new(pk),
+        verifying_key: TypedVerifyingKey::new(vk),
     })
 }
 
@@ -208,8 +438,8 @@ mod tests {
         let pk_bytes = keys.serialize_pk().unwrap();
         let vk_bytes = keys.serialize_vk().unwrap();
 
-        let _pk = CircuitKeyPair::deserialize_pk(&pk_bytes).unwrap();
-        let _vk = CircuitKeyPair::deserialize_vk(&vk_bytes).unwrap();
+        let _pk = CircuitKeyPair::<StateTransitionCircuit>::deserialize_pk(&pk_bytes).unwrap();
+        let _vk = CircuitKeyPair::<StateTransitionCircuit>::deserialize_vk(&vk_bytes).unwrap();
     }
 
     #[test]
@@ -220,8 +450,8 @@ mod tests {
         let pk_bytes = keys.serialize_pk().unwrap();
         let vk_bytes = keys.serialize_vk().unwrap();
 
-        let _pk = CircuitKeyPair::deserialize_pk(&pk_bytes).unwrap();
-        let _vk = CircuitKeyPair::deserialize_vk(&vk_bytes).unwrap();
+        let _pk = CircuitKeyPair::<ItemExistsSMTCircuit>::deserialize_pk(&pk_bytes).unwrap();
+        let _vk = CircuitKeyPair::<ItemExistsSMTCircuit>::deserialize_vk(&vk_bytes).unwrap();
     }
 
     #[test]
@@ -232,7 +462,55 @@ mod tests {
         let pk_bytes = keys.serialize_pk().unwrap();
         let vk_bytes = keys.serialize_vk().unwrap();
 
-        let _pk = CircuitKeyPair::deserialize_pk(&pk_bytes).unwrap();
-        let _vk = CircuitKeyPair::deserialize_vk(&vk_bytes).unwrap();
+        let _pk = CircuitKeyPair::<CapacitySMTCircuit>::deserialize_pk(&pk_bytes).unwrap();
+        let _vk = CircuitKeyPair::<CapacitySMTCircuit>::deserialize_vk(&vk_bytes).unwrap();
+    }
+
+    #[test]
+    fn test_memory_footprint() {
+        let keys = setup_all_circuits().unwrap();
+        let breakdown = keys.memory_breakdown();
+        let footprint = |circuit| breakdown.iter().find(|f| f.circuit == circuit).unwrap();
+
+        assert!(keys.memory_footprint() > 0);
+        assert_eq!(keys.memory_footprint(), breakdown.iter().map(KeyFootprint::total).sum());
+        assert!(breakdown.iter().all(|f| f.proving_key > f.verifying_key));
+
+        // StateTransition is the largest circuit with keys
+        assert!(footprint("state_transition").total() > footprint("item_exists").total());
+    }
+
+    #[test]
+    fn test_fingerprint_identifies_setup() {
+        let mut keys = setup_all_circuits().unwrap();
+        let fingerprint = keys.fingerprint().unwrap();
+        assert_eq!(fingerprint.len(), 64);
+
+        // Saving and loading keeps the fingerprint
+        let dir = tempfile::tempdir().unwrap();
+        keys.save_to_directory(dir.path()).unwrap();
+        let loaded = CircuitKeys::load_from_directory(dir.path()).unwrap();
+        assert_eq!(loaded.fingerprint().unwrap(), fingerprint);
+
+        // A different setup for any one circuit changes it
+        keys.capacity = setup_capacity(&mut StdRng::seed_from_u64(7)).unwrap();
+        assert_ne!(keys.fingerprint().unwrap(), fingerprint);
+    }
+
+    #[test]
+    fn test_load_names_missing_key_file() {
+        let keys = setup_all_circuits().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        keys.save_to_directory(dir.path()).unwrap();
+
+        // A keys directory from before the epoch circuit existed
+        std::fs::remove_file(dir.path().join("state_transition_epoch.pk")).unwrap();
+
+        let err = CircuitKeys::load_from_directory(dir.path()).err().unwrap();
+        assert!(matches!(
+            &err,
+            SetupError::MissingKeyFile(path) if path.ends_with("state_transition_epoch.pk")
+        ));
+        assert!(err.to_string().contains("regenerate"));
     }
 }
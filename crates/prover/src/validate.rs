@@ -0,0 +1,288 @@
+//! Off-circuit validation of state transition requests.
+//!
+//! Mirrors the constraints of `StateTransitionCircuit` natively so a client can
+//! find out *why* a request would be unsatisfiable before paying for a proof.
+
+use ark_bn254::Fr;
+use thiserror::Error;
+
+use crate::prove::InventoryState;
+
+/// The specific reason a state transition request would fail to prove.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error("Old commitment does not match old inventory state")]
+    OldCommitmentMismatch,
+    #[error("New commitment does not match new inventory state")]
+    NewCommitmentMismatch,
+    #[error("Insufficient quantity of item {item_id}: have {have}, need {need}")]
+    InsufficientQuantity { item_id: u64, have: u64, need: u64 },
+    #[error("Item {item_id} quantity is {actual}, expected {expected}")]
+    QuantityMismatch { item_id: u64, expected: u64, actual: u64 },
+    #[error("Item {item_id} changed but is not part of this operation")]
+    UnrelatedItemChanged { item_id: u64 },
+    #[error("Volume is {actual}, expected {expected}")]
+    VolumeMismatch { expected: u64, actual: u64 },
+    #[error("Capacity exceeded: {volume} > {max_capacity}")]
+    CapacityExceeded { volume: u64, max_capacity: u64 },
+    #[error("Arithmetic overflow: {0}")]
+    Overflow(&'static str),
+}
+
+/// Validate a withdraw of `amount` units of `item_id` from `old_state` to `new_state`.
+///
+/// Checks that both commitments open to their states, that the old quantity covers
+/// the withdrawal, that the new quantity and volume are exactly reduced, and that
+/// no other item changed.
+#[allow(clippy::too_many_arguments)]
+pub fn validate_withdraw_request(
+    old_state: &InventoryState,
+    new_state: &InventoryState,
+    old_commitment: Fr,
+    new_commitment: Fr,
+    item_id: u64,
+    amount: u64,
+    item_volume: u64,
+) -> Result<(), ValidationError> {
+    validate_commitments(old_state, new_state, old_commitment, new_commitment)?;
+
+    let old_qty = old_state.get_quantity(item_id);
+    if old_qty < amount {
+        return Err(ValidationError::InsufficientQuantity {
+            item_id,
+            have: old_qty,
+            need: amount,
+        });
+    }
+    check_quantity(new_state, item_id, old_qty - amount)?;
+    check_unrelated_items(old_state, new_state, item_id)?;
+
+    let volume_delta = amount
+        .checked_mul(item_volume)
+        .ok_or(ValidationError::Overflow("volume delta"))?;
+    let expected_volume = old_state
+        .current_volume
+        .checked_sub(volume_delta)
+        .ok_or(ValidationError::Overflow("volume underflow"))?;
+    check_volume(new_state, expected_volume)
+}
+
+/// Validate a deposit of `amount` units of `item_id` from `old_state` to `new_state`.
+///
+/// Same checks as [`validate_withdraw_request`] with quantities and volume increased,
+/// plus the capacity limit (`max_capacity` of 0 means unlimited).
+#[allow(clippy::too_many_arguments)]
+pub fn validate_deposit_request(
+    old_state: &InventoryState,
+    new_state: &InventoryState,
+    old_commitment: Fr,
+    new_commitment: Fr,
+    item_id: u64,
+    amount: u64,
+    item_volume: u64,
+    max_capacity: u64,
+) -> Result<(), ValidationError> {
+    validate_commitments(old_state, new_state, old_commitment, new_commitment)?;
+
+    let old_qty = old_state.get_quantity(item_id);
+    let expected_qty = old_qty
+        .checked_add(amount)
+        .ok_or(ValidationError::Overflow("quantity"))?;
+    check_quantity(new_state, item_id, expected_qty)?;
+    check_unrelated_items(old_state, new_state, item_id)?;
+
+    let volume_delta = amount
+        .checked_mul(item_volume)
+        .ok_or(ValidationError::Overflow("volume delta"))?;
+    let expected_volume = old_state
+        .current_volume
+        .checked_add(volume_delta)
+        .ok_or(ValidationError::Overflow("volume"))?;
+    check_volume(new_state, expected_volume)?;
+
+    if max_capacity > 0 && expected_volume > max_capacity {
+        return Err(ValidationError::CapacityExceeded {
+            volume: expected_volume,
+            max_capacity,
+        });
+    }
+
+    Ok(())
+}
+
+fn validate_commitments(
+    old_state: &InventoryState,
+    new_state: &InventoryState,
+    old_commitment: Fr,
+    new_commitment: Fr,
+) -> Result<(), ValidationError> {
+    if old_state.commitment() != old_commitment {
+        return Err(ValidationError::OldCommitmentMismatch);
+    }
+    if new_state.commitment() != new_commitment {
+        return Err(ValidationError::NewCommitmentMismatch);
+    }
+    Ok(())
+}
+
+fn check_quantity(
+    new_state: &InventoryState,
+    item_id: u64,
+    expected: u64,
+) -> Result<(), ValidationError> {
+    let actual = new_state.get_quantity(item_id);
+    if actual != expected {
+        return Err(ValidationError::QuantityMismatch {
+            item_id,
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+fn check_volume(new_state: &InventoryState, expected: u64) -> Result<(), ValidationError> {
+    if new_state.current_volume != expected {
+        return Err(ValidationError::VolumeMismatch {
+            expected,
+            actual: new_state.current_volume,
+        });
+    }
+    Ok(())
+}
+
+/// Every item other than `item_id` must hold the same quantity in both states.
+fn check_unrelated_items(
+    old_state: &InventoryState,
+    new_state: &InventoryState,
+    item_id: u64,
+) -> Result<(), ValidationError> {
+    let changed = old_state
+        .tree
+        .items()
+        .chain(new_state.tree.items())
+        .map(|(id, _)| id)
+        .filter(|&id| id != item_id)
+        .find(|&id| old_state.get_quantity(id) != new_state.get_quantity(id));
+
+    match changed {
+        Some(item_id) => Err(ValidationError::UnrelatedItemChanged { item_id }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn old_state() -> InventoryState {
+        let mut state = InventoryState::from_items(&[(1, 100), (2, 5)], Fr::from(111u64));
+        state.current_volume = 1050;
+        state
+    }
+
+    fn withdraw(state: &InventoryState) -> InventoryState {
+        state.withdraw(1, 30, 10, Fr::from(222u64)).unwrap().0
+    }
+
+    #[test]
+    fn test_validate_withdraw_valid() {
+        let old = old_state();
+        let new = withdraw(&old);
+
+        let result =
+            validate_withdraw_request(&old, &new, old.commitment(), new.commitment(), 1, 30, 10);
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_validate_old_commitment_mismatch() {
+        let old = old_state();
+        let new = withdraw(&old);
+
+        let result =
+            validate_withdraw_request(&old, &new, Fr::from(1u64), new.commitment(), 1, 30, 10);
+        assert_eq!(result, Err(ValidationError::OldCommitmentMismatch));
+    }
+
+    #[test]
+    fn test_validate_new_commitment_mismatch() {
+        let old = old_state();
+        let new = withdraw(&old);
+
+        let result =
+            validate_withdraw_request(&old, &new, old.commitment(), Fr::from(1u64), 1, 30, 10);
+        assert_eq!(result, Err(ValidationError::NewCommitmentMismatch));
+    }
+
+    #[test]
+    fn test_validate_insufficient_quantity() {
+        let old = old_state();
+        let new = withdraw(&old);
+
+        let result =
+            validate_withdraw_request(&old, &new, old.commitment(), new.commitment(), 1, 200, 10);
+        assert_eq!(
+            result,
+            Err(ValidationError::InsufficientQuantity { item_id: 1, have: 100, need: 200 })
+        );
+    }
+
+    #[test]
+    fn test_validate_wrong_new_quantity() {
+        let old = old_state();
+        let new = withdraw(&old);
+
+        // Claim a withdrawal of 20 while the new state reflects 30
+        let result =
+            validate_withdraw_request(&old, &new, old.commitment(), new.commitment(), 1, 20, 10);
+        assert_eq!(
+            result,
+            Err(ValidationError::QuantityMismatch { item_id: 1, expected: 80, actual: 70 })
+        );
+    }
+
+    #[test]
+    fn test_validate_unrelated_item_changed() {
+        let old = old_state();
+        let mut new = withdraw(&old);
+        new.tree.update(2, 6);
+
+        let result =
+            validate_withdraw_request(&old, &new, old.commitment(), new.commitment(), 1, 30, 10);
+        assert_eq!(result, Err(ValidationError::UnrelatedItemChanged { item_id: 2 }));
+    }
+
+    #[test]
+    fn test_validate_volume_mismatch() {
+        let old = old_state();
+        let mut new = withdraw(&old);
+        new.current_volume += 1;
+
+        let result =
+            validate_withdraw_request(&old, &new, old.commitment(), new.commitment(), 1, 30, 10);
+        assert_eq!(
+            result,
+            Err(ValidationError::VolumeMismatch { expected: 750, actual: 751 })
+        );
+    }
+
+    #[test]
+    fn test_validate_deposit_capacity_exceeded() {
+        let old = old_state();
+        let (new, _) = old.deposit(1, 10, 10, Fr::from(222u64)).unwrap();
+
+        let result = validate_deposit_request(
+            &old, &new, old.commitment(), new.commitment(), 1, 10, 10, 1100,
+        );
+        assert_eq!(
+            result,
+            Err(ValidationError::CapacityExceeded { volume: 1150, max_capacity: 1100 })
+        );
+
+        let result = validate_deposit_request(
+            &old, &new, old.commitment(), new.commitment(), 1, 10, 10, 2000,
+        );
+        assert_eq!(result, Ok(()));
+    }
+}
@@ -1,10 +1,23 @@
 //! Local proof verification for testing SMT-based circuits.
 
-use ark_bn254::{Bn254, Fr};
-use ark_groth16::{Groth16, Proof, VerifyingKey};
+use ark_bn254::{Bn254, Fr, G1Projective};
+use ark_ec::pairing::Pairing;
+use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_ff::{One, UniformRand, Zero};
+use ark_groth16::{Groth16, Proof};
 use ark_snark::SNARK;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
 use thiserror::Error;
 
+use inventory_circuits::{CapacitySMTCircuit, ItemExistsSMTCircuit, StateTransitionCircuit};
+
+use crate::error::ProverError;
+use crate::prove::ProofWithInputs;
+use crate::public_inputs::{
+    CapacityPublicInputs, ItemExistsPublicInputs, StateTransitionPublicInputs,
+};
+use crate::setup::TypedVerifyingKey;
+
 /// Errors during verification
 #[derive(Error, Debug)]
 pub enum VerifyError {
@@ -12,49 +25,158 @@ pub enum VerifyError {
     Verification(String),
     #[error("Invalid public inputs")]
     InvalidInputs,
+    #[error("Expected {expected} public inputs, got {actual}")]
+    PublicInputCount { expected: usize, actual: usize },
 }
 
-/// Verify a StateTransition proof (uses signal hash as single public input)
+/// Verify a StateTransition proof.
+///
+/// The epoch must be set exactly when the keys were set up with one.
 pub fn verify_state_transition(
-    vk: &VerifyingKey<Bn254>,
+    vk: &TypedVerifyingKey<StateTransitionCircuit>,
     proof: &Proof<Bn254>,
-    signal_hash: Fr,
-) -> Result<bool, VerifyError> {
-    let public_inputs = vec![signal_hash];
-
-    Groth16::<Bn254>::verify(vk, &public_inputs, proof)
-        .map_err(|e| VerifyError::Verification(e.to_string()))
+    public_inputs: &StateTransitionPublicInputs,
+) -> Result<bool, ProverError> {
+    verify_with_inputs(vk, proof, &public_inputs.to_vec())
 }
 
-/// Verify an ItemExists proof (uses public hash as single input)
+/// Verify an ItemExists proof against the statement it should prove.
 pub fn verify_item_exists(
-    vk: &VerifyingKey<Bn254>,
+    vk: &TypedVerifyingKey<ItemExistsSMTCircuit>,
     proof: &Proof<Bn254>,
-    public_hash: Fr,
-) -> Result<bool, VerifyError> {
-    let public_inputs = vec![public_hash];
+    public_inputs: &ItemExistsPublicInputs,
+) -> Result<bool, ProverError> {
+    verify_with_inputs(vk, proof, &public_inputs.to_vec())
+}
+
+/// Verify many ItemExists proofs made with the same key, one result per proof.
+///
+/// The key is prepared once and reused, so each proof costs only its own
+/// pairing check. Unlike [`batch_verify`], a bad proof does not hide which one
+/// it was: `result[i]` says whether `proofs[i]` proves its statement. Every
+/// statement's input count is checked against the key before any pairing.
+pub fn verify_item_exists_batch(
+    vk: &TypedVerifyingKey<ItemExistsSMTCircuit>,
+    proofs: &[(Proof<Bn254>, ItemExistsPublicInputs)],
+) -> Result<Vec<bool>, ProverError> {
+    let inputs: Vec<Vec<Fr>> = proofs.iter().map(|(_, inputs)| inputs.to_vec()).collect();
+    for public_inputs in &inputs {
+        check_input_count(vk, public_inputs)?;
+    }
+
+    let pvk = Groth16::<Bn254>::process_vk(vk)
+        .map_err(|e| VerifyError::Verification(e.to_string()))?;
 
-    Groth16::<Bn254>::verify(vk, &public_inputs, proof)
-        .map_err(|e| VerifyError::Verification(e.to_string()))
+    proofs
+        .iter()
+        .zip(&inputs)
+        .map(|((proof, _), public_inputs)| {
+            Groth16::<Bn254>::verify_with_processed_vk(&pvk, public_inputs, proof)
+                .map_err(|e| VerifyError::Verification(e.to_string()).into())
+        })
+        .collect()
 }
 
-/// Verify a Capacity proof (uses public hash as single input)
+/// Verify a Capacity proof against the statement it should prove.
 pub fn verify_capacity(
-    vk: &VerifyingKey<Bn254>,
+    vk: &TypedVerifyingKey<CapacitySMTCircuit>,
+    proof: &Proof<Bn254>,
+    public_inputs: &CapacityPublicInputs,
+) -> Result<bool, ProverError> {
+    verify_with_inputs(vk, proof, &public_inputs.to_vec())
+}
+
+/// Check the public input count against the key, then verify.
+fn verify_with_inputs<C>(
+    vk: &TypedVerifyingKey<C>,
     proof: &Proof<Bn254>,
-    public_hash: Fr,
-) -> Result<bool, VerifyError> {
-    let public_inputs = vec![public_hash];
+    public_inputs: &[Fr],
+) -> Result<bool, ProverError> {
+    check_input_count(vk, public_inputs)?;
 
-    Groth16::<Bn254>::verify(vk, &public_inputs, proof)
-        .map_err(|e| VerifyError::Verification(e.to_string()))
+    let valid = Groth16::<Bn254>::verify(vk, public_inputs, proof)
+        .map_err(|e| VerifyError::Verification(e.to_string()))?;
+    Ok(valid)
+}
+
+/// Reject public inputs whose count does not match the key.
+fn check_input_count<C>(
+    vk: &TypedVerifyingKey<C>,
+    public_inputs: &[Fr],
+) -> Result<(), ProverError> {
+    let expected = vk.num_public_inputs();
+    if public_inputs.len() != expected {
+        return Err(VerifyError::PublicInputCount {
+            expected,
+            actual: public_inputs.len(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Verify several proofs made with the same keys at once.
+///
+/// Checks a random linear combination of the Groth16 equations, so the cost is
+/// one multi-pairing of `proofs.len() + 3` pairs instead of a full verification
+/// per proof. Returns `Ok(true)` only if every proof is valid; it does not say
+/// which one failed, so callers that need that should verify individually.
+pub fn batch_verify<C>(
+    vk: &TypedVerifyingKey<C>,
+    proofs: &[ProofWithInputs],
+) -> Result<bool, ProverError> {
+    for proof in proofs {
+        check_input_count(vk, &proof.public_inputs)?;
+    }
+
+    // Each proof must satisfy e(A, B) = e(alpha, beta) * e(L, gamma) * e(C, delta),
+    // where L = gamma_abc[0] + sum(x_j * gamma_abc[j + 1]). Scaling proof i by a
+    // random r_i and multiplying, the whole batch holds iff
+    // prod e(r_i A_i, B_i) * e(-sum(r_i) alpha, beta) * e(-sum(r_i L_i), gamma)
+    //     * e(-sum(r_i C_i), delta) == 1
+    let mut rng = StdRng::from_entropy();
+    let mut g1 = Vec::with_capacity(proofs.len() + 3);
+    let mut g2 = Vec::with_capacity(proofs.len() + 3);
+    let mut r_sum = Fr::zero();
+    let mut l_sum = G1Projective::zero();
+    let mut c_sum = G1Projective::zero();
+
+    for proof in proofs {
+        let r = Fr::rand(&mut rng);
+        let mut scalars = vec![Fr::one()];
+        scalars.extend(&proof.public_inputs);
+        let l = G1Projective::msm(&vk.gamma_abc_g1, &scalars)
+            .map_err(|_| VerifyError::InvalidInputs)?;
+
+        g1.push((proof.proof.a * r).into_affine());
+        g2.push(proof.proof.b);
+        r_sum += r;
+        l_sum += l * r;
+        c_sum += proof.proof.c * r;
+    }
+
+    g1.push((-(vk.alpha_g1 * r_sum)).into_affine());
+    g2.push(vk.beta_g2);
+    g1.push((-l_sum).into_affine());
+    g2.push(vk.gamma_g2);
+    g1.push((-c_sum).into_affine());
+    g2.push(vk.delta_g2);
+
+    Ok(Bn254::multi_pairing(g1, g2).is_zero())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::prove::{prove_capacity, prove_item_exists, InventoryState};
-    use crate::setup::{setup_capacity, setup_item_exists};
+    use crate::prove::{
+        prove_capacity, prove_item_exists, prove_state_transition, prove_state_transition_at_epoch,
+        InventoryState,
+    };
+    use crate::setup::{
+        setup_capacity, setup_item_exists, setup_state_transition,
+        setup_state_transition_with_epoch,
+    };
+    use inventory_circuits::{signal::OpType, CircuitMeta};
     use ark_std::rand::{rngs::StdRng, SeedableRng};
 
     #[test]
@@ -71,13 +193,14 @@ mod tests {
         // Generate proof
         let proof_result = prove_item_exists(&keys.proving_key, &state, 42, 50).unwrap();
 
-        // Verify with correct public hash
-        let valid = verify_item_exists(
-            &keys.verifying_key,
-            &proof_result.proof,
-            proof_result.public_inputs[0],
-        )
-        .unwrap();
+        // Verify against the statement
+        let inputs = ItemExistsPublicInputs {
+            commitment: state.commitment(),
+            item_id: 42,
+            min_quantity: 50,
+        };
+        assert_eq!(inputs.to_vec(), proof_result.public_inputs);
+        let valid = verify_item_exists(&keys.verifying_key, &proof_result.proof, &inputs).unwrap();
 
         assert!(valid);
     }
@@ -96,13 +219,58 @@ mod tests {
         // Generate proof
         let proof_result = prove_item_exists(&keys.proving_key, &state, 42, 50).unwrap();
 
-        // Try to verify with wrong public hash
-        let wrong_hash = Fr::from(99999u64);
-        let valid = verify_item_exists(&keys.verifying_key, &proof_result.proof, wrong_hash).unwrap();
+        // Try to verify a stronger statement than was proven
+        let wrong_inputs = ItemExistsPublicInputs {
+            commitment: state.commitment(),
+            item_id: 42,
+            min_quantity: 100,
+        };
+        let valid =
+            verify_item_exists(&keys.verifying_key, &proof_result.proof, &wrong_inputs).unwrap();
 
         assert!(!valid);
     }
 
+    #[test]
+    fn test_verify_item_exists_batch() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_item_exists(&mut rng).unwrap();
+
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.tree.update(1, 100);
+        state.tree.update(2, 50);
+        state.current_volume = 500;
+
+        let statement = |item_id, min_quantity| ItemExistsPublicInputs {
+            commitment: state.commitment(),
+            item_id,
+            min_quantity,
+        };
+        let prove = |item_id, min_quantity| {
+            prove_item_exists(&keys.proving_key, &state, item_id, min_quantity).unwrap().proof
+        };
+
+        let proofs = vec![
+            (prove(1, 10), statement(1, 10)),
+            (prove(2, 50), statement(2, 50)),
+            // Claims more than was proven
+            (prove(1, 20), statement(1, 100)),
+            (prove(1, 100), statement(1, 100)),
+        ];
+        let results = verify_item_exists_batch(&keys.verifying_key, &proofs).unwrap();
+
+        assert_eq!(results, vec![true, true, false, true]);
+
+        // A key for a circuit with a different input count is refused up front
+        let other = setup_state_transition(&mut rng).unwrap().verifying_key.into_inner();
+        let wrong_key = TypedVerifyingKey::<ItemExistsSMTCircuit>::new(other);
+        let err = verify_item_exists_batch(&wrong_key, &proofs).unwrap_err();
+        assert!(matches!(
+            err,
+            ProverError::Verify(VerifyError::PublicInputCount { expected: 4, actual: 1 })
+        ));
+    }
+
     #[test]
     fn test_verify_capacity() {
         let mut rng = StdRng::seed_from_u64(42);
@@ -115,13 +283,134 @@ mod tests {
 
         let proof_result = prove_capacity(&keys.proving_key, &state, 1000).unwrap();
 
-        let valid = verify_capacity(
-            &keys.verifying_key,
-            &proof_result.proof,
-            proof_result.public_inputs[0],
+        let inputs = CapacityPublicInputs {
+            commitment: state.commitment(),
+            max_capacity: 1000,
+        };
+        let valid = verify_capacity(&keys.verifying_key, &proof_result.proof, &inputs).unwrap();
+
+        assert!(valid);
+    }
+
+    #[test]
+    fn test_verify_state_transition() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition(&mut rng).unwrap();
+
+        let state = InventoryState::new(Fr::from(12345u64));
+        let result = prove_state_transition(
+            &keys.proving_key,
+            &state,
+            Fr::from(67890u64),
+            1,
+            5,
+            10,
+            Fr::from(99999u64),
+            1000,
+            0,
+            Fr::from(12345678u64),
+            OpType::Deposit,
         )
         .unwrap();
 
-        assert!(valid);
+        let mut inputs = StateTransitionPublicInputs::from_signal(&result.signal, None);
+        assert_eq!(inputs.to_vec(), result.proof.public_inputs);
+        let valid = verify_state_transition(&keys.verifying_key, &result.proof.proof, &inputs);
+        assert!(valid.unwrap());
+
+        // An epoch for keys set up without one
+        inputs.epoch = Some(0);
+        let err = verify_state_transition(&keys.verifying_key, &result.proof.proof, &inputs)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ProverError::Verify(VerifyError::PublicInputCount { expected: 4, actual: 5 })
+        ));
+    }
+
+    #[test]
+    fn test_verify_state_transition_at_epoch() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_state_transition_with_epoch(&mut rng).unwrap();
+
+        let state = InventoryState::new(Fr::from(12345u64));
+        let result = prove_state_transition_at_epoch(
+            &keys.proving_key,
+            &state,
+            Fr::from(67890u64),
+            1,
+            5,
+            10,
+            Fr::from(99999u64),
+            1000,
+            0,
+            Fr::from(12345678u64),
+            OpType::Deposit,
+            7,
+        )
+        .unwrap();
+
+        let mut inputs =
+            StateTransitionPublicInputs::from_vec(&result.proof.public_inputs).unwrap();
+        assert_eq!(inputs.epoch, Some(7));
+        assert!(verify_state_transition(&keys.verifying_key, &result.proof.proof, &inputs).unwrap());
+
+        // The epoch is bound: relabelling the proof with a later epoch fails
+        inputs.epoch = Some(8);
+        assert!(!verify_state_transition(&keys.verifying_key, &result.proof.proof, &inputs).unwrap());
+    }
+
+    #[test]
+    fn test_key_input_counts_match_circuits() {
+        let mut rng = StdRng::seed_from_u64(42);
+
+        let keys = setup_state_transition(&mut rng).unwrap();
+        assert_eq!(
+            keys.verifying_key.num_public_inputs(),
+            StateTransitionCircuit::empty().num_public_inputs()
+        );
+
+        let keys = setup_item_exists(&mut rng).unwrap();
+        assert_eq!(
+            keys.verifying_key.num_public_inputs(),
+            ItemExistsSMTCircuit::empty().num_public_inputs()
+        );
+
+        let keys = setup_capacity(&mut rng).unwrap();
+        assert_eq!(
+            keys.verifying_key.num_public_inputs(),
+            CapacitySMTCircuit::empty().num_public_inputs()
+        );
+    }
+
+    #[test]
+    fn test_batch_verify() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let keys = setup_item_exists(&mut rng).unwrap();
+
+        let mut state = InventoryState::new(Fr::from(12345u64));
+        state.tree.update(1, 100);
+        state.tree.update(2, 30);
+        state.current_volume = 500;
+
+        let proofs = vec![
+            prove_item_exists(&keys.proving_key, &state, 1, 50).unwrap(),
+            prove_item_exists(&keys.proving_key, &state, 2, 10).unwrap(),
+            prove_item_exists(&keys.proving_key, &state, 1, 100).unwrap(),
+        ];
+        assert!(batch_verify(&keys.verifying_key, &proofs).unwrap());
+        assert!(batch_verify(&keys.verifying_key, &[]).unwrap());
+
+        // One bad proof fails the whole batch
+        let mut tampered = proofs.clone();
+        tampered[1].public_inputs[0] = Fr::from(99999u64);
+        assert!(!batch_verify(&keys.verifying_key, &tampered).unwrap());
+
+        // Proofs swapped between statements fail too
+        let mut swapped = proofs;
+        let first_proof = swapped[0].proof.clone();
+        swapped[0].proof = swapped[1].proof.clone();
+        swapped[1].proof = first_proof;
+        assert!(!batch_verify(&keys.verifying_key, &swapped).unwrap());
     }
 }
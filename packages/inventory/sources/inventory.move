@@ -28,7 +28,7 @@ module inventory::inventory {
     // ============ Structs ============
 
     /// A private inventory with hidden contents.
-    /// Commitment = Poseidon(inventory_root, current_volume, blinding)
+    /// Commitment = Poseidon(COMMITMENT_DOMAIN, inventory_root, current_volume, blinding)
     public struct PrivateInventory has key, store {
         id: UID,
         /// SMT-based commitment to inventory contents
@@ -61,7 +61,7 @@ export function Home() {
 inventory_root = SMT.root()  // Poseidon hash of item quantities
 
 // Commitment binds SMT root + volume + blinding
-commitment = Poseidon(inventory_root, current_volume, blinding)
+commitment = Poseidon(COMMITMENT_DOMAIN, inventory_root, current_volume, blinding)
 
 ON-CHAIN:  commitment, nonce, max_capacity
 OFF-CHAIN: SMT state, blinding factor (kept secret)`}